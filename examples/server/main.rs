@@ -2,7 +2,7 @@ use log::info;
 use registry::setup_registry;
 use specs::{Component, NullStorage};
 use voxelize::{
-    ChunkStage, FlatlandStage, LSystem, Server, Vec3, VoxelAccess, Voxelize, WorldConfig,
+    ChunkStage, FlatlandStage, LSystem, Server, Vec3, VoxelAccess, Voxelize, World, WorldConfig,
 };
 use worlds::{flat::setup_flat_world, terrain::setup_terrain_world, test::setup_test_world};
 
@@ -77,7 +77,7 @@ async fn main() -> std::io::Result<()> {
         info!("World creating...");
         let name: String = serde_json::from_value(value).expect("Can't understand name.");
         server
-            .create_world(&name, &WorldConfig::default())
+            .add_world(World::new(&name, &WorldConfig::default()))
             .expect("Could not create world.");
     });
 
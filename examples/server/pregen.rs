@@ -0,0 +1,64 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use registry::setup_registry;
+use voxelize::{PregenArgs, World};
+use worlds::terrain::setup_terrain_pipeline;
+
+mod registry;
+mod worlds;
+
+/// Pre-generate a world described by a world config file, with the stages of the terrain world,
+/// without starting a server. The world is named after the config file:
+///
+/// `cargo run --release --example pregen -- --config examples/server/terrain.toml --radius 32`
+fn main() {
+    let args = PregenArgs::from_env().unwrap_or_else(|e| {
+        eprintln!("{}\nUsage: pregen {}", e, PregenArgs::USAGE);
+        std::process::exit(1);
+    });
+
+    let config = args.world_config().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let name = args
+        .config
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("world")
+        .to_owned();
+
+    let registry = setup_registry();
+
+    let mut world = World::new(&name, &config);
+    setup_terrain_pipeline(&mut world);
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] [{bar:40.cyan/blue}] {msg} {pos}/{len} chunks ({eta})",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+    bar.set_message(world.name.clone());
+
+    let count = world
+        .pregenerate(&registry, &args.area, args.mesh, |done, total| {
+            bar.set_length(total as u64);
+            bar.set_position(done as u64);
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Could not pre-generate {:?}: {}", world.name, e);
+            std::process::exit(1);
+        });
+
+    bar.finish();
+
+    println!(
+        "Pre-generated {} chunks of {:?} into {:?}.",
+        count,
+        world.name,
+        world.config().save_dir
+    );
+}
@@ -151,18 +151,21 @@ pub fn setup_registry() -> Registry {
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(1, 0, 0),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(1, -1, 0),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                     ],
                 },
@@ -179,18 +182,21 @@ pub fn setup_registry() -> Registry {
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(-1, 0, 0),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(-1, -1, 0),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                     ],
                 },
@@ -207,18 +213,21 @@ pub fn setup_registry() -> Registry {
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(0, 0, 1),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(0, -1, 1),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                     ],
                 },
@@ -235,18 +244,21 @@ pub fn setup_registry() -> Registry {
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(0, 0, -1),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                         BlockRule::Simple(BlockSimpleRule {
                             offset: Vec3(0, -1, -1),
                             id: Some(green_stone_id),
                             rotation: None,
                             stage: None,
+                            state: None,
                         }),
                     ],
                 },
@@ -395,6 +407,7 @@ pub fn setup_registry() -> Registry {
                                     id: Some(0),
                                     rotation: None,
                                     stage: None,
+                                    state: None,
                                 }),
                                 // Add more BlockRule::Simple or BlockRule::Combination here as needed
                             ],
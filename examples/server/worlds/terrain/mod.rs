@@ -61,7 +61,7 @@ impl ChunkStage for TreeStage {
                             .generate(&tree_type, &Vec3(vx, height, vz))
                             .into_iter()
                             .for_each(|(Vec3(ux, uy, uz), id)| {
-                                chunk.set_voxel(ux, uy, uz, id);
+                                chunk.set_raw_voxel(ux, uy, uz, id);
                             });
                     }
                 }
@@ -150,6 +150,29 @@ pub fn setup_terrain_world() -> World {
 
     let mut world = World::new("terrain", &config);
 
+    setup_terrain_pipeline(&mut world);
+
+    world.ecs_mut().insert(KdTree::new());
+
+    setup_components(&mut world);
+    setup_entities(&mut world);
+    setup_dispatcher(&mut world);
+    setup_methods(&mut world);
+    setup_client(&mut world);
+
+    world.set_method_handle("time", |world, _, payload| {
+        let time_per_day = world.config().time_per_day as f32;
+        let new_time: TimeMethodPayload = serde_json::from_str(&payload).unwrap();
+        world.stats_mut().set_time(new_time.time % time_per_day);
+    });
+
+    world
+}
+
+/// Add the stages that generate the terrain world, seeded by the config of the world.
+pub fn setup_terrain_pipeline(world: &mut World) {
+    let config = WorldConfig::clone(&world.config());
+
     let mut terrain = Terrain::new(&config);
 
     // let fb0: Fbm<Perlin> = Fbm::new(config.seed)
@@ -441,20 +464,4 @@ pub fn setup_terrain_world() -> World {
 
         pipeline.add_stage(tree_stage);
     }
-
-    world.ecs_mut().insert(KdTree::new());
-
-    setup_components(&mut world);
-    setup_entities(&mut world);
-    setup_dispatcher(&mut world);
-    setup_methods(&mut world);
-    setup_client(&mut world);
-
-    world.set_method_handle("time", |world, _, payload| {
-        let time_per_day = world.config().time_per_day as f32;
-        let new_time: TimeMethodPayload = serde_json::from_str(&payload).unwrap();
-        world.stats_mut().set_time(new_time.time % time_per_day);
-    });
-
-    world
 }
@@ -68,7 +68,7 @@ onconnect = (e) => {
 
       if (message.chunks) {
         message.chunks.forEach((chunk) => {
-          ["lights", "voxels"].forEach((key) => {
+          ["lights", "voxels", "extras"].forEach((key) => {
             if (chunk[key]) {
               chunk[key] = new Uint32Array(chunk[key]);
               transferables.push(chunk[key].buffer);
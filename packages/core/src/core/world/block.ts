@@ -9,8 +9,24 @@ export type BlockSimpleRule = {
   id?: number;
   rotation?: BlockRotation;
   stage?: number;
+  state?: number;
 };
 
+export type BlockPropertyValue = boolean | number | string;
+
+/**
+ * A named property of a block with a finite set of values, the first of which is the default.
+ */
+export type BlockProperty = {
+  name: string;
+  values: BlockPropertyValue[];
+};
+
+/**
+ * The value of each property of a block, property name to value.
+ */
+export type BlockState = { [name: string]: BlockPropertyValue };
+
 export enum BlockRuleLogic {
   And = "and",
   Or = "or",
@@ -122,6 +138,10 @@ export type Block = {
     independent: boolean;
     isolated: boolean;
     range: UV;
+    /**
+     * The page of the server's texture atlas that `range` is on, if the server has one.
+     */
+    page: number;
     name: string;
   }[];
 
@@ -165,6 +185,16 @@ export type Block = {
   isolatedFaces: Set<string>;
 
   isEntity: boolean;
+
+  /**
+   * The tags of the block, such as "logs" or "replaceable", in lowercase.
+   */
+  tags: string[];
+
+  /**
+   * The named properties of the block, such as whether a door is open.
+   */
+  properties: BlockProperty[];
 };
 
 /**
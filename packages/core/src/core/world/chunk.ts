@@ -28,10 +28,11 @@ export class Chunk extends RawChunk {
       throw new Error("Chunk coords mismatch");
     }
 
-    const { voxels, lights } = data;
+    const { voxels, extras, lights } = data;
 
     if (lights && lights.byteLength) this.lights.data = lights;
     if (voxels && voxels.byteLength) this.voxels.data = voxels;
+    if (extras && extras.byteLength) this.extras.data = extras;
   }
 
   dispose() {
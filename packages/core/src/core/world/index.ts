@@ -53,6 +53,7 @@ import {
   Block,
   BlockDynamicPattern,
   BlockRotation,
+  BlockState,
   BlockUpdate,
   BlockUpdateWithSource,
   PY_ROTATION,
@@ -457,6 +458,15 @@ export class World<T = any> extends Scene implements NetIntercept {
 
   private textureLoaderLastMap: Record<string, Date> = {};
 
+  /**
+   * The sources last applied to block faces, `name::face` -> source. Applied again when the
+   * server reloads the registry and the block materials are rebuilt.
+   */
+  private blockTextureSources = new Map<
+    string,
+    string | Color | HTMLImageElement | Texture
+  >();
+
   private chunksTracker: [Coords2, number][] = [];
 
   private isTrackingChunks = false;
@@ -597,6 +607,13 @@ export class World<T = any> extends Scene implements NetIntercept {
     blockFaces.forEach((face) => {
       const id = `${face.name}::${block.id}`;
       this.textureLoaderLastMap[id] = now;
+
+      if (!face.isolated) {
+        this.blockTextureSources.set(
+          `${block.name.toLowerCase()}::${face.name}`,
+          source
+        );
+      }
     });
 
     // If it is a string, load the image.
@@ -1126,6 +1143,38 @@ export class World<T = any> extends Scene implements NetIntercept {
     return chunk.getVoxelStage(px, py, pz);
   }
 
+  /**
+   * Get the property values of a voxel by a 3D world position.
+   *
+   * @param px The x coordinate of the position.
+   * @param py The y coordinate of the position.
+   * @param pz The z coordinate of the position.
+   * @returns The block state at the given position, or null if its block has no properties.
+   */
+  getBlockStateAt(px: number, py: number, pz: number): BlockState | null {
+    this.checkIsInitialized("get block state", false);
+    const chunk = this.getChunkByPosition(px, py, pz);
+    if (chunk === undefined) return null;
+    const id = chunk.getVoxel(px, py, pz);
+    const states = this.registry.statesById.get(id);
+    return states?.[chunk.getVoxelState(px, py, pz)] ?? null;
+  }
+
+  /**
+   * Get a voxel state ID by a 3D world position.
+   *
+   * @param px The x coordinate of the position.
+   * @param py The y coordinate of the position.
+   * @param pz The z coordinate of the position.
+   * @returns The voxel state ID at the given position, or 0 if it does not exist.
+   */
+  getVoxelStateAt(px: number, py: number, pz: number) {
+    this.checkIsInitialized("get voxel state", false);
+    const chunk = this.getChunkByPosition(px, py, pz);
+    if (chunk === undefined) return 0;
+    return chunk.getVoxelState(px, py, pz);
+  }
+
   setVoxelStageAt(px: number, py: number, pz: number, stage: number) {
     this.checkIsInitialized("set voxel stage", false);
     const chunk = this.getChunkByPosition(px, py, pz);
@@ -1645,6 +1694,8 @@ export class World<T = any> extends Scene implements NetIntercept {
               this.getVoxelRotationAt(vx, vy, vz),
             getVoxelStageAt: (vx: number, vy: number, vz: number) =>
               this.getVoxelStageAt(vx, vy, vz),
+            getVoxelStateAt: (vx: number, vy: number, vz: number) =>
+              this.getVoxelStateAt(vx, vy, vz),
           }
         );
 
@@ -2203,11 +2254,46 @@ export class World<T = any> extends Scene implements NetIntercept {
       );
     }
 
-    const { blocks, options, stats } = this.initialData;
+    const { blocks, options, stats, states } = this.initialData;
 
     this._time = stats.time;
 
-    // Loading the registry
+    this.loadRegistry(blocks, states);
+
+    // Loading the options
+    this.options = {
+      ...this.options,
+      ...options,
+    };
+
+    this.physics.options = this.options;
+
+    await this.loadMaterials();
+
+    const registryData = this.registry.serialize();
+    this.meshWorkerPool.postMessage({ type: "init", registryData });
+
+    this.isInitialized = true;
+
+    this.renderRadius = this.options.defaultRenderRadius;
+
+    if (this.initialEntities) {
+      this.handleEntities(this.initialEntities);
+      this.initialEntities = null;
+    }
+  }
+
+  /**
+   * Populate the registry with the blocks and block states sent by the server, replacing any
+   * blocks it had before.
+   */
+  private loadRegistry(blocks: any, states: any) {
+    this.registry.blocksByName.clear();
+    this.registry.blocksById.clear();
+    this.registry.nameMap.clear();
+    this.registry.idMap.clear();
+    this.registry.statesById.clear();
+
     Object.keys(blocks).forEach((name) => {
       const block = blocks[name];
       const { id, aabbs, isDynamic } = block;
@@ -2247,27 +2333,41 @@ export class World<T = any> extends Scene implements NetIntercept {
       this.registry.idMap.set(id, lowerName);
     });
 
-    // Loading the options
-    this.options = {
-      ...this.options,
-      ...options,
-    };
+    Object.keys(states ?? {}).forEach((id) => {
+      this.registry.statesById.set(Number(id), states[id]);
+    });
+  }
 
-    this.physics.options = this.options;
+  /**
+   * Swap in the blocks of a registry the server reloaded, sent as a `vox-builtin:registry` event.
+   * The block materials are rebuilt with the textures applied so far, and the mesh workers are
+   * given the new registry. Animations from {@link applyBlockFrames} have to be applied again.
+   */
+  private async reloadRegistry({
+    blocks,
+    states,
+  }: {
+    blocks: any;
+    states: any;
+  }) {
+    this.loadRegistry(blocks, states);
 
     await this.loadMaterials();
 
     const registryData = this.registry.serialize();
     this.meshWorkerPool.postMessage({ type: "init", registryData });
 
-    this.isInitialized = true;
+    this.blockTextureSources.forEach((source, key) => {
+      const [name, faceName] = key.split("::");
+      const block = this.registry.blocksByName.get(name);
 
-    this.renderRadius = this.options.defaultRenderRadius;
+      if (!block || !block.faces.some((face) => face.name === faceName)) {
+        this.blockTextureSources.delete(key);
+        return;
+      }
 
-    if (this.initialEntities) {
-      this.handleEntities(this.initialEntities);
-      this.initialEntities = null;
-    }
+      this.applyBlockTexture(name, [faceName], source);
+    });
   }
 
   update(
@@ -2373,6 +2473,24 @@ export class World<T = any> extends Scene implements NetIntercept {
 
         break;
       }
+      case "EVENT": {
+        const { events } = message;
+
+        for (const event of events) {
+          if (event.name.toLowerCase() !== "vox-builtin:registry") {
+            continue;
+          }
+
+          if (this.isInitialized) {
+            this.reloadRegistry(event.payload);
+          } else if (this.initialData) {
+            // The reloaded blocks replace the ones this world will be initialized with.
+            this.initialData = { ...this.initialData, ...event.payload };
+          }
+        }
+
+        break;
+      }
       case "STATS": {
         const { json } = message;
 
@@ -2402,11 +2520,11 @@ export class World<T = any> extends Scene implements NetIntercept {
 
         // TODO: figure out how to do block cache
         updates.forEach((update) => {
-          const { vx, vy, vz, voxel } = update;
+          const { vx, vy, vz, voxel, extra } = update;
 
-          const type = BlockUtils.extractID(voxel);
+          const type = BlockUtils.extractID(voxel, extra);
           const rotation = BlockUtils.extractRotation(voxel);
-          const stage = BlockUtils.extractStage(voxel);
+          const stage = BlockUtils.extractStage(voxel, extra);
           const localRotation = this.getVoxelRotationAt(vx, vy, vz);
           const localStage = this.getVoxelStageAt(vx, vy, vz);
 
@@ -3520,9 +3638,16 @@ export class World<T = any> extends Scene implements NetIntercept {
           raw = BlockUtils.insertStage(raw, stage);
         }
 
+        let extra = BlockUtils.insertExtraID(0, type);
+
+        if (stage !== undefined) {
+          extra = BlockUtils.insertExtraStage(extra, stage);
+        }
+
         return {
           ...update,
           voxel: raw,
+          extra,
         };
       }),
     });
@@ -30,6 +30,11 @@ export class RawChunk {
 
   public voxels: NdArray<Uint32Array>;
 
+  /**
+   * The upper 32 bits of each voxel, empty unless the world uses the wide voxel encoding.
+   */
+  public extras: NdArray<Uint32Array>;
+
   public lights: NdArray<Uint32Array>;
 
   constructor(id: string, coords: Coords2, options: RawChunkOptions) {
@@ -42,6 +47,7 @@ export class RawChunk {
     const { size, maxHeight } = options;
 
     this.voxels = ndarray([] as any, [size, maxHeight, size]);
+    this.extras = ndarray([] as any, [size, maxHeight, size]);
     this.lights = ndarray([] as any, [size, maxHeight, size]);
 
     const [x, z] = coords;
@@ -57,15 +63,20 @@ export class RawChunk {
         x: this.coords[0],
         z: this.coords[1],
         voxels: this.voxels.data.buffer,
+        extras: this.extras.data.buffer,
         lights: this.lights.data.buffer,
         options: this.options,
       },
-      [this.voxels.data.buffer.slice(0), this.lights.data.buffer.slice(0)],
+      [
+        this.voxels.data.buffer.slice(0),
+        this.extras.data.buffer.slice(0),
+        this.lights.data.buffer.slice(0),
+      ],
     ];
   }
 
   static deserialize(data: any): RawChunk {
-    const { id, x, z, voxels, lights, options } = data;
+    const { id, x, z, voxels, extras, lights, options } = data;
 
     const chunk = new RawChunk(id, [x, z], options);
 
@@ -74,6 +85,8 @@ export class RawChunk {
       chunk.lights.data = new Uint32Array(lights);
     if (voxels && voxels.byteLength)
       chunk.voxels.data = new Uint32Array(voxels);
+    if (extras && extras.byteLength)
+      chunk.extras.data = new Uint32Array(extras);
 
     return chunk;
   }
@@ -89,10 +102,11 @@ export class RawChunk {
       throw new Error("Chunk coords mismatch");
     }
 
-    const { voxels, lights } = data;
+    const { voxels, extras, lights } = data;
 
     if (lights && lights.byteLength) this.lights.data = new Uint32Array(lights);
     if (voxels && voxels.byteLength) this.voxels.data = new Uint32Array(voxels);
+    if (extras && extras.byteLength) this.extras.data = new Uint32Array(extras);
   }
 
   /**
@@ -130,6 +144,41 @@ export class RawChunk {
     return this.voxels.set(lx, ly, lz, val);
   }
 
+  /**
+   * Get the extra value of a voxel, the upper 32 bits of voxels with the wide voxel encoding.
+   *
+   * @param vx The x voxel coordinate.
+   * @param vy The y voxel coordinate.
+   * @param vz The z voxel coordinate.
+   * @returns The extra value at the given voxel coordinate. If the voxel is not within
+   * the chunk or the world uses the packed voxel encoding, this method returns `0`.
+   */
+  getRawExtra(vx: number, vy: number, vz: number) {
+    if (!this.contains(vx, vy, vz) || this.extras.data.length === 0) {
+      return 0;
+    }
+
+    const [lx, ly, lz] = this.toLocal(vx, vy, vz);
+    return this.extras.get(lx, ly, lz);
+  }
+
+  /**
+   * Set the extra value of a voxel. Does nothing with the packed voxel encoding.
+   *
+   * Note: This method is purely client-side and does not affect the actual values on the server.
+   *
+   * @param vx The x voxel coordinate.
+   * @param vy The y voxel coordinate.
+   * @param vz The z voxel coordinate.
+   * @param value The extra value to set at the given voxel coordinate.
+   * @returns The extra value at the given voxel coordinate.
+   */
+  setRawExtra(vx: number, vy: number, vz: number, val: number) {
+    if (!this.contains(vx, vy, vz) || this.extras.data.length === 0) return 0;
+    const [lx, ly, lz] = this.toLocal(vx, vy, vz);
+    return this.extras.set(lx, ly, lz, val);
+  }
+
   /**
    * Get the raw light value at a given voxel coordinate.
    *
@@ -170,7 +219,14 @@ export class RawChunk {
    * @returns The voxel type ID at the given voxel coordinate.
    */
   getVoxel(vx: number, vy: number, vz: number) {
-    return BlockUtils.extractID(this.getRawValue(vx | 0, vy | 0, vz | 0));
+    vx |= 0;
+    vy |= 0;
+    vz |= 0;
+
+    return BlockUtils.extractID(
+      this.getRawValue(vx, vy, vz),
+      this.getRawExtra(vx, vy, vz)
+    );
   }
 
   /**
@@ -187,6 +243,7 @@ export class RawChunk {
   setVoxel(vx: number, vy: number, vz: number, id: number) {
     const value = BlockUtils.insertID(0, id);
     this.setRawValue(vx, vy, vz, value);
+    this.setRawExtra(vx, vy, vz, BlockUtils.insertExtraID(0, id));
     return id;
   }
 
@@ -236,7 +293,10 @@ export class RawChunk {
    */
   getVoxelStage(vx: number, vy: number, vz: number) {
     if (!this.contains(vx, vy, vz)) return 0;
-    return BlockUtils.extractStage(this.getRawValue(vx, vy, vz));
+    return BlockUtils.extractStage(
+      this.getRawValue(vx, vy, vz),
+      this.getRawExtra(vx, vy, vz)
+    );
   }
 
   /**
@@ -253,9 +313,31 @@ export class RawChunk {
   setVoxelStage(vx: number, vy: number, vz: number, stage: number) {
     const value = BlockUtils.insertStage(this.getRawValue(vx, vy, vz), stage);
     this.setRawValue(vx, vy, vz, value);
+    this.setRawExtra(
+      vx,
+      vy,
+      vz,
+      BlockUtils.insertExtraStage(this.getRawExtra(vx, vy, vz), stage)
+    );
     return stage;
   }
 
+  /**
+   * Get the block state ID at a given voxel coordinate.
+   *
+   * @param vx The x voxel coordinate.
+   * @param vy The y voxel coordinate.
+   * @param vz The z voxel coordinate.
+   * @returns The block state ID at the given voxel coordinate.
+   */
+  getVoxelState(vx: number, vy: number, vz: number) {
+    if (!this.contains(vx, vy, vz)) return 0;
+    return BlockUtils.extractState(
+      this.getRawValue(vx, vy, vz),
+      this.getRawExtra(vx, vy, vz)
+    );
+  }
+
   /**
    * Get the red light level at a given voxel coordinate.
    *
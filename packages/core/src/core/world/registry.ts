@@ -1,4 +1,4 @@
-import { Block } from ".";
+import { Block, BlockState } from ".";
 
 export class Registry {
   public blocksByName: Map<string, Block> = new Map();
@@ -9,6 +9,11 @@ export class Registry {
 
   public idMap: Map<number, string> = new Map();
 
+  /**
+   * The states of the blocks with properties, indexed by state ID.
+   */
+  public statesById: Map<number, BlockState[]> = new Map();
+
   /**
    * @hidden
    */
@@ -23,6 +28,7 @@ export class Registry {
         blocksById: Array.from(this.blocksById.entries()),
         nameMap: Array.from(this.nameMap.entries()),
         idMap: Array.from(this.idMap.entries()),
+        statesById: Array.from(this.statesById.entries()),
       })
     );
   }
@@ -33,6 +39,7 @@ export class Registry {
     registry.blocksById = new Map(data.blocksById);
     registry.nameMap = new Map(data.nameMap);
     registry.idMap = new Map(data.idMap);
+    registry.statesById = new Map(data.statesById);
     return registry;
   }
 }
@@ -244,6 +244,12 @@ onmessage = function (e) {
     return chunk?.getVoxelStage(vx, vy, vz) ?? 0;
   };
 
+  const getVoxelStateAt = (vx: number, vy: number, vz: number) => {
+    const coords = ChunkUtils.mapVoxelToChunk([vx, vy, vz], chunkSize);
+    const chunk = getChunkByCoords(coords);
+    return chunk?.getVoxelState(vx, vy, vz) ?? 0;
+  };
+
   const getBlockAt = (vx: number, vy: number, vz: number) => {
     const voxelId = getVoxelAt(vx, vy, vz);
     return registry.blocksById.get(voxelId);
@@ -307,6 +313,7 @@ onmessage = function (e) {
                   getVoxelAt,
                   getVoxelRotationAt,
                   getVoxelStageAt,
+                  getVoxelStateAt,
                 }
               );
 
@@ -12,6 +12,8 @@ import { LightColor } from "./light-utils";
 const ROTATION_MASK = 0xfff0ffff;
 const Y_ROTATION_MASK = 0xff0fffff;
 const STAGE_MASK = 0xf0ffffff;
+const STATE_MASK = 0x0fffffff;
+const EXTRA_STAGE_MASK = 0xf000ffff;
 
 /**
  * A utility class for extracting and inserting voxel data from and into numbers.
@@ -20,7 +22,13 @@ const STAGE_MASK = 0xf0ffffff;
  * - Voxel type: `0x0000ffff`
  * - Rotation: `0x000f0000`
  * - Y-rotation: `0x00f00000`
- * - Stage: `0xff000000`
+ * - Stage: `0x0f000000`
+ * - State: `0xf0000000`
+ *
+ * Worlds with the wide voxel encoding keep a second number per voxel, the extra, with:
+ * - Upper voxel type bits: `0x0000ffff`
+ * - Upper stage bits: `0x0fff0000`
+ * - Upper state bits: `0xf0000000`
  *
  * TODO-DOCS
  * For more information about voxel data, see [here](/)
@@ -38,10 +46,11 @@ export class BlockUtils {
    * Extract the voxel id from a number.
    *
    * @param voxel The voxel value to extract from.
+   * @param extra The extra value of the voxel, with the wide voxel encoding.
    * @returns The extracted voxel id.
    */
-  static extractID = (voxel: number) => {
-    return voxel & 0xffff;
+  static extractID = (voxel: number, extra = 0) => {
+    return ((voxel & 0xffff) | ((extra & 0xffff) << 16)) >>> 0;
   };
 
   /**
@@ -84,10 +93,11 @@ export class BlockUtils {
    * Extract the voxel stage from a number.
    *
    * @param voxel The voxel value to extract from.
+   * @param extra The extra value of the voxel, with the wide voxel encoding.
    * @returns The extracted voxel stage.
    */
-  static extractStage = (voxel: number) => {
-    return (voxel >> 24) & 0xf;
+  static extractStage = (voxel: number, extra = 0) => {
+    return ((voxel >> 24) & 0xf) | (((extra >>> 16) & 0xfff) << 4);
   };
 
   /**
@@ -98,7 +108,51 @@ export class BlockUtils {
    * @returns The inserted voxel value.
    */
   static insertStage = (voxel: number, stage: number) => {
-    return (voxel & STAGE_MASK) | (stage << 24);
+    return (voxel & STAGE_MASK) | ((stage & 0xf) << 24);
+  };
+
+  /**
+   * Insert the upper bits of a voxel id into the extra value of a voxel.
+   *
+   * @param extra The extra value to insert the id into.
+   * @param id The voxel id to insert.
+   * @returns The inserted extra value.
+   */
+  static insertExtraID = (extra: number, id: number) => {
+    return ((extra & 0xffff0000) | ((id >>> 16) & 0xffff)) >>> 0;
+  };
+
+  /**
+   * Insert the upper bits of a voxel stage into the extra value of a voxel.
+   *
+   * @param extra The extra value to insert the stage into.
+   * @param stage The voxel stage to insert.
+   * @returns The inserted extra value.
+   */
+  static insertExtraStage = (extra: number, stage: number) => {
+    return ((extra & EXTRA_STAGE_MASK) | (((stage >>> 4) & 0xfff) << 16)) >>> 0;
+  };
+
+  /**
+   * Extract the block state ID from a number.
+   *
+   * @param voxel The voxel value to extract from.
+   * @param extra The extra value of the voxel, with the wide voxel encoding.
+   * @returns The extracted block state ID.
+   */
+  static extractState = (voxel: number, extra = 0) => {
+    return ((voxel >>> 28) & 0xf) | (((extra >>> 28) & 0xf) << 4);
+  };
+
+  /**
+   * Insert a block state ID into a number.
+   *
+   * @param voxel The voxel value to insert the state into.
+   * @param state The block state ID to insert.
+   * @returns The inserted voxel value.
+   */
+  static insertState = (voxel: number, state: number) => {
+    return ((voxel & STATE_MASK) | (state << 28)) >>> 0;
   };
 
   static insertAll = (id: number, rotation?: BlockRotation, stage?: number) => {
@@ -133,6 +187,7 @@ export class BlockUtils {
       getVoxelAt: (x: number, y: number, z: number) => number;
       getVoxelRotationAt: (x: number, y: number, z: number) => BlockRotation;
       getVoxelStageAt: (x: number, y: number, z: number) => number;
+      getVoxelStateAt?: (x: number, y: number, z: number) => number;
     }
   ): boolean => {
     if (rule.type === "none") {
@@ -140,7 +195,7 @@ export class BlockUtils {
     }
 
     if (rule.type === "simple") {
-      const { offset, id, rotation, stage } = rule;
+      const { offset, id, rotation, stage, state } = rule;
       const [vx, vy, vz] = voxel;
       const ox = offset[0] + vx;
       const oy = offset[1] + vy;
@@ -165,6 +220,11 @@ export class BlockUtils {
         if (voxelStage !== stage) return false;
       }
 
+      if (state !== null && state !== undefined) {
+        const voxelState = functions.getVoxelStateAt?.(ox, oy, oz) ?? 0;
+        if (voxelState !== state) return false;
+      }
+
       // If all conditions pass, return true
       return true;
     }
@@ -20,6 +20,7 @@ export type ChunkProtocol = {
   meshes: MeshProtocol[];
   voxels: Uint32Array;
   lights: Uint32Array;
+  extras?: Uint32Array;
 };
 
 export type PeerProtocol<T> = {
@@ -53,6 +54,7 @@ export type UpdateProtocol = {
   vz: number;
   voxel?: number;
   light?: number;
+  extra?: number;
 };
 
 export type ChatProtocol = {
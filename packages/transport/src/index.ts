@@ -229,7 +229,7 @@ export class Transport extends WebSocket {
 
     if (message.chunks) {
       message.chunks.forEach((chunk) => {
-        ["lights", "voxels"].forEach((key) => {
+        ["lights", "voxels", "extras"].forEach((key) => {
           if (chunk[key]) {
             chunk[key] = new Uint32Array(chunk[key]);
           }
@@ -26,6 +26,12 @@ struct Config {
     serve: String,
 }
 
+/// The texture atlas of the registry, with its pages encoded as PNG images.
+struct Atlas {
+    manifest: String,
+    pages: Vec<Vec<u8>>,
+}
+
 /// Entry point for our websocket route
 async fn ws_route(
     req: HttpRequest,
@@ -91,6 +97,30 @@ async fn info(server: web::Data<Addr<Server>>) -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(info))
 }
 
+/// Manifest of the texture atlas, where each block texture is on the pages.
+async fn atlas_manifest(atlas: web::Data<Option<Atlas>>) -> HttpResponse {
+    match atlas.as_ref() {
+        Some(atlas) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(atlas.manifest.to_owned()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+/// A page of the texture atlas, as a PNG image.
+async fn atlas_page(atlas: web::Data<Option<Atlas>>, page: web::Path<usize>) -> HttpResponse {
+    match atlas
+        .as_ref()
+        .as_ref()
+        .and_then(|atlas| atlas.pages.get(page.into_inner()))
+    {
+        Some(page) => HttpResponse::Ok()
+            .content_type("image/png")
+            .body(page.to_owned()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
 pub struct Voxelize;
 
 impl Voxelize {
@@ -106,6 +136,19 @@ impl Voxelize {
         let serve = server.serve.to_owned();
         let secret = server.secret.to_owned();
 
+        let atlas = match server.registry.atlas() {
+            Some(atlas) => Some(Atlas {
+                manifest: serde_json::to_string(atlas)?,
+                pages: atlas
+                    .pages
+                    .iter()
+                    .map(|page| page.encode())
+                    .collect::<std::io::Result<_>>()?,
+            }),
+            None => None,
+        };
+        let atlas = web::Data::new(atlas);
+
         let server_addr = server.start();
 
         if serve.is_empty() {
@@ -124,9 +167,12 @@ impl Voxelize {
                 .app_data(web::Data::new(Config {
                     serve: serve.to_owned(),
                 }))
+                .app_data(atlas.clone())
                 .route("/", web::get().to(index))
                 .route("/ws/", web::get().to(ws_route))
-                .route("/info", web::get().to(info));
+                .route("/info", web::get().to(info))
+                .route("/atlas", web::get().to(atlas_manifest))
+                .route("/atlas/{page}", web::get().to(atlas_page));
 
             if serve.is_empty() {
                 app
@@ -20,7 +20,7 @@ use crate::{
     errors::AddWorldError,
     world::{Registry, World, WorldConfig},
     ChunkStatus, ClientJoinRequest, ClientLeaveRequest, ClientRequest, GetConfig, GetInfo, Mesher,
-    MessageQueue, Preload, Prepare, Stats, SyncWorld, Tick, TransportJoinRequest,
+    MessageQueue, Preload, Prepare, Stats, SyncWorld, TextureAtlas, Tick, TransportJoinRequest,
     TransportLeaveRequest,
 };
 
@@ -212,7 +212,7 @@ impl Server {
     /// started right away.
     pub fn add_world(&mut self, mut world: World) -> Result<&mut Addr<SyncWorld>, AddWorldError> {
         let name = world.name.clone();
-        let saving = world.config().saving;
+        let saving = world.config().saving && world.config().storage.is_on_disk();
         let save_dir = world.config().save_dir.clone();
         world.ecs_mut().insert(self.registry.clone());
 
@@ -640,6 +640,7 @@ pub struct ServerBuilder {
     interval: u64,
     secret: Option<String>,
     registry: Option<Registry>,
+    atlas: Option<TextureAtlas>,
 }
 
 impl ServerBuilder {
@@ -653,6 +654,7 @@ impl ServerBuilder {
             interval: DEFAULT_INTERVAL,
             secret: None,
             registry: None,
+            atlas: None,
         }
     }
 
@@ -699,9 +701,21 @@ impl ServerBuilder {
         self
     }
 
+    /// Configure the texture atlas that the UV ranges of block faces are taken from. The atlas is
+    /// served at `/atlas` as a manifest, and its pages at `/atlas/<page>` as PNG images.
+    pub fn atlas(mut self, atlas: &TextureAtlas) -> Self {
+        self.atlas = Some(atlas.to_owned());
+        self
+    }
+
     /// Instantiate a voxelize server instance.
     pub fn build(self) -> Server {
         let mut registry = self.registry.unwrap_or(Registry::new());
+
+        if let Some(atlas) = &self.atlas {
+            registry.set_atlas(atlas);
+        }
+
         registry.generate();
 
         if self.debug {
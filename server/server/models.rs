@@ -4,7 +4,7 @@ use actix::Message as ActixMessage;
 use libflate::zlib::Encoder;
 use prost::Message as ProstMesssage;
 
-use crate::libs::Ndarray;
+use crate::{libs::Ndarray, BlockUtils};
 
 /// Protocol buffers generated by `prost.rs`.
 pub mod protocols {
@@ -79,6 +79,9 @@ pub struct ChunkProtocol {
     pub meshes: Vec<MeshProtocol>,
     pub voxels: Option<Ndarray<u32>>,
     pub lights: Option<Ndarray<u32>>,
+
+    /// The upper 32 bits of each voxel, only sent with `VoxelEncoding::Wide`.
+    pub extras: Option<Ndarray<u32>>,
 }
 
 /// Protocol buffer compatible peer data structure.
@@ -95,7 +98,7 @@ pub struct UpdateProtocol {
     pub vx: i32,
     pub vy: i32,
     pub vz: i32,
-    pub voxel: u32,
+    pub voxel: u64,
     pub light: u32,
 }
 
@@ -271,6 +274,7 @@ impl MessageBuilder {
                         .collect(),
                     lights: chunk.lights.unwrap_or_default().data,
                     voxels: chunk.voxels.unwrap_or_default().data,
+                    extras: chunk.extras.unwrap_or_default().data,
                     x: chunk.x,
                     z: chunk.z,
                 })
@@ -280,12 +284,17 @@ impl MessageBuilder {
         if let Some(updates) = self.updates {
             message.updates = updates
                 .into_iter()
-                .map(|update| protocols::Update {
-                    vx: update.vx,
-                    vy: update.vy,
-                    vz: update.vz,
-                    light: update.light,
-                    voxel: update.voxel,
+                .map(|update| {
+                    let (voxel, extra) = BlockUtils::split(update.voxel);
+
+                    protocols::Update {
+                        vx: update.vx,
+                        vy: update.vy,
+                        vz: update.vz,
+                        light: update.light,
+                        voxel,
+                        extra,
+                    }
                 })
                 .collect()
         }
@@ -0,0 +1,499 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use hashbrown::HashMap;
+use log::warn;
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use serde::Serialize;
+
+use crate::UV;
+
+/// Name of the texture that block faces without a texture of their own are drawn with.
+pub const UNKNOWN_TEXTURE: &str = "unknown";
+
+/// An RGBA image with 8 bits per channel, such as a block texture or a page of a `TextureAtlas`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AtlasImage {
+    /// Width of the image in pixels.
+    pub width: u32,
+
+    /// Height of the image in pixels.
+    pub height: u32,
+
+    /// RGBA values of the pixels, row by row from the top.
+    #[serde(skip)]
+    pub pixels: Vec<u8>,
+}
+
+impl AtlasImage {
+    /// Create a transparent image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::filled(width, height, [0; 4])
+    }
+
+    /// Create an image of a single color.
+    pub fn filled(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    /// Decode a PNG image of any color type and bit depth.
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.set_transformations(Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            ColorType::Rgba => buffer,
+            ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|la| [la[0], la[0], la[0], la[1]])
+                .collect(),
+            ColorType::Grayscale => buffer.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+            ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Indexed PNG image was not expanded.",
+                ))
+            }
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Encode this image as a PNG image.
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+
+        let mut encoder = Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(bytes)
+    }
+
+    /// Get the color of a pixel.
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        self.pixels[index..index + 4].try_into().unwrap()
+    }
+
+    /// Set the color of a pixel.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        let index = ((y * self.width + x) * 4) as usize;
+        self.pixels[index..index + 4].copy_from_slice(&color);
+    }
+}
+
+/// Where a texture is on a `TextureAtlas`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlasEntry {
+    /// Index of the page that the texture is on.
+    pub page: usize,
+
+    /// Range of the texture on its page, without its gutter. For animated textures, this is the
+    /// range of the first frame.
+    pub range: UV,
+
+    /// Ranges of the frames of an animated texture, in the order of its strip. Empty if the
+    /// texture is not animated.
+    pub frames: Vec<UV>,
+
+    /// Milliseconds between the frames of an animated texture.
+    pub interval: u64,
+}
+
+/// Block textures packed into pages of images, so that block faces can share a texture. Textures
+/// are named `<block>::<face>`, and are looked up by block and face names, case-insensitively.
+/// Once set with `Registry::set_atlas`, the UV ranges of block faces are taken from this atlas.
+///
+/// Serialized, the atlas is the manifest of its pages and textures.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureAtlas {
+    /// Pages of this atlas. Pages are square, and only the last one can be smaller than the
+    /// maximum size.
+    pub pages: Vec<AtlasImage>,
+
+    /// Textures on this atlas, lowercase `<block>::<face>` -> entry.
+    pub textures: HashMap<String, AtlasEntry>,
+
+    /// A checkerboard texture for block faces without a texture.
+    pub unknown: AtlasEntry,
+}
+
+impl TextureAtlas {
+    /// Create a texture atlas builder.
+    pub fn new() -> TextureAtlasBuilder {
+        TextureAtlasBuilder::new()
+    }
+
+    /// Get the texture of a block face, or the unknown texture if it has none.
+    pub fn get(&self, block: &str, face: &str) -> &AtlasEntry {
+        self.textures
+            .get(&format!("{}::{}", block, face).to_lowercase())
+            .unwrap_or(&self.unknown)
+    }
+}
+
+/// A texture on its way onto a page, with its frames stacked in a strip.
+struct Tile {
+    name: String,
+    image: AtlasImage,
+    frames: u32,
+    frame_height: u32,
+    cell_width: u32,
+    cell_height: u32,
+}
+
+impl Tile {
+    fn height(&self) -> u32 {
+        self.cell_height * self.frames
+    }
+}
+
+const DEFAULT_MAX_SIZE: u32 = 4096;
+const DEFAULT_PADDING: u32 = 0;
+const DEFAULT_GUTTER: u32 = 4;
+const DEFAULT_MIP_LEVELS: u32 = 2;
+const DEFAULT_FRAME_INTERVAL: u64 = 100;
+const UNKNOWN_SIZE: u32 = 16;
+
+/// Builder for a texture atlas.
+pub struct TextureAtlasBuilder {
+    max_size: u32,
+    padding: u32,
+    gutter: u32,
+    mip_levels: u32,
+    frame_interval: u64,
+    dirs: Vec<PathBuf>,
+    textures: BTreeMap<String, AtlasImage>,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextureAtlasBuilder {
+    /// Create a new texture atlas builder.
+    pub fn new() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE,
+            padding: DEFAULT_PADDING,
+            gutter: DEFAULT_GUTTER,
+            mip_levels: DEFAULT_MIP_LEVELS,
+            frame_interval: DEFAULT_FRAME_INTERVAL,
+            dirs: vec![],
+            textures: BTreeMap::new(),
+        }
+    }
+
+    /// Configure the largest width and height of a page, in pixels.
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Configure the transparent pixels left between textures.
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Configure the pixels around each texture that repeat its edges, so that filtering does not
+    /// bleed neighboring textures in.
+    pub fn gutter(mut self, gutter: u32) -> Self {
+        self.gutter = gutter;
+        self
+    }
+
+    /// Configure how many mipmap levels the atlas should hold up for. Textures are placed on
+    /// multiples of `2^mip_levels` pixels, so that each level downsamples them on their own. Keep
+    /// the gutter at least `2^mip_levels` wide too.
+    pub fn mip_levels(mut self, mip_levels: u32) -> Self {
+        self.mip_levels = mip_levels;
+        self
+    }
+
+    /// Configure the milliseconds between the frames of animated textures.
+    pub fn frame_interval(mut self, frame_interval: u64) -> Self {
+        self.frame_interval = frame_interval;
+        self
+    }
+
+    /// Add the PNG textures of a folder, named `<block>::<face>.png`. Textures that are taller than
+    /// they are wide by a whole multiple are animated, with their frames stacked from the top.
+    pub fn dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.dirs.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Add a texture named `<block>::<face>`, replacing any texture of the same name.
+    pub fn texture(mut self, name: &str, image: &AtlasImage) -> Self {
+        self.textures.insert(name.to_lowercase(), image.to_owned());
+        self
+    }
+
+    /// Read the textures and pack them into pages.
+    pub fn build(self) -> io::Result<TextureAtlas> {
+        let mut textures = BTreeMap::new();
+
+        for dir in &self.dirs {
+            textures.extend(Self::read_dir(dir)?);
+        }
+
+        textures.extend(self.textures.clone());
+
+        let mut tiles = vec![self.tile(UNKNOWN_TEXTURE, Self::unknown_image())?];
+
+        for (name, image) in textures {
+            tiles.push(self.tile(&name, image)?);
+        }
+
+        // Shelves waste the least space with the tallest textures first.
+        tiles.sort_by(|a, b| {
+            b.height()
+                .cmp(&a.height())
+                .then(b.cell_width.cmp(&a.cell_width))
+                .then(a.name.cmp(&b.name))
+        });
+
+        let mut pages = vec![];
+        let mut entries = HashMap::new();
+
+        while !tiles.is_empty() {
+            let area = tiles
+                .iter()
+                .map(|tile| tile.cell_width as u64 * tile.height() as u64)
+                .sum::<u64>();
+            let largest = tiles
+                .iter()
+                .map(|tile| tile.cell_width.max(tile.height()))
+                .max()
+                .unwrap();
+
+            let mut size = ((area as f64).sqrt().ceil() as u32)
+                .max(largest)
+                .next_power_of_two()
+                .min(self.max_size);
+
+            let mut placed = Self::shelve(&tiles, size);
+
+            while placed.len() < tiles.len() && size < self.max_size {
+                size = (size * 2).min(self.max_size);
+                placed = Self::shelve(&tiles, size);
+            }
+
+            let mut page = AtlasImage::new(size, size);
+
+            for (tile, (x, y)) in tiles.iter().zip(&placed) {
+                let entry = self.draw(&mut page, pages.len(), tile, *x, *y);
+                entries.insert(tile.name.clone(), entry);
+            }
+
+            tiles.drain(..placed.len());
+            pages.push(page);
+        }
+
+        let unknown = entries.remove(UNKNOWN_TEXTURE).unwrap();
+
+        Ok(TextureAtlas {
+            pages,
+            textures: entries,
+            unknown,
+        })
+    }
+
+    fn read_dir(dir: &Path) -> io::Result<BTreeMap<String, AtlasImage>> {
+        let mut textures = BTreeMap::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            let is_png = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+
+            if !is_png {
+                continue;
+            }
+
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            if !name.contains("::") {
+                warn!(
+                    "Texture {:?} is not named <block>::<face>, skipping it.",
+                    path
+                );
+                continue;
+            }
+
+            let image = AtlasImage::decode(&fs::read(&path)?).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+            })?;
+
+            textures.insert(name, image);
+        }
+
+        Ok(textures)
+    }
+
+    fn unknown_image() -> AtlasImage {
+        let mut image = AtlasImage::new(UNKNOWN_SIZE, UNKNOWN_SIZE);
+        let half = UNKNOWN_SIZE / 2;
+
+        for y in 0..UNKNOWN_SIZE {
+            for x in 0..UNKNOWN_SIZE {
+                let color = if (x < half) == (y < half) {
+                    [255, 0, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                };
+
+                image.set_pixel(x, y, color);
+            }
+        }
+
+        image
+    }
+
+    fn tile(&self, name: &str, image: AtlasImage) -> io::Result<Tile> {
+        if image.width == 0 || image.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Texture {} is empty.", name),
+            ));
+        }
+
+        let frames = if image.height > image.width && image.height.is_multiple_of(image.width) {
+            image.height / image.width
+        } else {
+            1
+        };
+        let frame_height = image.height / frames;
+
+        let align = |length: u32| length.next_multiple_of(1 << self.mip_levels);
+
+        let tile = Tile {
+            name: name.to_owned(),
+            cell_width: align(image.width + self.gutter * 2 + self.padding),
+            cell_height: align(frame_height + self.gutter * 2 + self.padding),
+            image,
+            frames,
+            frame_height,
+        };
+
+        if tile.cell_width > self.max_size || tile.height() > self.max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Texture {} does not fit on a page of {} pixels.",
+                    name, self.max_size
+                ),
+            ));
+        }
+
+        Ok(tile)
+    }
+
+    /// Lay tiles out on shelves of a page, in order, until one doesn't fit.
+    fn shelve(tiles: &[Tile], size: u32) -> Vec<(u32, u32)> {
+        let mut placed = vec![];
+
+        let mut x = 0;
+        let mut y = 0;
+        let mut shelf = 0;
+
+        for tile in tiles {
+            if x + tile.cell_width > size {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+
+            if y + tile.height() > size {
+                break;
+            }
+
+            placed.push((x, y));
+
+            x += tile.cell_width;
+            shelf = shelf.max(tile.height());
+        }
+
+        placed
+    }
+
+    /// Draw each frame of a tile with its edges repeated into the gutter.
+    fn draw(&self, page: &mut AtlasImage, index: usize, tile: &Tile, x: u32, y: u32) -> AtlasEntry {
+        let gutter = self.gutter as i64;
+        let width = tile.image.width as i64;
+        let height = tile.frame_height as i64;
+        let size = page.width as f32;
+
+        let mut frames = vec![];
+
+        for frame in 0..tile.frames {
+            let start_x = x + self.gutter;
+            let start_y = y + frame * tile.cell_height + self.gutter;
+
+            for dy in -gutter..height + gutter {
+                for dx in -gutter..width + gutter {
+                    let color = tile.image.get_pixel(
+                        dx.clamp(0, width - 1) as u32,
+                        (frame as i64 * height + dy.clamp(0, height - 1)) as u32,
+                    );
+
+                    page.set_pixel(
+                        (start_x as i64 + dx) as u32,
+                        (start_y as i64 + dy) as u32,
+                        color,
+                    );
+                }
+            }
+
+            // V goes up from the bottom of the page.
+            frames.push(UV {
+                start_u: start_x as f32 / size,
+                end_u: (start_x + tile.image.width) as f32 / size,
+                start_v: 1.0 - (start_y + tile.frame_height) as f32 / size,
+                end_v: 1.0 - start_y as f32 / size,
+            });
+        }
+
+        AtlasEntry {
+            page: index,
+            range: frames[0].clone(),
+            frames: if tile.frames > 1 { frames } else { vec![] },
+            interval: self.frame_interval,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use specs::Entity;
 
 use crate::MetadataComp;
@@ -7,6 +7,9 @@ use crate::MetadataComp;
 pub struct Bookkeeping {
     //  id -> (etype, entity, metadata)
     pub(crate) entities: HashMap<String, (String, Entity, MetadataComp)>,
+
+    /// IDs of block entities unloaded along with their chunk, whose saved files are kept.
+    pub(crate) unloaded: HashSet<String>,
 }
 
 impl Bookkeeping {
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+/// The raw voxel value of a block that is falling as an entity.
+#[derive(Debug, Default, Component, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct FallingBlockComp(pub u64);
+
+impl FallingBlockComp {
+    /// Create a new component of the block this entity lands as.
+    pub fn new(raw: u64) -> Self {
+        Self(raw)
+    }
+}
@@ -5,6 +5,7 @@ mod collisions;
 mod current_chunk;
 mod direction;
 mod etype;
+mod falling_block;
 mod flags;
 mod id;
 mod interactor;
@@ -24,6 +25,7 @@ pub use collisions::*;
 pub use current_chunk::CurrentChunkComp;
 pub use direction::DirectionComp;
 pub use etype::ETypeComp;
+pub use falling_block::FallingBlockComp;
 pub use flags::*;
 pub use id::IDComp;
 pub use interactor::InteractorComp;
@@ -1,6 +1,16 @@
-use serde::Serialize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
-use super::generators::NoiseOptions;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    generators::NoiseOptions,
+    history::HISTORY_FOLDER,
+    storage::{StorageBackend, SNAPSHOTS_FOLDER},
+    voxels::VoxelEncoding,
+};
 
 /// World configuration, storing information of how a world is constructed.
 #[derive(Clone, Serialize)]
@@ -89,6 +99,9 @@ pub struct WorldConfig {
     /// Path to save all the saved chunks. Needs `save` to be true to be used.
     pub save_dir: String,
 
+    /// Which storage backend saved data goes to. Default is `StorageBackend::Directory`.
+    pub storage: StorageBackend,
+
     /// Saving interval.
     pub save_interval: usize,
 
@@ -97,6 +110,68 @@ pub struct WorldConfig {
 
     /// Whether entities should be saved. Only applies if `saving` is true.
     pub save_entities: bool,
+
+    /// Whether chunk lights should be saved, so that loaded chunks skip relighting. Only applies if `saving` is true.
+    pub save_lights: bool,
+
+    /// Whether chunk voxels and lights are kept palette compressed in memory. Default is false.
+    pub paletted_chunks: bool,
+
+    /// How many bits each voxel takes in chunks, the protocol and saved files. Default is
+    /// `VoxelEncoding::Packed`.
+    pub voxel_encoding: VoxelEncoding,
+
+    /// Name of the block that replaces saved blocks no longer in the registry. Default is "Air".
+    pub fallback_block: String,
+
+    /// Number of ticks a chunk can go without interested clients before it is saved and unloaded.
+    /// Zero disables unloading. Default is 0.
+    pub unload_chunks_after: u64,
+
+    /// Directory that world snapshots are kept in. Default is a `snapshots` folder inside `save_dir`.
+    pub snapshot_dir: String,
+
+    /// Number of random voxels in each sub chunk near clients that get a random tick every tick.
+    /// Zero disables random ticks. Default is 3.
+    pub random_tick_speed: usize,
+
+    /// Radius in chunks around each client within which chunks get random ticks. Default is 8 chunks.
+    pub random_tick_radius: usize,
+
+    /// Maximum fluid voxels in a chunk that flow each tick, the rest wait for the next tick. Default is 64.
+    pub max_fluid_ticks_per_chunk: usize,
+
+    /// Maximum `Block::on_neighbor_changed` calls each tick, the rest wait for the next tick. Default is 256.
+    pub max_neighbor_updates_per_tick: usize,
+
+    /// Longest chain of neighbor changes caused by `Block::on_neighbor_changed` updates, after which
+    /// changes stop notifying their neighbors. Default is 64.
+    pub max_neighbor_update_depth: u32,
+
+    /// Number of world edits each client can undo. Default is 32.
+    pub max_edit_history: usize,
+
+    /// Furthest a client can update voxels from, measured from its position to the voxel's center.
+    /// Zero disables the check. Default is 0.
+    pub max_update_reach: f32,
+
+    /// Maximum voxel updates a client can send per second. Zero disables the check. Default is 0.
+    pub max_updates_per_second: usize,
+
+    /// Block IDs that clients can update voxels to, which needs to include air for breaking blocks.
+    /// Empty allows every block. Default is empty.
+    pub allowed_update_blocks: Vec<u32>,
+
+    /// Whether every applied voxel change is recorded with its source, see `History`. Default is false.
+    pub record_history: bool,
+
+    /// Directory that the voxel history is kept in. Default is a `history` folder inside `save_dir`,
+    /// or in memory if the world is not saving.
+    pub history_dir: String,
+
+    /// Most voxel changes kept per chunk when the history is kept in memory, dropping the oldest.
+    /// Default is 1024.
+    pub max_memory_history: usize,
 }
 
 impl Default for WorldConfig {
@@ -145,8 +220,22 @@ const DEFAULT_SAVING: bool = false;
 const DEFAULT_SAVE_DIR: &str = "";
 const DEFAULT_SAVE_INTERVAL: usize = 300;
 const DEFAULT_COMMAND_SYMBOL: &str = "/";
-
-/// Builder for a world configuration.
+const DEFAULT_FALLBACK_BLOCK: &str = "Air";
+const DEFAULT_UNLOAD_CHUNKS_AFTER: u64 = 0;
+const DEFAULT_RANDOM_TICK_SPEED: usize = 3;
+const DEFAULT_RANDOM_TICK_RADIUS: usize = 8;
+const DEFAULT_MAX_FLUID_TICKS_PER_CHUNK: usize = 64;
+const DEFAULT_MAX_NEIGHBOR_UPDATES_PER_TICK: usize = 256;
+const DEFAULT_MAX_NEIGHBOR_UPDATE_DEPTH: u32 = 64;
+const DEFAULT_MAX_EDIT_HISTORY: usize = 32;
+const DEFAULT_MAX_UPDATE_REACH: f32 = 0.0;
+const DEFAULT_MAX_UPDATES_PER_SECOND: usize = 0;
+const DEFAULT_RECORD_HISTORY: bool = false;
+const DEFAULT_MAX_MEMORY_HISTORY: usize = 1024;
+
+/// Builder for a world configuration. Can also be read from a world config file, see `from_file`.
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct WorldConfigBuilder {
     max_clients: usize,
     chunk_size: usize,
@@ -176,9 +265,34 @@ pub struct WorldConfigBuilder {
     terrain: NoiseOptions,
     saving: bool,
     save_dir: String,
+    storage: StorageBackend,
     save_interval: usize,
     command_symbol: String,
     save_entities: bool,
+    save_lights: bool,
+    paletted_chunks: bool,
+    voxel_encoding: VoxelEncoding,
+    fallback_block: String,
+    unload_chunks_after: u64,
+    snapshot_dir: String,
+    random_tick_speed: usize,
+    random_tick_radius: usize,
+    max_fluid_ticks_per_chunk: usize,
+    max_neighbor_updates_per_tick: usize,
+    max_neighbor_update_depth: u32,
+    max_edit_history: usize,
+    max_update_reach: f32,
+    max_updates_per_second: usize,
+    allowed_update_blocks: Vec<u32>,
+    record_history: bool,
+    history_dir: String,
+    max_memory_history: usize,
+}
+
+impl Default for WorldConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WorldConfigBuilder {
@@ -212,13 +326,56 @@ impl WorldConfigBuilder {
             client_collision_repulsion: DEFAULT_CLIENT_COLLISION_REPULSION,
             saving: DEFAULT_SAVING,
             save_dir: DEFAULT_SAVE_DIR.to_owned(),
+            storage: StorageBackend::default(),
             save_interval: DEFAULT_SAVE_INTERVAL,
             terrain: NoiseOptions::default(),
             command_symbol: DEFAULT_COMMAND_SYMBOL.to_owned(),
             save_entities: true,
+            save_lights: true,
+            paletted_chunks: false,
+            voxel_encoding: VoxelEncoding::default(),
+            fallback_block: DEFAULT_FALLBACK_BLOCK.to_owned(),
+            unload_chunks_after: DEFAULT_UNLOAD_CHUNKS_AFTER,
+            snapshot_dir: String::new(),
+            random_tick_speed: DEFAULT_RANDOM_TICK_SPEED,
+            random_tick_radius: DEFAULT_RANDOM_TICK_RADIUS,
+            max_fluid_ticks_per_chunk: DEFAULT_MAX_FLUID_TICKS_PER_CHUNK,
+            max_neighbor_updates_per_tick: DEFAULT_MAX_NEIGHBOR_UPDATES_PER_TICK,
+            max_neighbor_update_depth: DEFAULT_MAX_NEIGHBOR_UPDATE_DEPTH,
+            max_edit_history: DEFAULT_MAX_EDIT_HISTORY,
+            max_update_reach: DEFAULT_MAX_UPDATE_REACH,
+            max_updates_per_second: DEFAULT_MAX_UPDATES_PER_SECOND,
+            allowed_update_blocks: vec![],
+            record_history: DEFAULT_RECORD_HISTORY,
+            history_dir: String::new(),
+            max_memory_history: DEFAULT_MAX_MEMORY_HISTORY,
         }
     }
 
+    /// Read a world config file, parsed as TOML if it ends with `.toml` and as JSON otherwise. The
+    /// options are named like the fields of `WorldConfig` in camel case, and the ones left out keep
+    /// their defaults.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)?;
+
+        let parsed: Result<Self, String> = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&data).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_str(&data).map_err(|e| e.to_string())
+        };
+
+        let builder = parsed.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not parse world config file {:?}: {}", path, e),
+            )
+        })?;
+
+        let save_dir = builder.save_dir.clone();
+        Ok(builder.save_dir(&save_dir))
+    }
+
     /// Configure the maximum clients allowed for this world. Defaults is 100 clients.
     pub fn max_clients(mut self, max_clients: usize) -> Self {
         self.max_clients = max_clients;
@@ -364,6 +521,12 @@ impl WorldConfigBuilder {
         self
     }
 
+    /// Configure the storage backend saved data goes to. Default is `StorageBackend::Directory`.
+    pub fn storage(mut self, storage: StorageBackend) -> Self {
+        self.storage = storage;
+        self
+    }
+
     /// Configure the saving interval of the world.
     pub fn save_interval(mut self, save_interval: usize) -> Self {
         self.save_interval = save_interval.to_owned();
@@ -382,6 +545,135 @@ impl WorldConfigBuilder {
         self
     }
 
+    /// Configure whether chunk lights should be saved. Only applies if `saving` is true.
+    pub fn save_lights(mut self, save_lights: bool) -> Self {
+        self.save_lights = save_lights;
+        self
+    }
+
+    /// Configure whether chunk voxels and lights are kept palette compressed in memory, trading a bit
+    /// of CPU for a much smaller memory footprint per loaded chunk. Default is false.
+    pub fn paletted_chunks(mut self, paletted_chunks: bool) -> Self {
+        self.paletted_chunks = paletted_chunks;
+        self
+    }
+
+    /// Configure how many bits each voxel takes. `VoxelEncoding::Wide` doubles the memory of chunk
+    /// voxels for 32-bit block IDs and 16-bit stages. Default is `VoxelEncoding::Packed`.
+    pub fn voxel_encoding(mut self, voxel_encoding: VoxelEncoding) -> Self {
+        self.voxel_encoding = voxel_encoding;
+        self
+    }
+
+    /// Configure the block that replaces saved blocks no longer in the registry when chunks load. Default is "Air".
+    pub fn fallback_block(mut self, fallback_block: &str) -> Self {
+        self.fallback_block = fallback_block.to_owned();
+        self
+    }
+
+    /// Configure how many ticks a chunk can go without interested clients before it is saved (if
+    /// `saving` is on) and unloaded from memory. Zero disables unloading. Default is 0.
+    pub fn unload_chunks_after(mut self, unload_chunks_after: u64) -> Self {
+        self.unload_chunks_after = unload_chunks_after;
+        self
+    }
+
+    /// Configure the directory that world snapshots are kept in. Default is a `snapshots` folder
+    /// inside `save_dir`.
+    pub fn snapshot_dir(mut self, snapshot_dir: &str) -> Self {
+        if cfg!(target_os = "windows") {
+            self.snapshot_dir = snapshot_dir.replace("/", "\\");
+        } else {
+            self.snapshot_dir = snapshot_dir.to_owned();
+        }
+        self
+    }
+
+    /// Configure how many random voxels in each sub chunk near clients get a random tick every
+    /// tick. Zero disables random ticks. Default is 3.
+    pub fn random_tick_speed(mut self, random_tick_speed: usize) -> Self {
+        self.random_tick_speed = random_tick_speed;
+        self
+    }
+
+    /// Configure the radius in chunks around each client within which chunks get random ticks.
+    /// Default is 8 chunks.
+    pub fn random_tick_radius(mut self, random_tick_radius: usize) -> Self {
+        self.random_tick_radius = random_tick_radius;
+        self
+    }
+
+    /// Configure the maximum fluid voxels in a chunk that flow each tick, so that fluids do not
+    /// flood the update queue with more than lighting and meshing can keep up with. Default is 64.
+    pub fn max_fluid_ticks_per_chunk(mut self, max_fluid_ticks_per_chunk: usize) -> Self {
+        self.max_fluid_ticks_per_chunk = max_fluid_ticks_per_chunk;
+        self
+    }
+
+    /// Configure the maximum `Block::on_neighbor_changed` calls each tick. Default is 256.
+    pub fn max_neighbor_updates_per_tick(mut self, max_neighbor_updates_per_tick: usize) -> Self {
+        self.max_neighbor_updates_per_tick = max_neighbor_updates_per_tick;
+        self
+    }
+
+    /// Configure the longest chain of neighbor changes caused by `Block::on_neighbor_changed`
+    /// updates, which keeps blocks that keep changing each other from looping forever. Default is 64.
+    pub fn max_neighbor_update_depth(mut self, max_neighbor_update_depth: u32) -> Self {
+        self.max_neighbor_update_depth = max_neighbor_update_depth;
+        self
+    }
+
+    /// Configure the number of world edits each client can undo. Default is 32.
+    pub fn max_edit_history(mut self, max_edit_history: usize) -> Self {
+        self.max_edit_history = max_edit_history;
+        self
+    }
+
+    /// Configure how far from its position a client can update voxels. Zero disables the check.
+    /// Default is 0.
+    pub fn max_update_reach(mut self, max_update_reach: f32) -> Self {
+        self.max_update_reach = max_update_reach;
+        self
+    }
+
+    /// Configure the maximum voxel updates a client can send per second. Zero disables the check.
+    /// Default is 0.
+    pub fn max_updates_per_second(mut self, max_updates_per_second: usize) -> Self {
+        self.max_updates_per_second = max_updates_per_second;
+        self
+    }
+
+    /// Configure the block IDs that clients can update voxels to. Include air to let clients break
+    /// blocks. Empty allows every block, which is the default.
+    pub fn allowed_update_blocks(mut self, allowed_update_blocks: &[u32]) -> Self {
+        self.allowed_update_blocks = allowed_update_blocks.to_vec();
+        self
+    }
+
+    /// Configure whether every applied voxel change is recorded with its source. Default is false.
+    pub fn record_history(mut self, record_history: bool) -> Self {
+        self.record_history = record_history;
+        self
+    }
+
+    /// Configure the directory that the voxel history is kept in. Default is a `history` folder
+    /// inside `save_dir`, or in memory if the world is not saving.
+    pub fn history_dir(mut self, history_dir: &str) -> Self {
+        if cfg!(target_os = "windows") {
+            self.history_dir = history_dir.replace("/", "\\");
+        } else {
+            self.history_dir = history_dir.to_owned();
+        }
+        self
+    }
+
+    /// Configure the most voxel changes kept per chunk when the history is kept in memory, dropping
+    /// the oldest. Default is 1024.
+    pub fn max_memory_history(mut self, max_memory_history: usize) -> Self {
+        self.max_memory_history = max_memory_history;
+        self
+    }
+
     /// Create a world configuration.
     pub fn build(self) -> WorldConfig {
         // Make sure there are still chunks in the world.
@@ -389,14 +681,30 @@ impl WorldConfigBuilder {
             panic!("Min/max chunk options do not make sense.");
         }
 
-        if self.max_height % self.sub_chunks != 0 {
-            panic!("Max height should be divisible by sub-chunks.");
+        if self.sub_chunks == 0 || self.max_height % self.sub_chunks != 0 {
+            panic!("Max height should be divisible by a non-zero number of sub-chunks.");
         }
 
         if !self.saving && !self.save_dir.is_empty() {
             panic!("Save directory shouldn't be used unless `config.save` is set to true!");
         }
 
+        let snapshot_dir = if self.snapshot_dir.is_empty() && self.saving {
+            let mut path = PathBuf::from(&self.save_dir);
+            path.push(SNAPSHOTS_FOLDER);
+            path.to_string_lossy().into_owned()
+        } else {
+            self.snapshot_dir
+        };
+
+        let history_dir = if self.history_dir.is_empty() && self.saving {
+            let mut path = PathBuf::from(&self.save_dir);
+            path.push(HISTORY_FOLDER);
+            path.to_string_lossy().into_owned()
+        } else {
+            self.history_dir
+        };
+
         WorldConfig {
             max_clients: self.max_clients,
             chunk_size: self.chunk_size,
@@ -426,9 +734,28 @@ impl WorldConfigBuilder {
             terrain: self.terrain,
             saving: self.saving,
             save_dir: self.save_dir,
+            storage: self.storage,
             save_interval: self.save_interval,
             command_symbol: self.command_symbol,
             save_entities: self.save_entities,
+            save_lights: self.save_lights,
+            paletted_chunks: self.paletted_chunks,
+            voxel_encoding: self.voxel_encoding,
+            fallback_block: self.fallback_block,
+            unload_chunks_after: self.unload_chunks_after,
+            snapshot_dir,
+            random_tick_speed: self.random_tick_speed,
+            random_tick_radius: self.random_tick_radius,
+            max_fluid_ticks_per_chunk: self.max_fluid_ticks_per_chunk,
+            max_neighbor_updates_per_tick: self.max_neighbor_updates_per_tick,
+            max_neighbor_update_depth: self.max_neighbor_update_depth,
+            max_edit_history: self.max_edit_history,
+            max_update_reach: self.max_update_reach,
+            max_updates_per_second: self.max_updates_per_second,
+            allowed_update_blocks: self.allowed_update_blocks,
+            record_history: self.record_history,
+            history_dir,
+            max_memory_history: self.max_memory_history,
         }
     }
 }
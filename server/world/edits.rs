@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+
+use crate::{Chunks, PasteOptions, Registry, Schematic, Vec3, VoxelAccess, VoxelUpdate, World};
+
+/// Name of the event that tells a client how far along one of its edits is.
+pub const EDIT_PROGRESS_EVENT: &str = "vox-builtin:edit-progress";
+
+/// A voxel changed by a world edit, as the voxel, its raw value before and its raw value after.
+pub type VoxelChange = (Vec3<i32>, u64, u64);
+
+/// What a queued world edit does to the history of its client once it is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// A new edit, which can be undone and clears what could be redone.
+    Edit,
+
+    /// Undoing an edit, which can then be redone.
+    Undo,
+
+    /// Redoing an undone edit, which can then be undone again.
+    Redo,
+}
+
+/// A world edit waiting to be applied, a few voxel updates at a time.
+pub struct EditJob {
+    /// ID of this edit, to ask `Edits::progress` about.
+    pub id: usize,
+
+    /// The client this edit is recorded for.
+    pub client_id: String,
+
+    /// What this edit does to the history of its client once it is done.
+    pub kind: EditKind,
+
+    /// The voxel updates that make up this edit.
+    pub updates: Vec<VoxelUpdate>,
+
+    /// Number of `updates` applied so far.
+    pub applied: usize,
+
+    /// Number of `updates` skipped so far, for being outside of the world's height or in a chunk
+    /// that was not ready.
+    pub skipped: usize,
+
+    /// The voxels this edit changed so far.
+    pub changes: Vec<VoxelChange>,
+}
+
+/// The edits a client can undo and redo, latest last.
+#[derive(Debug, Default, Clone)]
+pub struct EditHistory {
+    /// The changes of each finished edit, which undoing rolls back.
+    pub undo: Vec<Vec<VoxelChange>>,
+
+    /// The changes of each undone edit, which redoing applies again.
+    pub redo: Vec<Vec<VoxelChange>>,
+}
+
+/// Bulk world edits, along with the undo/redo history and clipboard of each client. Edits are queued
+/// here and fed into the voxel update queue by `EditsSystem`, without going over
+/// `config.max_updates_per_tick`.
+#[derive(Default)]
+pub struct Edits {
+    /// Edits waiting to be applied, in order.
+    pub(crate) jobs: VecDeque<EditJob>,
+
+    /// History of each client, client ID -> history.
+    histories: HashMap<String, EditHistory>,
+
+    /// Region each client last copied, client ID -> schematic.
+    clipboards: HashMap<String, Schematic>,
+
+    next_id: usize,
+
+    max_history: usize,
+}
+
+impl Edits {
+    /// Create an edit queue that remembers up to `max_history` edits per client.
+    pub fn new(max_history: usize) -> Self {
+        Self {
+            max_history,
+            ..Default::default()
+        }
+    }
+
+    /// Queue voxel updates as an edit of a client. Returns the ID of the edit.
+    pub fn queue(&mut self, client_id: &str, kind: EditKind, updates: Vec<VoxelUpdate>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.jobs.push_back(EditJob {
+            id,
+            client_id: client_id.to_owned(),
+            kind,
+            updates,
+            applied: 0,
+            skipped: 0,
+            changes: vec![],
+        });
+
+        id
+    }
+
+    /// How far along a queued edit is, as the number of voxel updates applied and the total number
+    /// of updates. Skipped updates are not counted as applied. `None` once the edit is done.
+    pub fn progress(&self, id: usize) -> Option<(usize, usize)> {
+        self.jobs
+            .iter()
+            .find(|job| job.id == id)
+            .map(|job| (job.applied, job.updates.len()))
+    }
+
+    /// The undo/redo history of a client.
+    pub fn history(&self, client_id: &str) -> Option<&EditHistory> {
+        self.histories.get(client_id)
+    }
+
+    /// The region a client last copied.
+    pub fn clipboard(&self, client_id: &str) -> Option<&Schematic> {
+        self.clipboards.get(client_id)
+    }
+
+    /// Replace the clipboard of a client.
+    pub fn set_clipboard(&mut self, client_id: &str, schematic: Schematic) {
+        self.clipboards.insert(client_id.to_owned(), schematic);
+    }
+
+    /// Queue undoing the latest finished edit of a client. Returns the ID of the queued edit, or
+    /// `None` if there is nothing to undo.
+    pub fn undo(&mut self, client_id: &str) -> Option<usize> {
+        let changes = self.histories.get_mut(client_id)?.undo.pop()?;
+        Some(self.queue(client_id, EditKind::Undo, Self::revert(&changes)))
+    }
+
+    /// Queue redoing the latest undone edit of a client. Returns the ID of the queued edit, or
+    /// `None` if there is nothing to redo.
+    pub fn redo(&mut self, client_id: &str) -> Option<usize> {
+        let changes = self.histories.get_mut(client_id)?.redo.pop()?;
+        Some(self.queue(client_id, EditKind::Redo, Self::revert(&changes)))
+    }
+
+    /// Record a finished edit in the history of its client.
+    pub(crate) fn finish(&mut self, job: EditJob) {
+        if job.changes.is_empty() {
+            return;
+        }
+
+        let history = self.histories.entry(job.client_id).or_default();
+
+        let stack = match job.kind {
+            EditKind::Edit => {
+                history.redo.clear();
+                &mut history.undo
+            }
+            EditKind::Undo => &mut history.redo,
+            EditKind::Redo => &mut history.undo,
+        };
+
+        stack.push(job.changes);
+
+        if stack.len() > self.max_history {
+            stack.remove(0);
+        }
+    }
+
+    /// The voxel updates that put changed voxels back the way they were.
+    fn revert(changes: &[VoxelChange]) -> Vec<VoxelUpdate> {
+        changes
+            .iter()
+            .rev()
+            .map(|(voxel, old, _)| (voxel.to_owned(), *old))
+            .collect()
+    }
+}
+
+/// Bulk editing of the world on behalf of a client, see `World::edit`. Each operation queues one
+/// edit that the client can undo, and returns its ID.
+pub struct WorldEdit<'a> {
+    world: &'a mut World,
+    client_id: String,
+}
+
+impl<'a> WorldEdit<'a> {
+    pub(crate) fn new(world: &'a mut World, client_id: &str) -> Self {
+        Self {
+            world,
+            client_id: client_id.to_owned(),
+        }
+    }
+
+    /// Fill the box between two inclusive corners.
+    pub fn fill(&mut self, min: &Vec3<i32>, max: &Vec3<i32>, raw: u64) -> usize {
+        let updates = Self::cuboid(min, max).map(|voxel| (voxel, raw)).collect();
+        self.queue(updates)
+    }
+
+    /// Set the voxels between two inclusive corners whose raw values pass a filter.
+    pub fn replace<F: Fn(u64) -> bool>(
+        &mut self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        filter: F,
+        raw: u64,
+    ) -> usize {
+        let updates = {
+            let chunks = self.world.read_resource::<Chunks>();
+
+            Self::cuboid(min, max)
+                .filter(|voxel| filter(chunks.get_raw_voxel(voxel.0, voxel.1, voxel.2)))
+                .map(|voxel| (voxel, raw))
+                .collect()
+        };
+
+        self.queue(updates)
+    }
+
+    /// Fill the walls, floor and ceiling of the box between two inclusive corners.
+    pub fn hollow_box(&mut self, min: &Vec3<i32>, max: &Vec3<i32>, raw: u64) -> usize {
+        let (low, high) = Self::corners(min, max);
+
+        let updates = Self::cuboid(min, max)
+            .filter(|voxel| {
+                voxel.0 == low.0
+                    || voxel.0 == high.0
+                    || voxel.1 == low.1
+                    || voxel.1 == high.1
+                    || voxel.2 == low.2
+                    || voxel.2 == high.2
+            })
+            .map(|voxel| (voxel, raw))
+            .collect();
+
+        self.queue(updates)
+    }
+
+    /// Fill the voxels within `radius` of a center voxel.
+    pub fn sphere(&mut self, center: &Vec3<i32>, radius: f32, raw: u64) -> usize {
+        let reach = radius.floor() as i32;
+        let Vec3(cx, cy, cz) = *center;
+
+        let updates = Self::cuboid(
+            &Vec3(cx - reach, cy - reach, cz - reach),
+            &Vec3(cx + reach, cy + reach, cz + reach),
+        )
+        .filter(|voxel| {
+            let (dx, dy, dz) = (voxel.0 - cx, voxel.1 - cy, voxel.2 - cz);
+            ((dx * dx + dy * dy + dz * dz) as f32) <= radius * radius
+        })
+        .map(|voxel| (voxel, raw))
+        .collect();
+
+        self.queue(updates)
+    }
+
+    /// Fill an upright cylinder of `height` voxels standing on a center voxel.
+    pub fn cylinder(&mut self, center: &Vec3<i32>, radius: f32, height: u32, raw: u64) -> usize {
+        if height == 0 {
+            return self.queue(vec![]);
+        }
+
+        let reach = radius.floor() as i32;
+        let Vec3(cx, cy, cz) = *center;
+
+        let updates = Self::cuboid(
+            &Vec3(cx - reach, cy, cz - reach),
+            &Vec3(cx + reach, cy + height as i32 - 1, cz + reach),
+        )
+        .filter(|voxel| {
+            let (dx, dz) = (voxel.0 - cx, voxel.2 - cz);
+            ((dx * dx + dz * dz) as f32) <= radius * radius
+        })
+        .map(|voxel| (voxel, raw))
+        .collect();
+
+        self.queue(updates)
+    }
+
+    /// Copy the box between two inclusive corners into the clipboard of the client. Returns the
+    /// size of the copied box.
+    pub fn copy(&mut self, min: &Vec3<i32>, max: &Vec3<i32>) -> Vec3<usize> {
+        let schematic = self.world.read_resource::<Chunks>().export_schematic(
+            min,
+            max,
+            &self.world.read_resource::<Registry>(),
+        );
+        let size = schematic.size.to_owned();
+
+        self.world
+            .write_resource::<Edits>()
+            .set_clipboard(&self.client_id, schematic);
+
+        size
+    }
+
+    /// Paste the clipboard of the client with its minimum corner at `origin`. Returns `None` if the
+    /// client has not copied anything.
+    pub fn paste(&mut self, origin: &Vec3<i32>, options: &PasteOptions) -> Option<usize> {
+        let updates = {
+            let edits = self.world.read_resource::<Edits>();
+            let registry = self.world.read_resource::<Registry>();
+            let encoding = self.world.config().voxel_encoding;
+
+            edits
+                .clipboard(&self.client_id)?
+                .voxel_updates(origin, options, &registry, encoding)
+        };
+
+        Some(self.queue(updates))
+    }
+
+    /// Repeat the box between two inclusive corners `count` times next to itself, stepping by its own
+    /// size along `direction`, such as `Vec3(0, 1, 0)` to stack it upwards.
+    pub fn stack(
+        &mut self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        direction: &Vec3<i32>,
+        count: usize,
+    ) -> usize {
+        let (low, high) = Self::corners(min, max);
+        let step = Vec3(
+            direction.0.signum() * (high.0 - low.0 + 1),
+            direction.1.signum() * (high.1 - low.1 + 1),
+            direction.2.signum() * (high.2 - low.2 + 1),
+        );
+
+        let updates = {
+            let chunks = self.world.read_resource::<Chunks>();
+            let region: Vec<VoxelUpdate> = Self::cuboid(min, max)
+                .map(|voxel| {
+                    let raw = chunks.get_raw_voxel(voxel.0, voxel.1, voxel.2);
+                    (voxel, raw)
+                })
+                .collect();
+
+            (1..=count as i32)
+                .flat_map(|i| {
+                    region.iter().map(move |(voxel, raw)| {
+                        (
+                            Vec3(
+                                voxel.0 + step.0 * i,
+                                voxel.1 + step.1 * i,
+                                voxel.2 + step.2 * i,
+                            ),
+                            *raw,
+                        )
+                    })
+                })
+                .collect()
+        };
+
+        self.queue(updates)
+    }
+
+    /// Queue undoing the latest finished edit of the client, see `Edits::undo`.
+    pub fn undo(&mut self) -> Option<usize> {
+        self.world.write_resource::<Edits>().undo(&self.client_id)
+    }
+
+    /// Queue redoing the latest undone edit of the client, see `Edits::redo`.
+    pub fn redo(&mut self) -> Option<usize> {
+        self.world.write_resource::<Edits>().redo(&self.client_id)
+    }
+
+    fn queue(&mut self, updates: Vec<VoxelUpdate>) -> usize {
+        self.world
+            .write_resource::<Edits>()
+            .queue(&self.client_id, EditKind::Edit, updates)
+    }
+
+    fn corners(min: &Vec3<i32>, max: &Vec3<i32>) -> (Vec3<i32>, Vec3<i32>) {
+        (
+            Vec3(min.0.min(max.0), min.1.min(max.1), min.2.min(max.2)),
+            Vec3(min.0.max(max.0), min.1.max(max.1), min.2.max(max.2)),
+        )
+    }
+
+    fn cuboid(min: &Vec3<i32>, max: &Vec3<i32>) -> impl Iterator<Item = Vec3<i32>> {
+        let (low, high) = Self::corners(min, max);
+
+        (low.0..=high.0).flat_map(move |vx| {
+            (low.1..=high.1).flat_map(move |vy| (low.2..=high.2).map(move |vz| Vec3(vx, vy, vz)))
+        })
+    }
+}
@@ -1,33 +1,24 @@
 use hashbrown::HashMap;
-use log::{info, warn};
-use serde_json::{json, Value};
+use log::warn;
+use serde_json::json;
 use specs::{Entity, World as ECSWorld, WorldExt};
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::sync::Arc;
 
-use crate::{ETypeComp, IDComp, MetadataComp, PositionComp, RigidBodyComp, WorldConfig};
+use crate::{MetadataComp, PositionComp, RigidBodyComp, WorldConfig, WorldStorage};
 
 /// Takes all the metadata components, and saves them into the
-/// world saving directory by their ID's.
+/// world storage by their ID's.
 #[derive(Clone)]
 pub struct EntitiesSaver {
-    pub folder: PathBuf,
+    pub storage: Option<Arc<dyn WorldStorage>>,
     pub saving: bool,
 }
 
 impl EntitiesSaver {
-    pub fn new(config: &WorldConfig) -> Self {
-        let mut folder = PathBuf::from(&config.save_dir);
-        folder.push("entities");
-
-        if config.saving && config.save_entities {
-            fs::create_dir_all(&folder).expect("Unable to create entities directory...");
-        }
-
+    pub fn new(config: &WorldConfig, storage: Option<Arc<dyn WorldStorage>>) -> Self {
         Self {
-            saving: config.saving && config.save_entities,
-            folder,
+            saving: config.saving && config.save_entities && storage.is_some(),
+            storage,
         }
     }
 
@@ -48,11 +39,11 @@ impl EntitiesSaver {
         // info!("Saving metadata for entity {}: {:?}", id, metadata);
         map.insert("etype".to_owned(), json!(etype_value));
         map.insert("metadata".to_owned(), json!(metadata));
-        let mut path = self.folder.clone();
-        path.push(format!("{}.json", id));
-        let mut file = File::create(&path).expect("Could not create entity file...");
         let j = serde_json::to_string(&json!(map)).unwrap();
-        file.write_all(j.as_bytes())
+        self.storage
+            .as_ref()
+            .unwrap()
+            .save_entity(id, j.as_bytes())
             .expect("Unable to write entity file.");
     }
 
@@ -61,9 +52,7 @@ impl EntitiesSaver {
             return;
         }
 
-        let mut path = self.folder.clone();
-        path.push(format!("{}.json", id));
-        if let Err(e) = fs::remove_file(&path) {
+        if let Err(e) = self.storage.as_ref().unwrap().delete_entity(id) {
             warn!(
                 "Failed to remove entity file: {}. Entity could still be saving?",
                 e
@@ -58,6 +58,9 @@ pub struct Mesher {
 
     /// The thread pool for meshing.
     pool: ThreadPool,
+
+    /// Whether chunks are only lit and not meshed, such as when pre-generating a world headlessly.
+    pub(crate) lights_only: bool,
 }
 
 impl Mesher {
@@ -76,6 +79,7 @@ impl Mesher {
                 .num_threads(64)
                 .build()
                 .unwrap(),
+            lights_only: false,
         }
     }
 
@@ -131,6 +135,7 @@ impl Mesher {
         let r#type = r#type.clone();
         let registry = Arc::new(registry.clone());
         let config = Arc::new(config.clone());
+        let lights_only = self.lights_only;
 
         self.pool.spawn(move || {
             processes
@@ -148,13 +153,17 @@ impl Mesher {
                         LightColor::Blue,
                     ];
 
-                    let sub_chunks = chunk.updated_levels.clone();
+                    let sub_chunks = if lights_only {
+                        HashSet::new()
+                    } else {
+                        chunk.updated_levels.clone()
+                    };
                     let Vec3(min_x, min_y, min_z) = chunk.min;
                     let Vec3(max_x, _, max_z) = chunk.max;
                     let blocks_per_sub_chunk =
                         (space.options.max_height / space.options.sub_chunks) as i32;
 
-                    if chunk.meshes.is_none() {
+                    if !chunk.has_lights {
                         let mut light_queues = vec![VecDeque::new(); 4];
 
                         for dx in -1..=1 {
@@ -197,7 +206,10 @@ impl Mesher {
                             }
                         }
 
-                        chunk.lights = space.get_lights(coords.0, coords.1).unwrap().clone();
+                        chunk
+                            .lights
+                            .assign(space.get_lights(coords.0, coords.1).unwrap().data.clone());
+                        chunk.has_lights = true;
                     }
 
                     for level in sub_chunks {
@@ -3,6 +3,7 @@ mod lsystem;
 mod mesher;
 mod noise;
 mod pipeline;
+mod signals;
 mod spline;
 mod terrain;
 mod trees;
@@ -12,6 +13,7 @@ pub use lights::{LightNode, Lights};
 pub use lsystem::*;
 pub use mesher::Mesher;
 pub use pipeline::*;
+pub use signals::*;
 pub use spline::SplineMap;
 pub use terrain::*;
 pub use trees::*;
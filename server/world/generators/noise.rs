@@ -1,5 +1,5 @@
 use noise::{Fbm, HybridMulti, MultiFractal, NoiseFn, Perlin, RidgedMulti, Seedable};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use splines::interpolate::Interpolator;
 use std::f64;
 
@@ -63,7 +63,7 @@ impl SeededNoise {
 }
 
 /// Multi-fractal noise options.
-#[derive(Clone, Default, Serialize, Debug)]
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct NoiseOptions {
     pub seed: u32,
 
@@ -10,6 +10,8 @@ use crate::{
     WorldConfig,
 };
 
+/// What chunk stages have to work with. Stages can look blocks up by tag through the registry, such
+/// as `registry.has_tag(id, "replaceable")`, instead of keeping lists of IDs.
 #[derive(Clone)]
 pub struct Resources<'a> {
     pub registry: &'a Registry,
@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+
+use crate::{Lights, Registry, Vec3, VoxelAccess, WorldConfig};
+
+use super::lights::VOXEL_NEIGHBORS;
+
+/// Highest power level a signal can have, as it is kept in the stage of conductors.
+pub const MAX_SIGNAL_LEVEL: u32 = 15;
+
+/// Node of a signal propagation queue.
+#[derive(Debug, Clone)]
+pub struct SignalNode {
+    pub voxel: [i32; 3],
+    pub level: u32,
+}
+
+/// A set of utility functions to carry signal power from sources through conductors, much like
+/// torch light. The power of a conductor is its stage, and drops by one for every conductor it
+/// goes through.
+pub struct Signals;
+
+impl Signals {
+    /// Power a voxel gives off: the power of a source, or the stage of a conductor.
+    pub fn get_power(space: &dyn VoxelAccess, voxel: &Vec3<i32>, registry: &Registry) -> u32 {
+        let &Vec3(vx, vy, vz) = voxel;
+        let block = registry.get_block_by_id(space.get_voxel(vx, vy, vz));
+
+        if block.signal_power > 0 {
+            block.signal_power
+        } else if block.signal_conductor {
+            space.get_voxel_stage(vx, vy, vz)
+        } else {
+            0
+        }
+    }
+
+    /// Highest power given off by the voxels connected to a voxel, which is what a consumer there
+    /// receives. A conductor there carries one less.
+    pub fn get_received_power(
+        space: &dyn VoxelAccess,
+        voxel: &Vec3<i32>,
+        registry: &Registry,
+    ) -> u32 {
+        let &Vec3(vx, vy, vz) = voxel;
+        let faces = Self::get_faces(space, voxel, registry);
+
+        VOXEL_NEIGHBORS
+            .iter()
+            .filter_map(|&[ox, oy, oz]| {
+                let n_voxel = Vec3(vx + ox, vy + oy, vz + oz);
+                let n_faces = Self::get_faces(space, &n_voxel, registry);
+
+                if !Lights::can_enter(&n_faces, &faces, -ox, -oy, -oz) {
+                    return None;
+                }
+
+                Some(Self::get_power(space, &n_voxel, registry))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Propagate a queue of `SignalNode`s into the conductors connected to them. Returns the
+    /// conductors whose power changed.
+    pub fn flood_power(
+        space: &mut dyn VoxelAccess,
+        mut queue: VecDeque<SignalNode>,
+        registry: &Registry,
+        config: &WorldConfig,
+    ) -> Vec<Vec3<i32>> {
+        let max_height = config.max_height as i32;
+        let mut changed = vec![];
+
+        while let Some(SignalNode { voxel, level }) = queue.pop_front() {
+            if level <= 1 {
+                continue;
+            }
+
+            let [vx, vy, vz] = voxel;
+            let faces = Self::get_faces(&*space, &Vec3(vx, vy, vz), registry);
+
+            for [ox, oy, oz] in &VOXEL_NEIGHBORS {
+                let nvy = vy + oy;
+
+                if nvy < 0 || nvy >= max_height {
+                    continue;
+                }
+
+                let n_voxel = Vec3(vx + ox, nvy, vz + oz);
+                let n_block = registry.get_block_by_id(space.get_voxel(n_voxel.0, nvy, n_voxel.2));
+                let next_level = level - 1;
+
+                // To not continue:
+                // (1) Neighbor does not carry signals, or is not connected to this voxel.
+                // (2) Neighbor power is greater or equal to what it would get.
+                if !n_block.signal_conductor
+                    || !Lights::can_enter(
+                        &faces,
+                        &Self::get_faces(&*space, &n_voxel, registry),
+                        *ox,
+                        *oy,
+                        *oz,
+                    )
+                    || space.get_voxel_stage(n_voxel.0, nvy, n_voxel.2) >= next_level
+                {
+                    continue;
+                }
+
+                space.set_voxel_stage(n_voxel.0, nvy, n_voxel.2, next_level);
+
+                queue.push_back(SignalNode {
+                    voxel: [n_voxel.0, nvy, n_voxel.2],
+                    level: next_level,
+                });
+                changed.push(n_voxel);
+            }
+        }
+
+        changed
+    }
+
+    /// Take away the power a voxel used to give off at `level`, then fill the conductors it fed
+    /// back in from whatever else powers them. Returns the conductors whose power changed.
+    pub fn remove_power(
+        space: &mut dyn VoxelAccess,
+        voxel: &Vec3<i32>,
+        level: u32,
+        registry: &Registry,
+        config: &WorldConfig,
+    ) -> Vec<Vec3<i32>> {
+        let max_height = config.max_height as i32;
+
+        let mut fill = VecDeque::<SignalNode>::new();
+        let mut queue = VecDeque::<SignalNode>::new();
+        let mut changed = vec![];
+
+        queue.push_back(SignalNode {
+            voxel: [voxel.0, voxel.1, voxel.2],
+            level,
+        });
+
+        while let Some(SignalNode { voxel, level }) = queue.pop_front() {
+            let [vx, vy, vz] = voxel;
+
+            for [ox, oy, oz] in &VOXEL_NEIGHBORS {
+                let nvy = vy + oy;
+
+                if nvy < 0 || nvy >= max_height {
+                    continue;
+                }
+
+                let n_voxel = Vec3(vx + ox, nvy, vz + oz);
+                let n_faces = Self::get_faces(&*space, &n_voxel, registry);
+
+                // The voxel here may not connect anymore, so only the side of the neighbor counts.
+                if !Lights::can_enter_into(&n_faces, *ox, *oy, *oz) {
+                    continue;
+                }
+
+                let n_block = registry.get_block_by_id(space.get_voxel(n_voxel.0, nvy, n_voxel.2));
+                let n_level = Self::get_power(&*space, &n_voxel, registry);
+
+                if n_level == 0 {
+                    continue;
+                }
+
+                if n_block.signal_conductor && n_level < level {
+                    space.set_voxel_stage(n_voxel.0, nvy, n_voxel.2, 0);
+
+                    queue.push_back(SignalNode {
+                        voxel: [n_voxel.0, nvy, n_voxel.2],
+                        level: n_level,
+                    });
+                    changed.push(n_voxel);
+                } else {
+                    fill.push_back(SignalNode {
+                        voxel: [n_voxel.0, nvy, n_voxel.2],
+                        level: n_level,
+                    });
+                }
+            }
+        }
+
+        changed.extend(Signals::flood_power(space, fill, registry, config));
+        changed
+    }
+
+    /// The faces of a voxel that signals go through, rotated with the voxel. Blocks that have
+    /// nothing to do with signals have none.
+    fn get_faces(space: &dyn VoxelAccess, voxel: &Vec3<i32>, registry: &Registry) -> [bool; 6] {
+        let &Vec3(vx, vy, vz) = voxel;
+        let block = registry.get_block_by_id(space.get_voxel(vx, vy, vz));
+
+        if !block.is_signal_block() {
+            return [false; 6];
+        }
+
+        space
+            .get_voxel_rotation(vx, vy, vz)
+            .rotate_transparency(block.signal_faces)
+    }
+}
@@ -3,7 +3,7 @@ use std::f64;
 use hashbrown::HashMap;
 use nalgebra::{Rotation3, Vector3};
 
-use crate::{LSystem, NoiseOptions, SeededNoise, Vec3, VoxelUpdate};
+use crate::{BlockUtils, LSystem, NoiseOptions, SeededNoise, Vec3, VoxelUpdate};
 
 /// There are a set of L-system symbols for the tree generator.
 /// The symbols are:
@@ -223,7 +223,10 @@ impl Trees {
                         let new_y = vec.y.round() as i32;
                         let new_z = vec.z.round() as i32;
 
-                        changes.push((Vec3(fx + new_x, fy + new_y, fz + new_z), trunk_id));
+                        changes.push((
+                            Vec3(fx + new_x, fy + new_y, fz + new_z),
+                            BlockUtils::insert_wide_id(0, trunk_id),
+                        ));
                     }
                 }
             }
@@ -251,7 +254,10 @@ impl Trees {
                         continue;
                     }
 
-                    changes.push((Vec3(vx + x, vy + y, vz + z), leaf_id));
+                    changes.push((
+                        Vec3(vx + x, vy + y, vz + z),
+                        BlockUtils::insert_wide_id(0, leaf_id),
+                    ));
                 }
             }
         }
@@ -0,0 +1,249 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hashbrown::HashMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChunkUtils, RegionArea, Vec2, Vec3};
+
+/// Name of the folder inside `save_dir` that the voxel history is kept in by default.
+pub const HISTORY_FOLDER: &str = "history";
+
+/// The source of voxel changes that were not made on behalf of a client.
+pub const SYSTEM_SOURCE: &str = "system";
+
+/// A recorded voxel change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// ID of the client that made the change, or `SYSTEM_SOURCE`.
+    pub source: String,
+
+    /// The tick the change was applied on.
+    pub tick: u64,
+
+    /// When the change was applied, in milliseconds since the UNIX epoch.
+    pub time: u64,
+
+    /// The voxel that changed.
+    pub voxel: Vec3<i32>,
+
+    /// Raw value of the voxel before the change.
+    pub old: u64,
+
+    /// Raw value of the voxel after the change.
+    pub new: u64,
+}
+
+impl HistoryEntry {
+    /// When the change was applied.
+    pub fn applied_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.time)
+    }
+}
+
+/// An append-only log of the voxel changes applied to a world, one log per chunk. Kept in
+/// `config.history_dir` as one JSON entry per line, or in memory if there is no directory, where
+/// only the latest `config.max_memory_history` changes of each chunk are kept.
+#[derive(Default)]
+pub struct History {
+    /// Whether changes are being recorded.
+    pub enabled: bool,
+
+    /// Changes recorded this tick that are not written yet, chunk coords -> entries.
+    pending: HashMap<Vec2<i32>, Vec<HistoryEntry>>,
+
+    /// The history of every chunk, if it is not kept on disk.
+    memory: HashMap<Vec2<i32>, Vec<HistoryEntry>>,
+
+    dir: Option<PathBuf>,
+
+    chunk_size: usize,
+
+    max_memory: usize,
+}
+
+impl History {
+    /// Create a voxel history, written to `dir` if it is not empty, or else keeping up to
+    /// `max_memory` changes per chunk in memory.
+    pub fn new(enabled: bool, dir: &str, chunk_size: usize, max_memory: usize) -> Self {
+        let dir = if dir.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(dir))
+        };
+
+        if enabled {
+            if let Some(dir) = &dir {
+                if let Err(e) = fs::create_dir_all(dir) {
+                    warn!("Could not create history folder: {}", e);
+                }
+            }
+        }
+
+        Self {
+            enabled,
+            dir,
+            chunk_size,
+            max_memory,
+            ..Default::default()
+        }
+    }
+
+    /// Record a voxel change, to be written on the next `flush`.
+    pub fn record(&mut self, source: &str, tick: u64, voxel: &Vec3<i32>, old: u64, new: u64) {
+        if !self.enabled {
+            return;
+        }
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        self.pending
+            .entry(self.coords(voxel))
+            .or_default()
+            .push(HistoryEntry {
+                source: source.to_owned(),
+                tick,
+                time,
+                voxel: voxel.to_owned(),
+                old,
+                new,
+            });
+    }
+
+    /// Append the changes recorded so far to the log of their chunk.
+    pub fn flush(&mut self) {
+        for (coords, entries) in self.pending.drain() {
+            let dir = match &self.dir {
+                Some(dir) => dir,
+                None => {
+                    let memory = self.memory.entry(coords).or_default();
+                    memory.extend(entries);
+
+                    if memory.len() > self.max_memory {
+                        memory.drain(..memory.len() - self.max_memory);
+                    }
+
+                    continue;
+                }
+            };
+
+            let mut data = vec![];
+
+            for entry in &entries {
+                serde_json::to_writer(&mut data, entry).unwrap();
+                data.push(b'\n');
+            }
+
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(Self::log_path(dir, &coords))
+                .and_then(|mut file| file.write_all(&data));
+
+            if let Err(e) = written {
+                warn!("Could not write the history of chunk {:?}: {}", coords, e);
+            }
+        }
+    }
+
+    /// Every change in a chunk, oldest first.
+    pub fn chunk(&self, coords: &Vec2<i32>) -> Vec<HistoryEntry> {
+        let mut entries = match &self.dir {
+            Some(dir) => Self::read_log(&Self::log_path(dir, coords)),
+            None => self.memory.get(coords).cloned().unwrap_or_default(),
+        };
+
+        if let Some(pending) = self.pending.get(coords) {
+            entries.extend(pending.iter().cloned());
+        }
+
+        entries
+    }
+
+    /// Every change to a voxel, oldest first. The last entry tells who changed it last.
+    pub fn at(&self, voxel: &Vec3<i32>) -> Vec<HistoryEntry> {
+        self.chunk(&self.coords(voxel))
+            .into_iter()
+            .filter(|entry| entry.voxel == *voxel)
+            .collect()
+    }
+
+    /// Every change a source made since a point in time, oldest first within each chunk.
+    pub fn by_source(&self, source: &str, since: SystemTime) -> Vec<HistoryEntry> {
+        self.chunks()
+            .iter()
+            .flat_map(|coords| self.chunk(coords))
+            .filter(|entry| entry.source == source && entry.applied_at() >= since)
+            .collect()
+    }
+
+    /// Every change a source made inside an area since a point in time, oldest first.
+    pub fn in_area(&self, source: &str, area: &RegionArea, since: SystemTime) -> Vec<HistoryEntry> {
+        area.chunks(self.chunk_size)
+            .iter()
+            .flat_map(|coords| self.chunk(coords))
+            .filter(|entry| {
+                entry.source == source
+                    && entry.applied_at() >= since
+                    && area.contains(&entry.voxel, self.chunk_size)
+            })
+            .collect()
+    }
+
+    /// The coordinates of the chunks with any history.
+    pub fn chunks(&self) -> Vec<Vec2<i32>> {
+        let mut coords: Vec<Vec2<i32>> = match &self.dir {
+            Some(dir) => fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter_map(|entry| {
+                            let name = entry.file_name().to_string_lossy().into_owned();
+                            let (cx, cz) = name.strip_suffix(".jsonl")?.split_once('_')?;
+                            Some(Vec2(cx.parse().ok()?, cz.parse().ok()?))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => self.memory.keys().cloned().collect(),
+        };
+
+        for pending in self.pending.keys() {
+            if !coords.contains(pending) {
+                coords.push(pending.to_owned());
+            }
+        }
+
+        coords
+    }
+
+    fn coords(&self, voxel: &Vec3<i32>) -> Vec2<i32> {
+        ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, self.chunk_size)
+    }
+
+    fn log_path(dir: &Path, coords: &Vec2<i32>) -> PathBuf {
+        dir.join(format!("{}_{}.jsonl", coords.0, coords.1))
+    }
+
+    fn read_log(path: &Path) -> Vec<HistoryEntry> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(_) => return vec![],
+        };
+
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+}
@@ -1,18 +1,24 @@
+mod atlas;
 mod bookkeeping;
 mod clients;
 mod components;
 mod config;
+mod edits;
 mod entities;
 mod entity_ids;
 mod events;
 mod generators;
+mod history;
 mod interests;
 mod messages;
 mod physics;
+mod pregen;
 mod profiler;
+mod regions;
 mod registry;
 mod search;
 mod stats;
+mod storage;
 mod systems;
 mod types;
 mod utils;
@@ -23,7 +29,7 @@ use actix::{
     SyncContext,
 };
 use actix::{Addr, SyncArbiter};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use log::{info, warn};
 use nanoid::nanoid;
 use profiler::Profiler;
@@ -39,33 +45,40 @@ use std::path::PathBuf;
 use std::sync::{Mutex, RwLock};
 use std::{env, sync::Arc};
 use std::{
-    fs::{self, File},
-    time::Duration,
+    fs, io,
+    time::{Duration, Instant, SystemTime},
 };
 
 use crate::{
     encode_message,
     protocols::Peer,
     server::{Message, MessageType},
-    EncodedMessage, EntityOperation, EntityProtocol, PeerProtocol, Server, Vec2, Vec3,
+    EncodedMessage, EntityOperation, EntityProtocol, PeerProtocol, Server, UpdateProtocol, Vec2,
+    Vec3,
 };
 
 use super::common::ClientFilter;
 
+pub use atlas::*;
 pub use bookkeeping::*;
 pub use clients::*;
 pub use components::*;
 pub use config::*;
+pub use edits::*;
 pub use entities::*;
 pub use entity_ids::*;
 pub use events::*;
 pub use generators::*;
+pub use history::*;
 pub use interests::*;
 pub use messages::*;
 pub use physics::*;
+pub use pregen::*;
+pub use regions::*;
 pub use registry::*;
 pub use search::*;
 pub use stats::*;
+pub use storage::*;
 pub use systems::*;
 pub use types::*;
 pub use utils::*;
@@ -73,6 +86,9 @@ pub use voxels::*;
 
 pub type Transports = HashMap<String, Recipient<EncodedMessage>>;
 
+/// Validator of the voxel updates clients send, see `World::set_update_validator`.
+pub type UpdateValidator = Arc<dyn Fn(&World, &str, &VoxelUpdate) -> bool + Send + Sync>;
+
 /// The default client metadata parser, parses PositionComp and DirectionComp, and updates RigidBodyComp.
 pub fn default_client_parser(world: &mut World, metadata: &str, client_ent: Entity) {
     let metadata: PeerUpdate = match serde_json::from_str(metadata) {
@@ -154,6 +170,12 @@ pub struct World {
     /// The handler for commands.
     command_handle: Option<Arc<dyn Fn(&mut World, &str, &str) + Send + Sync>>,
 
+    /// The validator of voxel updates sent by clients, on top of the checks in the config.
+    update_validator: Option<UpdateValidator>,
+
+    /// Number of voxel updates each client sent since a second started, client ID -> (start, count).
+    update_rates: HashMap<String, (Instant, usize)>,
+
     /// A map to spawn and create entities.
     entity_loaders:
         HashMap<String, Arc<dyn Fn(&mut World, MetadataComp) -> EntityBuilder + Send + Sync>>,
@@ -335,7 +357,17 @@ fn dispatcher() -> DispatcherBuilder<'static, 'static> {
         .with(EntitiesMetaSystem, "entities-meta", &[])
         .with(PeersMetaSystem, "peers-meta", &[])
         .with(CurrentChunkSystem, "current-chunk", &[])
-        .with(ChunkUpdatingSystem, "chunk-updating", &["current-chunk"])
+        .with(EditsSystem, "edits", &[])
+        .with(
+            ChunkUpdatingSystem,
+            "chunk-updating",
+            &["current-chunk", "edits"],
+        )
+        .with(
+            ChunkRandomTickingSystem,
+            "chunk-random-ticking",
+            &["chunk-updating"],
+        )
         .with(ChunkRequestsSystem, "chunk-requests", &["current-chunk"])
         .with(
             ChunkGeneratingSystem,
@@ -344,7 +376,13 @@ fn dispatcher() -> DispatcherBuilder<'static, 'static> {
         )
         .with(ChunkSendingSystem, "chunk-sending", &["chunk-generation"])
         .with(ChunkSavingSystem, "chunk-saving", &["chunk-generation"])
+        .with(
+            ChunkUnloadingSystem,
+            "chunk-unloading",
+            &["chunk-sending", "chunk-saving"],
+        )
         .with(PhysicsSystem, "physics", &["current-chunk", "update-stats"])
+        .with(FallingBlocksSystem, "falling-blocks", &["physics"])
         .with(DataSavingSystem, "entities-saving", &["entities-meta"])
         .with(
             EntitiesSendingSystem,
@@ -405,7 +443,7 @@ impl World {
     pub fn new(name: &str, config: &WorldConfig) -> Self {
         let id = nanoid!();
 
-        if config.saving {
+        if config.saving && config.storage.is_on_disk() {
             let folder = PathBuf::from(&config.save_dir);
 
             // If folder doesn't exist, create it.
@@ -416,6 +454,12 @@ impl World {
             }
         }
 
+        let storage = if config.saving {
+            Some(config.storage.open(&config.save_dir))
+        } else {
+            None
+        };
+
         let mut ecs = ECSWorld::new();
 
         ecs.register::<AddrComp>();
@@ -427,6 +471,7 @@ impl World {
         ecs.register::<DirectionComp>();
         ecs.register::<EntityFlag>();
         ecs.register::<ETypeComp>();
+        ecs.register::<FallingBlockComp>();
         ecs.register::<IDComp>();
         ecs.register::<InteractorComp>();
         ecs.register::<JsonComp>();
@@ -441,13 +486,10 @@ impl World {
         ecs.insert(name.to_owned());
         ecs.insert(config.clone());
 
-        ecs.insert(Chunks::new(config));
-        ecs.insert(EntitiesSaver::new(&config));
-        ecs.insert(Stats::new(
-            config.saving,
-            &config.save_dir,
-            config.default_time,
-        ));
+        ecs.insert(Chunks::new(config, storage.clone()));
+        ecs.insert(EntitiesSaver::new(config, storage.clone()));
+        ecs.insert(Regions::new(config.chunk_size, storage.clone()));
+        ecs.insert(Stats::new(storage, config.default_time));
         ecs.insert(Search::new());
 
         ecs.insert(Mesher::new());
@@ -463,6 +505,13 @@ impl World {
         ecs.insert(EncodedMessageQueue::new());
         ecs.insert(Profiler::new(Duration::from_secs_f64(0.001)));
         ecs.insert(EntityIDs::new());
+        ecs.insert(Edits::new(config.max_edit_history));
+        ecs.insert(History::new(
+            config.record_history,
+            &config.history_dir,
+            config.chunk_size,
+            config.max_memory_history,
+        ));
 
         let mut world = Self {
             id,
@@ -481,6 +530,8 @@ impl World {
             client_modifier: None,
             transport_handle: None,
             command_handle: None,
+            update_validator: None,
+            update_rates: HashMap::default(),
             addr: None,
             server_addr: None,
         };
@@ -529,6 +580,19 @@ impl World {
             }
         });
 
+        world.set_entity_loader(FALLING_BLOCK_ETYPE, |world, metadata| {
+            let position = metadata.get::<PositionComp>("position").unwrap_or_default();
+            let falling_block = metadata
+                .get::<FallingBlockComp>("block")
+                .unwrap_or_default();
+
+            world
+                .create_entity(&nanoid!(), FALLING_BLOCK_ETYPE)
+                .with(RigidBodyComp::new(&falling_block_body(&position.0)))
+                .with(falling_block)
+                .with(position)
+        });
+
         world
     }
 
@@ -665,6 +729,7 @@ impl World {
     pub(crate) fn remove_client(&mut self, id: &str) {
         let removed = self.clients_mut().remove(id);
         self.entity_ids_mut().remove(id);
+        self.update_rates.remove(id);
 
         if let Some(client) = removed {
             {
@@ -737,6 +802,618 @@ impl World {
         self.dispatcher = Arc::new(dispatch);
     }
 
+    /// Replace the storage this world saves to with a custom backend. Only takes effect if
+    /// `config.saving` is true, and should be called before the world is added to a server.
+    pub fn set_storage(&mut self, storage: Arc<dyn WorldStorage>) {
+        if !self.config().saving {
+            warn!(
+                "Setting a storage on world {:?}, but saving is off.",
+                self.name
+            );
+            return;
+        }
+
+        let storage = Some(storage);
+        let save_entities = self.config().save_entities;
+
+        self.chunks_mut().set_storage(storage.clone());
+        self.stats_mut().set_storage(storage.clone());
+        self.regions_mut().set_storage(storage.clone());
+
+        let mut saver = self.write_resource::<EntitiesSaver>();
+        saver.saving = save_entities;
+        saver.storage = storage;
+    }
+
+    /// Access the storage this world saves to, if saving is on.
+    pub fn storage(&self) -> Option<Arc<dyn WorldStorage>> {
+        self.chunks().storage().cloned()
+    }
+
+    /// Write everything that is waiting to be saved right away: changed chunks, entities, stats and
+    /// regions.
+    pub fn flush(&mut self) {
+        if !self.config().saving {
+            return;
+        }
+
+        {
+            let registry = self.ecs.read_resource::<Registry>();
+            let mut chunks = self.ecs.write_resource::<Chunks>();
+
+            let to_save: Vec<Vec2<i32>> = chunks.to_save.drain(..).collect();
+
+            for coords in to_save {
+                if !chunks.save(&coords, &registry) {
+                    chunks.add_chunk_to_save(&coords, false);
+                }
+            }
+
+            chunks.save_stale_lights();
+        }
+
+        if self.config().save_entities {
+            let saver = self.read_resource::<EntitiesSaver>();
+            let ids = self.read_component::<IDComp>();
+            let etypes = self.read_component::<ETypeComp>();
+            let metadatas = self.read_component::<MetadataComp>();
+
+            for (id, etype, metadata) in (&ids, &etypes, &metadatas).join() {
+                saver.save(&id.0, &etype.0, etype.1, metadata);
+            }
+        }
+
+        self.stats().save();
+        self.regions().save();
+    }
+
+    /// The snapshots of this world, kept in `config.snapshot_dir`.
+    pub fn snapshots(&self) -> Snapshots {
+        Snapshots::new(&PathBuf::from(&self.config().snapshot_dir))
+    }
+
+    /// Flush all unsaved changes, then copy everything this world has saved into a new snapshot.
+    pub fn snapshot(&mut self) -> io::Result<SnapshotInfo> {
+        let storage = self.saving_storage()?;
+
+        self.flush();
+
+        let info = self.snapshots().create(&self.name, storage.as_ref())?;
+
+        info!(
+            "World {:?} took snapshot {:?} of {} chunks and {} entities.",
+            self.name, info.name, info.chunks, info.entities
+        );
+
+        Ok(info)
+    }
+
+    /// Roll this world back to a snapshot while it runs. With a region, given as two inclusive corner
+    /// chunks, only the chunks within it are restored. Otherwise the whole world is restored, entities
+    /// and stats included. Restored chunks that are loaded get reloaded and sent to their clients again.
+    /// Returns the number of chunks restored.
+    pub fn restore_snapshot(
+        &mut self,
+        name: &str,
+        region: Option<(Vec2<i32>, Vec2<i32>)>,
+    ) -> io::Result<usize> {
+        let storage = self.saving_storage()?;
+        let snapshot = self.snapshots().open(name)?;
+
+        let bounds = region.as_ref().map(|(a, b)| {
+            (
+                Vec2(a.0.min(b.0), a.1.min(b.1)),
+                Vec2(a.0.max(b.0), a.1.max(b.1)),
+            )
+        });
+        let in_region = |coords: &Vec2<i32>| match &bounds {
+            Some((min, max)) => {
+                coords.0 >= min.0 && coords.0 <= max.0 && coords.1 >= min.1 && coords.1 <= max.1
+            }
+            None => true,
+        };
+
+        let loaded: Vec<Vec2<i32>> = self.chunks().map.keys().cloned().collect();
+        let to_restore: Vec<Vec2<i32>> = snapshot
+            .list_chunks()?
+            .into_iter()
+            .chain(storage.list_chunks()?)
+            .chain(loaded)
+            .filter(|coords| in_region(coords))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // Unload the chunks first, so that none of their pending saves land on top of the snapshot.
+        let mut to_reload = vec![];
+
+        {
+            let mut chunks = self.ecs.write_resource::<Chunks>();
+            let mut pipeline = self.ecs.write_resource::<Pipeline>();
+            let mut mesher = self.ecs.write_resource::<Mesher>();
+
+            for coords in &to_restore {
+                pipeline.remove_chunk(coords);
+                mesher.remove_chunk(coords);
+
+                if chunks.unload(coords).is_some() {
+                    to_reload.push(coords.to_owned());
+                }
+            }
+        }
+
+        for coords in &to_restore {
+            match snapshot.load_chunk(coords)? {
+                Some(data) => storage.save_chunk(coords, &data)?,
+                None => storage.delete_chunk(coords)?,
+            }
+        }
+
+        if region.is_some() {
+            // Light crosses the edges of the region, so the saved lights on both sides are out of date.
+            let mut chunks = self.chunks_mut();
+
+            let stale: Vec<Vec2<i32>> = to_restore
+                .iter()
+                .flat_map(|coords| chunks.light_traversed_chunks(coords))
+                .collect();
+
+            chunks.mark_lights_stale(&stale);
+        } else {
+            self.restore_snapshot_metadata(&snapshot, storage.as_ref())?;
+            self.restore_snapshot_entities(&snapshot, storage.as_ref())?;
+        }
+
+        {
+            let mut pipeline = self.pipeline_mut();
+
+            for coords in &to_reload {
+                pipeline.add_chunk(coords, true);
+            }
+        }
+
+        info!(
+            "World {:?} restored {} chunks from snapshot {:?}.",
+            self.name,
+            to_restore.len(),
+            name
+        );
+
+        Ok(to_restore.len())
+    }
+
+    /// Run the arguments of a snapshot command and describe the result. Chat commands are not hooked
+    /// up to this by default, so call it from a command handler once the sender is known to be allowed:
+    ///
+    /// - `create`, or nothing, takes a snapshot.
+    /// - `list` lists the snapshots.
+    /// - `restore <name> [<x1> <z1> <x2> <z2>]` restores the whole world, or a region of chunks.
+    /// - `delete <name>` deletes a snapshot.
+    pub fn snapshot_command(&mut self, args: &str) -> String {
+        let args: Vec<&str> = args.split_whitespace().collect();
+
+        let result = match args.as_slice() {
+            [] | ["create"] => self
+                .snapshot()
+                .map(|info| format!("Took snapshot {}.", info.name)),
+            ["list"] => self.snapshots().list().map(|snapshots| {
+                if snapshots.is_empty() {
+                    "There are no snapshots.".to_owned()
+                } else {
+                    snapshots
+                        .iter()
+                        .map(|info| info.name.to_owned())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            }),
+            ["restore", name] => self
+                .restore_snapshot(name, None)
+                .map(|count| format!("Restored {} chunks from snapshot {}.", count, name)),
+            ["restore", name, x1, z1, x2, z2] => {
+                let corners: Vec<i32> = [x1, z1, x2, z2]
+                    .iter()
+                    .filter_map(|value| value.parse().ok())
+                    .collect();
+
+                if corners.len() != 4 {
+                    return "Region corners should be chunk coordinates.".to_owned();
+                }
+
+                let region = (Vec2(corners[0], corners[1]), Vec2(corners[2], corners[3]));
+
+                self.restore_snapshot(name, Some(region))
+                    .map(|count| format!("Restored {} chunks from snapshot {}.", count, name))
+            }
+            ["delete", name] => self
+                .snapshots()
+                .delete(name)
+                .map(|_| format!("Deleted snapshot {}.", name)),
+            _ => {
+                return "Usage: create | list | restore <name> [<x1> <z1> <x2> <z2>] | delete <name>"
+                    .to_owned()
+            }
+        };
+
+        result.unwrap_or_else(|e| format!("Snapshot command failed: {}", e))
+    }
+
+    /// Copy the voxels between two inclusive corners into a schematic, along with the JSON data of
+    /// the block entities inside.
+    pub fn export_schematic(&self, min: &Vec3<i32>, max: &Vec3<i32>) -> Schematic {
+        let chunks = self.chunks();
+        let mut schematic = chunks.export_schematic(min, max, &self.registry());
+
+        let low = Vec3(min.0.min(max.0), min.1.min(max.1), min.2.min(max.2));
+        let jsons = self.read_component::<JsonComp>();
+
+        for (voxel, entity) in &chunks.block_entities {
+            let relative = Vec3(voxel.0 - low.0, voxel.1 - low.1, voxel.2 - low.2);
+
+            if relative.0 < 0
+                || relative.1 < 0
+                || relative.2 < 0
+                || relative.0 >= schematic.size.0 as i32
+                || relative.1 >= schematic.size.1 as i32
+                || relative.2 >= schematic.size.2 as i32
+            {
+                continue;
+            }
+
+            if let Some(json) = jsons.get(*entity) {
+                schematic.block_entities.push(SchematicBlockEntity {
+                    voxel: relative,
+                    json: json.0.to_owned(),
+                });
+            }
+        }
+
+        schematic
+            .block_entities
+            .sort_by_key(|block_entity| block_entity.voxel.to_arr());
+
+        schematic
+    }
+
+    /// Paste a schematic with its minimum corner at `origin`, see `Chunks::paste_schematic`.
+    pub fn paste_schematic(
+        &mut self,
+        schematic: &Schematic,
+        origin: &Vec3<i32>,
+        options: &PasteOptions,
+    ) -> usize {
+        let registry = self.ecs.read_resource::<Registry>();
+        let mut chunks = self.ecs.write_resource::<Chunks>();
+
+        chunks.paste_schematic(schematic, origin, options, &registry)
+    }
+
+    /// Edit the world in bulk on behalf of a client, who can then undo and redo the edits. The edits
+    /// are applied over the next ticks, see `Edits`.
+    pub fn edit(&mut self, client_id: &str) -> WorldEdit<'_> {
+        WorldEdit::new(self, client_id)
+    }
+
+    /// Put back the voxels a source changed inside an area since a point in time, the way they were
+    /// before its first change to each. Needs `config.record_history`. Returns the ID of the edit
+    /// doing so, which is recorded for `SYSTEM_SOURCE`, or `None` if there is nothing to roll back.
+    pub fn rollback(
+        &mut self,
+        source: &str,
+        area: &RegionArea,
+        since: SystemTime,
+    ) -> Option<usize> {
+        let mut restored: Vec<VoxelUpdate> = vec![];
+        let mut seen = HashSet::new();
+
+        for entry in self.history().in_area(source, area, since) {
+            if seen.insert(entry.voxel.to_owned()) {
+                restored.push((entry.voxel, entry.old));
+            }
+        }
+
+        if restored.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.write_resource::<Edits>()
+                .queue(SYSTEM_SOURCE, EditKind::Edit, restored),
+        )
+    }
+
+    /// Reload the block files of the registry while the world runs, see `Registry::reload`. The new
+    /// blocks are sent to every client as a `REGISTRY_EVENT`, and the loaded chunks holding changed
+    /// blocks are meshed again. Voxels of blocks taken out of the files become `config.fallback_block`,
+    /// and chunks within light reach of blocks that let light through or give it off differently are
+    /// relit and sent again. Call it from a command handler once the sender is known to be allowed.
+    /// Returns the number of chunks remeshed.
+    pub fn reload_registry(&mut self) -> io::Result<usize> {
+        let old = Registry::clone(&self.registry());
+        let mut registry = old.clone();
+        let changed = registry.reload()?;
+
+        self.check_block_ids(&registry)?;
+        self.ecs.insert(registry);
+
+        let registry = self.ecs.read_resource::<Registry>();
+        let config = self.ecs.read_resource::<WorldConfig>();
+
+        self.ecs.write_resource::<Events>().dispatch(
+            Event::new(REGISTRY_EVENT)
+                .payload(json!({
+                    "blocks": registry.blocks_by_name,
+                    "states": registry.get_state_table(),
+                }))
+                .build(),
+        );
+
+        let removed: HashSet<u32> = old
+            .blocks_by_id
+            .keys()
+            .filter(|&&id| !registry.has_type(id))
+            .copied()
+            .collect();
+
+        let relit: HashSet<u32> = changed
+            .iter()
+            .filter(|&&id| {
+                old.blocks_by_id
+                    .get(&id)
+                    .is_some_and(|block| block.lights_differently(registry.get_block_by_id(id)))
+            })
+            .chain(removed.iter())
+            .copied()
+            .collect();
+
+        // Empty blocks such as air are never meshed, but can still change how light spreads.
+        let remeshed: HashSet<u32> = changed
+            .into_iter()
+            .filter(|&id| !registry.get_block_by_id(id).is_empty)
+            .chain(relit.iter().copied())
+            .collect();
+
+        if remeshed.is_empty() {
+            return Ok(0);
+        }
+
+        let mut chunks = self.ecs.write_resource::<Chunks>();
+        let mut mesher = self.ecs.write_resource::<Mesher>();
+
+        let fallback = chunks.fallback_id(&registry);
+        let ready: Vec<Vec2<i32>> = chunks
+            .map
+            .keys()
+            .filter(|coords| chunks.is_chunk_ready(coords))
+            .cloned()
+            .collect();
+
+        let mut affected = HashSet::new();
+        let mut unlit = HashSet::new();
+
+        for coords in ready {
+            let chunk = chunks.raw_mut(&coords).unwrap();
+            let mut voxels = chunk.raw_voxels();
+            let ids: HashSet<u32> = voxels
+                .iter()
+                .map(|&voxel| BlockUtils::extract_id(voxel))
+                .collect();
+
+            if ids.is_disjoint(&remeshed) {
+                continue;
+            }
+
+            if !ids.is_disjoint(&removed) {
+                voxels
+                    .iter_mut()
+                    .filter(|voxel| removed.contains(&BlockUtils::extract_id(**voxel)))
+                    .for_each(|voxel| *voxel = BlockUtils::insert_wide_id(0, fallback));
+
+                chunk.assign_raw_voxels(&voxels);
+                chunk.calculate_max_height(&registry);
+
+                if config.saving {
+                    chunks.add_chunk_to_save(&coords, false);
+                }
+            }
+
+            if !ids.is_disjoint(&relit) {
+                unlit.extend(chunks.light_traversed_chunks(&coords));
+            }
+
+            affected.insert(coords);
+        }
+
+        if !removed.is_empty() {
+            warn!(
+                "World {:?} replaced the voxels of unregistered blocks {:?} with {:?}.",
+                self.name, removed, config.fallback_block
+            );
+        }
+
+        // Chunks without lights are lit again by the mesher, then sent to the clients.
+        for coords in unlit {
+            if chunks.is_chunk_ready(&coords) {
+                chunks.raw_mut(&coords).unwrap().has_lights = false;
+                affected.insert(coords);
+            }
+        }
+
+        let processes = affected
+            .iter()
+            .map(|coords| {
+                let chunk = chunks.raw_mut(coords).unwrap();
+                chunk.updated_levels = (0..config.sub_chunks as u32).collect();
+                let chunk = chunk.to_owned();
+
+                let mut space = chunks
+                    .make_space(coords, config.max_light_level as usize)
+                    .needs_height_maps()
+                    .needs_voxels();
+
+                if chunk.has_lights {
+                    space = space.needs_lights();
+                }
+
+                (chunk, space.build())
+            })
+            .collect::<Vec<_>>();
+
+        mesher.process(processes, &MessageType::Update, &registry, &config);
+
+        info!(
+            "World {:?} reloaded its registry, remeshing {} chunks.",
+            self.name,
+            affected.len()
+        );
+
+        Ok(affected.len())
+    }
+
+    /// The storage of this world, or an error if it is not saving.
+    fn saving_storage(&self) -> io::Result<Arc<dyn WorldStorage>> {
+        self.storage().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("World {:?} is not saving.", self.name),
+            )
+        })
+    }
+
+    /// Replace the saved world metadata, such as stats and stale lights, with that of a snapshot.
+    fn restore_snapshot_metadata(
+        &mut self,
+        snapshot: &dyn WorldStorage,
+        storage: &dyn WorldStorage,
+    ) -> io::Result<()> {
+        let keys: HashSet<String> = snapshot
+            .list_metadata()?
+            .into_iter()
+            .filter(|key| key != SNAPSHOT_INFO_KEY)
+            .collect();
+
+        for key in storage.list_metadata()? {
+            if !keys.contains(&key) && !key.starts_with("quarantine.") {
+                storage.delete_metadata(&key)?;
+            }
+        }
+
+        for key in &keys {
+            if let Some(data) = snapshot.load_metadata(key)? {
+                storage.save_metadata(key, &data)?;
+            }
+        }
+
+        let storage = self.storage();
+        self.chunks_mut().set_storage(storage.clone());
+        self.regions_mut().set_storage(storage);
+
+        if let Some(stats) = snapshot
+            .load_metadata(STATS_KEY)?
+            .and_then(|data| serde_json::from_slice::<StatsJson>(&data).ok())
+        {
+            self.stats_mut().set_time(stats.time);
+        }
+
+        Ok(())
+    }
+
+    /// Replace the saved entities with those of a snapshot, and respawn the entities in the world.
+    fn restore_snapshot_entities(
+        &mut self,
+        snapshot: &dyn WorldStorage,
+        storage: &dyn WorldStorage,
+    ) -> io::Result<()> {
+        let ids: HashSet<String> = snapshot.list_entities()?.into_iter().collect();
+
+        for id in storage.list_entities()? {
+            if !ids.contains(&id) {
+                storage.delete_entity(&id)?;
+            }
+        }
+
+        let mut records = vec![];
+
+        for id in &ids {
+            if let Some(data) = snapshot.load_entity(id)? {
+                storage.save_entity(id, &data)?;
+                records.push((id.to_owned(), data));
+            }
+        }
+
+        let live: Vec<(Entity, String)> = {
+            let entities = self.ecs.entities();
+            let id_comps = self.read_component::<IDComp>();
+            let flags = self.read_component::<EntityFlag>();
+
+            (&entities, &id_comps, &flags)
+                .join()
+                .map(|(ent, id, _)| (ent, id.0.to_owned()))
+                .collect()
+        };
+
+        // Entities that come back are revived under the same ID, so the entity sending system never
+        // sees them go and only their physics bodies need to be cleaned up here. The others are
+        // announced as deleted by the sending system.
+        {
+            let mut physics = self.physics_mut();
+
+            for (ent, id) in &live {
+                if !ids.contains(id) {
+                    continue;
+                }
+
+                if let Some((collider, body)) = physics.entity_to_handlers.remove(ent) {
+                    physics.unregister(&body, &collider);
+                }
+            }
+        }
+
+        {
+            let despawned: HashSet<Entity> = live.iter().map(|(ent, _)| *ent).collect();
+            self.chunks_mut()
+                .block_entities
+                .retain(|_, ent| !despawned.contains(ent));
+        }
+
+        for (ent, _) in live {
+            if let Err(e) = self.ecs.delete_entity(ent) {
+                warn!("Could not despawn entity while restoring a snapshot: {}", e);
+            }
+        }
+
+        for (id, data) in records {
+            let mut data: HashMap<String, Value> = match serde_json::from_slice(&data) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Could not read entity {:?} from snapshot: {}", id, e);
+                    continue;
+                }
+            };
+
+            let etype = data
+                .remove("etype")
+                .and_then(|etype| serde_json::from_value::<String>(etype).ok());
+            let metadata = data
+                .remove("metadata")
+                .and_then(|metadata| serde_json::from_value::<MetadataComp>(metadata).ok());
+
+            match (etype, metadata) {
+                (Some(etype), Some(metadata)) => {
+                    if self.revive_entity(&id, &etype, metadata).is_none() {
+                        warn!("Could not revive entity {:?} from snapshot.", id);
+                    }
+                }
+                _ => warn!(
+                    "Entity {:?} in snapshot is missing its type or metadata.",
+                    id
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn set_client_modifier<F: Fn(&mut World, Entity) + Send + Sync + 'static>(
         &mut self,
         modifier: F,
@@ -783,6 +1460,134 @@ impl World {
         self.command_handle = Some(Arc::new(handle));
     }
 
+    /// Set a validator for the voxel updates clients send, which runs after the checks configured by
+    /// `max_update_reach`, `max_updates_per_second` and `allowed_update_blocks`. Rejected updates
+    /// are reverted on the client that sent them.
+    pub fn set_update_validator<
+        F: Fn(&World, &str, &VoxelUpdate) -> bool + Send + Sync + 'static,
+    >(
+        &mut self,
+        validator: F,
+    ) {
+        self.update_validator = Some(Arc::new(validator));
+    }
+
+    /// Check whether a client is allowed to make a voxel update, see `set_update_validator`. Every
+    /// update checked counts towards the rate limit of the client.
+    pub fn validate_update(&mut self, client_id: &str, update: &VoxelUpdate) -> bool {
+        let config = WorldConfig::clone(&self.config());
+        let position = self.client_position(client_id);
+
+        self.check_update(client_id, update, &config, position.as_ref())
+    }
+
+    /// The checks of `validate_update`, with the config and the position of the client looked up
+    /// once per message by the caller instead of once per voxel.
+    fn check_update(
+        &mut self,
+        client_id: &str,
+        update: &VoxelUpdate,
+        config: &WorldConfig,
+        position: Option<&Vec3<f32>>,
+    ) -> bool {
+        let (voxel, raw) = update;
+
+        if !config.allowed_update_blocks.is_empty()
+            && !config
+                .allowed_update_blocks
+                .contains(&BlockUtils::extract_id(*raw))
+        {
+            return false;
+        }
+
+        if config.max_update_reach > 0.0 {
+            let within_reach = position.is_some_and(|Vec3(px, py, pz)| {
+                let dx = voxel.0 as f32 + 0.5 - px;
+                let dy = voxel.1 as f32 + 0.5 - py;
+                let dz = voxel.2 as f32 + 0.5 - pz;
+
+                (dx * dx + dy * dy + dz * dz).sqrt() <= config.max_update_reach
+            });
+
+            if !within_reach {
+                return false;
+            }
+        }
+
+        let old_id = self.chunks().get_voxel(voxel.0, voxel.1, voxel.2);
+        let new_id = BlockUtils::extract_id(*raw);
+
+        {
+            let registry = self.registry();
+            let regions = self.regions();
+
+            // Blocks that are not registered count as solid.
+            let is_empty = |id| registry.has_type(id) && registry.get_emptiness_by_id(id);
+
+            // Changing the state or rotation of a block, like opening a door, is interacting with it.
+            let allowed = if old_id == new_id {
+                regions.is_allowed(voxel, RegionFlag::Interact, Some(client_id))
+            } else {
+                (is_empty(old_id) || regions.is_allowed(voxel, RegionFlag::Break, Some(client_id)))
+                    && (is_empty(new_id)
+                        || regions.is_allowed(voxel, RegionFlag::Build, Some(client_id)))
+            };
+
+            if !allowed {
+                return false;
+            }
+        }
+
+        if let Some(validator) = self.update_validator.to_owned() {
+            if !validator(self, client_id, update) {
+                return false;
+            }
+        }
+
+        // Only updates that pass every other check count towards the rate limit.
+        if config.max_updates_per_second > 0 {
+            let now = Instant::now();
+            let (start, count) = self
+                .update_rates
+                .entry(client_id.to_owned())
+                .or_insert((now, 0));
+
+            if now.duration_since(*start) >= Duration::from_secs(1) {
+                *start = now;
+                *count = 0;
+            }
+
+            *count += 1;
+
+            if *count > config.max_updates_per_second {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Whether a client is allowed a region flag where it stands. Clients that are nowhere are not.
+    pub fn is_client_allowed(&self, client_id: &str, flag: RegionFlag) -> bool {
+        self.client_position(client_id)
+            .is_some_and(|Vec3(px, py, pz)| {
+                let voxel = Vec3(px.floor() as i32, py.floor() as i32, pz.floor() as i32);
+                self.regions().is_allowed(&voxel, flag, Some(client_id))
+            })
+    }
+
+    /// Position of a client, if it is in this world.
+    fn client_position(&self, client_id: &str) -> Option<Vec3<f32>> {
+        let ids = self.read_component::<IDComp>();
+        let positions = self.read_component::<PositionComp>();
+        let flags = self.read_component::<ClientFlag>();
+
+        (&ids, &positions, &flags)
+            .join()
+            .find(|(id, _, _)| id.0 == client_id)
+            .map(|(_, position, _)| position.0.to_owned())
+    }
+
     pub fn set_entity_loader<
         F: Fn(&mut World, MetadataComp) -> EntityBuilder + Send + Sync + 'static,
     >(
@@ -945,6 +1750,26 @@ impl World {
         self.write_resource::<Mesher>()
     }
 
+    /// Access the protected regions of this world.
+    pub fn regions(&self) -> Fetch<'_, Regions> {
+        self.read_resource::<Regions>()
+    }
+
+    /// Access a mutable reference to the protected regions of this world.
+    pub fn regions_mut(&mut self) -> FetchMut<'_, Regions> {
+        self.write_resource::<Regions>()
+    }
+
+    /// Access the voxel history of this world.
+    pub fn history(&self) -> Fetch<'_, History> {
+        self.read_resource::<History>()
+    }
+
+    /// Access a mutable reference to the voxel history of this world.
+    pub fn history_mut(&mut self) -> FetchMut<'_, History> {
+        self.write_resource::<History>()
+    }
+
     /// Create a basic entity ready to be added more.
     pub fn create_base_entity(&mut self, id: &str, etype: &str) -> EntityBuilder {
         self.ecs_mut()
@@ -975,6 +1800,20 @@ impl World {
             return None;
         }
 
+        let voxel = Vec3(
+            position.0.floor() as i32,
+            position.1.floor() as i32,
+            position.2.floor() as i32,
+        );
+
+        if !self
+            .regions()
+            .is_allowed(&voxel, RegionFlag::EntitySpawning, None)
+        {
+            warn!("Tried to spawn {} inside a protected region.", etype);
+            return None;
+        }
+
         let loader = self
             .entity_loaders
             .get(&etype.to_lowercase())
@@ -1078,6 +1917,10 @@ impl World {
 
     /// Prepare to start.
     pub(crate) fn prepare(&mut self) {
+        if let Err(e) = self.check_block_ids(&self.registry()) {
+            panic!("{}", e);
+        }
+
         // Merge consecutive chunk stages that don't require spaces together.
         self.pipeline_mut().merge_stages();
         self.load_entities();
@@ -1093,6 +1936,47 @@ impl World {
         }
     }
 
+    /// Make sure every block ID and block state of a registry fits in the voxels of this world.
+    fn check_block_ids(&self, registry: &Registry) -> io::Result<()> {
+        let encoding = self.config().voxel_encoding;
+
+        if let Some(block) = registry
+            .blocks_by_id
+            .values()
+            .find(|block| block.id > encoding.max_id())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Block {:?} has ID {}, more than the {} that {:?} voxels can hold. Use `VoxelEncoding::Wide`.",
+                    block.name,
+                    block.id,
+                    encoding.max_id(),
+                    encoding
+                ),
+            ));
+        }
+
+        if let Some((id, states)) = registry
+            .get_state_table()
+            .iter()
+            .find(|(_, states)| states.len() > encoding.max_states())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Block {:?} has {} states, more than the {} that {:?} voxels can hold. Use `VoxelEncoding::Wide`.",
+                    registry.get_block_by_id(*id).name,
+                    states.len(),
+                    encoding.max_states(),
+                    encoding
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Preload the chunks in the world.
     pub(crate) fn preload(&mut self) {
         let radius = self.config().preload_radius as i32;
@@ -1306,20 +2190,71 @@ impl World {
     }
 
     /// Handler for `Update` type messages.
-    fn on_update(&mut self, _: &str, data: Message) {
-        let chunk_size = self.config().chunk_size;
-        let mut chunks = self.chunks_mut();
+    fn on_update(&mut self, client_id: &str, data: Message) {
+        let config = WorldConfig::clone(&self.config());
+        let position = self.client_position(client_id);
+        let chunk_size = config.chunk_size;
+        let wide = config.voxel_encoding == VoxelEncoding::Wide;
 
-        data.updates.into_iter().for_each(|update| {
+        let mut accepted = vec![];
+        let mut rejected = vec![];
+
+        for update in data.updates {
             let coords =
                 ChunkUtils::map_voxel_to_chunk(update.vx, update.vy, update.vz, chunk_size);
 
-            if !chunks.is_within_world(&coords) {
-                return;
+            if !self.chunks().is_within_world(&coords) {
+                continue;
             }
 
-            chunks.update_voxel(&Vec3(update.vx, update.vy, update.vz), update.voxel);
-        });
+            // Packed worlds have no upper bits, whatever the client sent.
+            let extra = if wide { update.extra } else { 0 };
+            let update = (
+                Vec3(update.vx, update.vy, update.vz),
+                BlockUtils::join(update.voxel, extra),
+            );
+
+            if self.check_update(client_id, &update, &config, position.as_ref()) {
+                accepted.push(update);
+            } else {
+                rejected.push(update.0);
+            }
+        }
+
+        if !accepted.is_empty() {
+            self.chunks_mut().update_voxels_as(client_id, &accepted);
+        }
+
+        if rejected.is_empty() {
+            return;
+        }
+
+        warn!(
+            "Rejected {} voxel updates from client {}.",
+            rejected.len(),
+            client_id
+        );
+
+        // Put the voxels back the way they are on the client that changed them.
+        let reverts = {
+            let chunks = self.chunks();
+
+            rejected
+                .into_iter()
+                .map(|Vec3(vx, vy, vz)| UpdateProtocol {
+                    vx,
+                    vy,
+                    vz,
+                    voxel: chunks.get_raw_voxel(vx, vy, vz),
+                    light: chunks.get_raw_light(vx, vy, vz),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        self.broadcast(
+            Message::new(&MessageType::Update).updates(&reverts).build(),
+            ClientFilter::Direct(client_id.to_owned()),
+        );
     }
 
     /// Handler for `Method` type messages.
@@ -1333,6 +2268,20 @@ impl World {
                 return;
             }
 
+            let flag = self
+                .regions()
+                .methods
+                .get(&method.name.to_lowercase())
+                .copied();
+
+            if flag.is_some_and(|flag| !self.is_client_allowed(client_id, flag)) {
+                warn!(
+                    "Client {} called method {} inside a protected region.",
+                    client_id, method.name
+                );
+                return;
+            }
+
             let handle = self.method_handles.get(&method.name).unwrap().to_owned();
 
             handle(self, client_id, &method.payload);
@@ -1348,6 +2297,20 @@ impl World {
         };
 
         data.events.into_iter().for_each(|event| {
+            let flag = self
+                .regions()
+                .events
+                .get(&event.name.to_lowercase())
+                .copied();
+
+            if flag.is_some_and(|flag| !self.is_client_allowed(client_id, flag)) {
+                warn!(
+                    "Client {} sent event {} inside a protected region.",
+                    client_id, event.name
+                );
+                return;
+            }
+
             if !self.event_handles.contains_key(&event.name.to_lowercase()) {
                 let curr_chunk = self
                     .read_component::<CurrentChunkComp>()
@@ -1394,63 +2357,93 @@ impl World {
 
     /// Load existing entities.
     fn load_entities(&mut self) {
-        if self.config().saving {
-            // TODO: THIS FEELS HACKY
-
-            let paths = fs::read_dir(self.read_resource::<EntitiesSaver>().folder.clone()).unwrap();
-            let mut loaded_entities = HashMap::new();
-
-            for path in paths {
-                let path = path.unwrap().path();
-
-                if let Ok(entity_data) = File::open(&path) {
-                    let id = path.file_stem().unwrap().to_str().unwrap().to_owned();
-                    let mut data: HashMap<String, Value> =
-                        match serde_json::from_reader(entity_data) {
-                            Ok(data) => data,
-                            Err(e) => {
-                                info!(
-                                    "Could not load entity file: {:?}. Error: {}, removing...",
-                                    path, e
-                                );
-                                // remove the file
-                                fs::remove_file(path).unwrap();
-                                continue;
-                            }
-                        };
-                    let etype: String = serde_json::from_value(data.remove("etype").unwrap())
-                        .unwrap_or_else(|_| {
-                            panic!("EType filed does not exist on file: {:?}", path)
-                        });
-                    let metadata: MetadataComp =
-                        serde_json::from_value(data.remove("metadata").unwrap()).unwrap_or_else(
-                            |_| panic!("Metadata field does not exist on file: {:?}", path),
-                        );
-
-                    if let Some(ent) = self.revive_entity(&id, &etype, metadata.to_owned()) {
-                        loaded_entities.insert(id.to_owned(), (etype, ent, metadata));
-                    } else {
-                        info!(
-                            "Failed to revive block entity {:?} of type {}, removing...",
-                            id, etype
-                        );
-                        // remove the file
-                        fs::remove_file(path).unwrap();
-                    }
-                }
+        if !self.config().saving {
+            return;
+        }
+
+        let storage = if let Some(storage) = self.storage() {
+            storage
+        } else {
+            return;
+        };
+
+        let ids = match storage.list_entities() {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!("Could not list saved entities: {}", e);
+                return;
             }
+        };
+
+        let mut loaded_entities = HashMap::new();
 
-            if !loaded_entities.is_empty() {
-                let name = self.name.to_owned();
-                let mut bookkeeping = self.write_resource::<Bookkeeping>();
+        for id in ids {
+            let bytes = match storage.load_entity(&id) {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Could not read entity {:?} from storage: {}", id, e);
+                    continue;
+                }
+            };
+
+            let mut data: HashMap<String, Value> = match serde_json::from_slice(&bytes) {
+                Ok(data) => data,
+                Err(e) => {
+                    info!(
+                        "Could not load entity file: {:?}. Error: {}, removing...",
+                        id, e
+                    );
+                    storage
+                        .delete_entity(&id)
+                        .expect("Unable to remove entity file.");
+                    continue;
+                }
+            };
+            let etype: String = match data.remove("etype").map(serde_json::from_value) {
+                Some(Ok(etype)) => etype,
+                _ => {
+                    warn!(
+                        "EType field does not exist on entity: {:?}, skipping...",
+                        id
+                    );
+                    continue;
+                }
+            };
+            let metadata: MetadataComp = match data.remove("metadata").map(serde_json::from_value) {
+                Some(Ok(metadata)) => metadata,
+                _ => {
+                    warn!(
+                        "Metadata field does not exist on entity: {:?}, skipping...",
+                        id
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(ent) = self.revive_entity(&id, &etype, metadata.to_owned()) {
+                loaded_entities.insert(id.to_owned(), (etype, ent, metadata));
+            } else {
                 info!(
-                    "World {:?} loaded {} entities from disk.",
-                    name,
-                    loaded_entities.len()
+                    "Failed to revive block entity {:?} of type {}, removing...",
+                    id, etype
                 );
-                bookkeeping.entities = loaded_entities;
+                storage
+                    .delete_entity(&id)
+                    .expect("Unable to remove entity file.");
             }
         }
+
+        if !loaded_entities.is_empty() {
+            let name = self.name.to_owned();
+            let mut bookkeeping = self.write_resource::<Bookkeeping>();
+            info!(
+                "World {:?} loaded {} entities from disk.",
+                name,
+                loaded_entities.len()
+            );
+            bookkeeping.entities = loaded_entities;
+        }
     }
 
     fn generate_init_message(&self, id: &str) -> Message {
@@ -1459,6 +2452,10 @@ impl World {
 
         json.insert("id".to_owned(), json!(id));
         json.insert("blocks".to_owned(), json!(self.registry().blocks_by_name));
+        json.insert(
+            "states".to_owned(),
+            json!(self.registry().get_state_table()),
+        );
         json.insert("options".to_owned(), json!(config));
         json.insert(
             "stats".to_owned(),
@@ -0,0 +1,362 @@
+use std::{
+    collections::VecDeque,
+    io,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use hashbrown::HashSet;
+use specs::{DispatcherBuilder, WorldExt};
+
+use crate::{
+    ChunkGeneratingSystem, ChunkStatus, Chunks, Mesher, Pipeline, Registry, Vec2, WorldConfig,
+    WorldConfigBuilder,
+};
+
+use super::World;
+
+/// Number of target chunks generated at once, which bounds how much of the world is in memory.
+const PREGEN_BATCH_SIZE: usize = 256;
+
+/// How long pre-generation waits without any chunk making progress before giving up.
+const PREGEN_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An area of chunks to pre-generate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PregenArea {
+    /// A square of chunks around a center chunk, `radius` chunks out in every direction.
+    Radius(Vec2<i32>, i32),
+
+    /// A rectangle of chunks between two inclusive corner chunks.
+    Rect(Vec2<i32>, Vec2<i32>),
+}
+
+impl PregenArea {
+    /// List the chunks in this area, row by row.
+    pub fn chunks(&self) -> Vec<Vec2<i32>> {
+        let (min, max) = match self {
+            Self::Radius(center, radius) => (
+                Vec2(center.0 - radius, center.1 - radius),
+                Vec2(center.0 + radius, center.1 + radius),
+            ),
+            Self::Rect(a, b) => (
+                Vec2(a.0.min(b.0), a.1.min(b.1)),
+                Vec2(a.0.max(b.0), a.1.max(b.1)),
+            ),
+        };
+
+        let mut list = vec![];
+
+        for z in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                list.push(Vec2(x, z));
+            }
+        }
+
+        list
+    }
+}
+
+/// Arguments of a headless pre-generation binary:
+///
+/// - `--config <file>` reads the world config from a JSON or TOML file, see
+///   `WorldConfigBuilder::from_file`.
+/// - `--save-dir <dir>` saves the world there instead of the `saveDir` of the config file.
+/// - `--radius <r>` pre-generates the chunks within `r` chunks of the origin.
+/// - `--rect <x1> <z1> <x2> <z2>` pre-generates a rectangle of chunks instead.
+/// - `--mesh` also runs the mesher on the generated chunks. Meshes are never saved, so this only
+///   checks that every chunk meshes.
+#[derive(Debug, Clone)]
+pub struct PregenArgs {
+    /// Path of the world config file.
+    pub config: PathBuf,
+
+    /// Where to save the world, if not where the config file says.
+    pub save_dir: Option<String>,
+
+    /// The chunks to pre-generate.
+    pub area: PregenArea,
+
+    /// Whether chunks are meshed as well as lit.
+    pub mesh: bool,
+}
+
+impl PregenArgs {
+    /// Usage of the pre-generation arguments.
+    pub const USAGE: &'static str =
+        "--config <file> [--save-dir <dir>] (--radius <r> | --rect <x1> <z1> <x2> <z2>) [--mesh]";
+
+    /// Parse the arguments of the running binary.
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parse a list of arguments, not including the name of the binary.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut args = args.into_iter();
+
+        let mut config = None;
+        let mut save_dir = None;
+        let mut area = None;
+        let mut mesh = false;
+
+        let next_number = |args: &mut I::IntoIter, flag: &str| -> Result<i32, String> {
+            args.next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("`{}` expects whole numbers.", flag))
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    config = Some(PathBuf::from(
+                        args.next().ok_or("`--config` expects a file.")?,
+                    ));
+                }
+                "--save-dir" => {
+                    save_dir = Some(args.next().ok_or("`--save-dir` expects a folder.")?);
+                }
+                "--radius" => {
+                    let radius = next_number(&mut args, "--radius")?;
+
+                    if radius < 0 {
+                        return Err("`--radius` cannot be negative.".to_owned());
+                    }
+
+                    area = Some(PregenArea::Radius(Vec2(0, 0), radius));
+                }
+                "--rect" => {
+                    let x1 = next_number(&mut args, "--rect")?;
+                    let z1 = next_number(&mut args, "--rect")?;
+                    let x2 = next_number(&mut args, "--rect")?;
+                    let z2 = next_number(&mut args, "--rect")?;
+
+                    area = Some(PregenArea::Rect(Vec2(x1, z1), Vec2(x2, z2)));
+                }
+                "--mesh" => mesh = true,
+                other => return Err(format!("Unknown argument `{}`.", other)),
+            }
+        }
+
+        Ok(Self {
+            config: config.ok_or("`--config` is required.")?,
+            save_dir,
+            area: area.ok_or("Either `--radius` or `--rect` is required.")?,
+            mesh,
+        })
+    }
+
+    /// Read the world config file, set up to save into the save directory.
+    pub fn world_config(&self) -> io::Result<WorldConfig> {
+        let mut builder = WorldConfigBuilder::from_file(&self.config)?.saving(true);
+
+        if let Some(save_dir) = &self.save_dir {
+            builder = builder.save_dir(save_dir);
+        }
+
+        let config = builder.build();
+
+        if config.save_dir.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "World config file {:?} has no `saveDir`, and `--save-dir` was not given.",
+                    self.config
+                ),
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+impl World {
+    /// Generate and light an area of chunks and save them to storage, without starting a server.
+    /// Only the chunk pipeline and the mesher run, a batch of chunks at a time, and chunks are
+    /// unloaded once nothing left to generate needs them. Meshes are only built if `mesh` is true,
+    /// and are never saved. `progress` is called with the number of chunks done and the total.
+    /// Blocks until done, and returns the number of chunks generated, or an error if no chunk makes
+    /// progress for a minute.
+    pub fn pregenerate<F: FnMut(usize, usize)>(
+        &mut self,
+        registry: &Registry,
+        area: &PregenArea,
+        mesh: bool,
+        mut progress: F,
+    ) -> io::Result<usize> {
+        self.storage().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "World {:?} is not saving, nothing to pre-generate into.",
+                    self.name
+                ),
+            )
+        })?;
+
+        self.ecs_mut().insert(registry.clone());
+        self.pipeline_mut().merge_stages();
+        self.mesher_mut().lights_only = !mesh;
+
+        let mut pending: VecDeque<Vec2<i32>> = {
+            let chunks = self.chunks();
+            area.chunks()
+                .into_iter()
+                .filter(|coords| chunks.is_within_world(coords))
+                .collect()
+        };
+        let targets: HashSet<Vec2<i32>> = pending.iter().cloned().collect();
+        let total = targets.len();
+
+        // Chunks are only lit once their neighbors are generated, and those neighbors in turn may
+        // need theirs for stages with margins, so two rings of light range are generated around each.
+        let padding = 2
+            * (self.config().max_light_level as f32 / self.config().chunk_size as f32).ceil()
+                as i32;
+
+        let mut in_flight = HashSet::new();
+        let mut done = HashSet::new();
+
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(ChunkGeneratingSystem, "chunk-generation", &[])
+            .build();
+
+        progress(0, total);
+
+        // Anything that changes in the loaded chunks counts as progress.
+        let mut last_state = (0, 0, 0);
+        let mut last_progress = Instant::now();
+
+        while done.len() < total {
+            while in_flight.len() < PREGEN_BATCH_SIZE {
+                let coords = match pending.pop_front() {
+                    Some(coords) => coords,
+                    None => break,
+                };
+
+                let chunks = self.ecs.read_resource::<Chunks>();
+                let mut pipeline = self.ecs.write_resource::<Pipeline>();
+
+                for x in -padding..=padding {
+                    for z in -padding..=padding {
+                        let n_coords = Vec2(coords.0 + x, coords.1 + z);
+
+                        if chunks.is_within_world(&n_coords) && !chunks.map.contains_key(&n_coords)
+                        {
+                            pipeline.add_chunk(&n_coords, false);
+                        }
+                    }
+                }
+
+                in_flight.insert(coords);
+            }
+
+            dispatcher.dispatch(&self.ecs);
+            self.ecs.maintain();
+
+            let count = done.len();
+
+            {
+                let chunks = self.ecs.read_resource::<Chunks>();
+                let mut mesher = self.ecs.write_resource::<Mesher>();
+
+                in_flight.retain(|coords| {
+                    if chunks.is_chunk_ready(coords) {
+                        done.insert(coords.to_owned());
+                        return false;
+                    }
+
+                    // Meshing gives up on chunks whose neighbors are missing, so retry them.
+                    if let Some(chunk) = chunks.raw(coords) {
+                        if chunk.status == ChunkStatus::Meshing && !mesher.has_chunk(coords) {
+                            mesher.add_chunk(coords, false);
+                        }
+                    }
+
+                    true
+                });
+            }
+
+            if done.len() > count {
+                self.unload_pregenerated(registry, &targets, &done);
+                progress(done.len(), total);
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let state = {
+                let chunks = self.ecs.read_resource::<Chunks>();
+                let meshing = chunks
+                    .map
+                    .values()
+                    .filter(|chunk| chunk.status == ChunkStatus::Meshing)
+                    .count();
+
+                (done.len(), chunks.map.len(), meshing)
+            };
+
+            if state != last_state {
+                last_state = state;
+                last_progress = Instant::now();
+            } else if last_progress.elapsed() > PREGEN_STALL_TIMEOUT {
+                self.flush();
+                self.mesher_mut().lights_only = false;
+
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "Pre-generation of world {:?} stalled with {} of {} chunks done, {:?} never became ready.",
+                        self.name,
+                        done.len(),
+                        total,
+                        in_flight.iter().take(8).collect::<Vec<_>>()
+                    ),
+                ));
+            }
+        }
+
+        self.flush();
+        self.mesher_mut().lights_only = false;
+
+        Ok(total)
+    }
+
+    /// Save and unload the chunks that no chunk left to pre-generate could still need.
+    fn unload_pregenerated(
+        &mut self,
+        registry: &Registry,
+        targets: &HashSet<Vec2<i32>>,
+        done: &HashSet<Vec2<i32>>,
+    ) {
+        let pipeline = self.ecs.read_resource::<Pipeline>();
+        let mesher = self.ecs.read_resource::<Mesher>();
+        let mut chunks = self.ecs.write_resource::<Chunks>();
+
+        let to_unload: Vec<Vec2<i32>> = chunks
+            .map
+            .iter()
+            .filter(|(coords, chunk)| {
+                chunk.status == ChunkStatus::Ready
+                    && !pipeline.has_chunk(coords)
+                    && !mesher.has_chunk(coords)
+                    && chunks
+                        .light_traversed_chunks(coords)
+                        .iter()
+                        .all(|n_coords| !targets.contains(n_coords) || done.contains(n_coords))
+            })
+            .map(|(coords, _)| coords.to_owned())
+            .collect();
+
+        for coords in to_unload {
+            if chunks.to_save.contains(&coords) && !chunks.save(&coords, registry) {
+                continue;
+            }
+
+            chunks.unload(&coords);
+        }
+
+        chunks.save_stale_lights();
+        chunks.to_send.clear();
+    }
+}
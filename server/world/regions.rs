@@ -0,0 +1,381 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use hashbrown::HashMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{ChunkUtils, Vec2, Vec3, WorldStorage};
+
+/// The metadata key the regions are saved under.
+pub const REGIONS_KEY: &str = "regions.json";
+
+/// Something a region can allow or deny to those who are not its owner or members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegionFlag {
+    /// Placing blocks.
+    Build,
+
+    /// Breaking blocks.
+    Break,
+
+    /// Changing the state or rotation of blocks, and using the methods and events protected with
+    /// this flag.
+    Interact,
+
+    /// Spawning entities through `World::spawn_entity_at`.
+    EntitySpawning,
+
+    /// Fighting other clients. Not enforced by the server, ask `Regions::is_allowed` about it.
+    Pvp,
+}
+
+/// The space a region takes up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum RegionArea {
+    /// Every voxel from `min` to `max`, inclusive. The corners can be given in any order.
+    Box { min: Vec3<i32>, max: Vec3<i32> },
+
+    /// Every voxel in a set of chunks, from the bottom to the top of the world.
+    Chunks { chunks: Vec<Vec2<i32>> },
+}
+
+impl RegionArea {
+    /// Whether a voxel is inside this area.
+    pub fn contains(&self, voxel: &Vec3<i32>, chunk_size: usize) -> bool {
+        match self {
+            Self::Box { min, max } => {
+                let (min, max) = Self::corners(min, max);
+
+                (min.0..=max.0).contains(&voxel.0)
+                    && (min.1..=max.1).contains(&voxel.1)
+                    && (min.2..=max.2).contains(&voxel.2)
+            }
+            Self::Chunks { chunks } => chunks.contains(&ChunkUtils::map_voxel_to_chunk(
+                voxel.0, voxel.1, voxel.2, chunk_size,
+            )),
+        }
+    }
+
+    /// The coordinates of the chunks this area touches.
+    pub fn chunks(&self, chunk_size: usize) -> Vec<Vec2<i32>> {
+        match self {
+            Self::Box { min, max } => {
+                let (min, max) = Self::corners(min, max);
+                let Vec2(min_cx, min_cz) =
+                    ChunkUtils::map_voxel_to_chunk(min.0, min.1, min.2, chunk_size);
+                let Vec2(max_cx, max_cz) =
+                    ChunkUtils::map_voxel_to_chunk(max.0, max.1, max.2, chunk_size);
+
+                (min_cx..=max_cx)
+                    .flat_map(|cx| (min_cz..=max_cz).map(move |cz| Vec2(cx, cz)))
+                    .collect()
+            }
+            Self::Chunks { chunks } => chunks.to_owned(),
+        }
+    }
+
+    fn corners(min: &Vec3<i32>, max: &Vec3<i32>) -> (Vec3<i32>, Vec3<i32>) {
+        (
+            Vec3(min.0.min(max.0), min.1.min(max.1), min.2.min(max.2)),
+            Vec3(min.0.max(max.0), min.1.max(max.1), min.2.max(max.2)),
+        )
+    }
+}
+
+/// A named part of the world with an owner, members, and flags for what everyone else may do there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    /// Name of the region, unique within the world.
+    pub name: String,
+
+    /// The space this region takes up.
+    pub area: RegionArea,
+
+    /// ID of the client that owns this region, if any.
+    pub owner: Option<String>,
+
+    /// IDs of the clients that may do anything here, along with the owner.
+    pub members: Vec<String>,
+
+    /// What everyone else may do here. Flags that are not set are allowed.
+    pub flags: BTreeMap<RegionFlag, bool>,
+
+    /// Where regions overlap, only those with the highest priority apply.
+    pub priority: i32,
+}
+
+impl Region {
+    /// Create a region that allows everything, to be restricted with `flag`.
+    pub fn new(name: &str, area: RegionArea) -> Self {
+        Self {
+            name: name.to_owned(),
+            area,
+            owner: None,
+            members: vec![],
+            flags: BTreeMap::new(),
+            priority: 0,
+        }
+    }
+
+    /// Set the owner of this region.
+    pub fn owner(mut self, client_id: &str) -> Self {
+        self.owner = Some(client_id.to_owned());
+        self
+    }
+
+    /// Add a member to this region.
+    pub fn member(mut self, client_id: &str) -> Self {
+        if !self.is_member(client_id) {
+            self.members.push(client_id.to_owned());
+        }
+        self
+    }
+
+    /// Allow or deny something to those who are not the owner or members.
+    pub fn flag(mut self, flag: RegionFlag, allowed: bool) -> Self {
+        self.flags.insert(flag, allowed);
+        self
+    }
+
+    /// Set the priority of this region over the regions it overlaps.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Whether a client is the owner or a member of this region.
+    pub fn is_member(&self, client_id: &str) -> bool {
+        self.owner.as_deref() == Some(client_id) || self.members.iter().any(|id| id == client_id)
+    }
+
+    /// Whether a client, or the server if `client_id` is `None`, may do something here.
+    pub fn allows(&self, flag: RegionFlag, client_id: Option<&str>) -> bool {
+        client_id.is_some_and(|id| self.is_member(id))
+            || self.flags.get(&flag).copied().unwrap_or(true)
+    }
+}
+
+/// The protected regions of a world, indexed by the chunks they touch and saved to the world
+/// storage whenever they change.
+pub struct Regions {
+    /// Every region, name -> region.
+    regions: HashMap<String, Region>,
+
+    /// Names of the regions touching each chunk, chunk coords -> names.
+    index: HashMap<Vec2<i32>, Vec<String>>,
+
+    /// Flags the client has to be allowed where it stands to call a method, method name -> flag.
+    pub(crate) methods: HashMap<String, RegionFlag>,
+
+    /// Flags the client has to be allowed where it stands to send an event, event name -> flag.
+    pub(crate) events: HashMap<String, RegionFlag>,
+
+    chunk_size: usize,
+
+    storage: Option<Arc<dyn WorldStorage>>,
+
+    /// Whether the regions saved in the storage could not be read, in which case they are not
+    /// saved over until `overwrite_saved` is called.
+    unreadable: bool,
+}
+
+impl Regions {
+    /// Create the regions of a world, loading any saved in the storage.
+    pub fn new(chunk_size: usize, storage: Option<Arc<dyn WorldStorage>>) -> Self {
+        let mut regions = Self {
+            regions: HashMap::new(),
+            index: HashMap::new(),
+            methods: HashMap::new(),
+            events: HashMap::new(),
+            chunk_size,
+            storage: None,
+            unreadable: false,
+        };
+
+        regions.set_storage(storage);
+        regions
+    }
+
+    /// Swap out the storage that regions are saved to, replacing the regions with those saved in it.
+    /// Regions that cannot be read are left alone in the storage, see `overwrite_saved`.
+    pub fn set_storage(&mut self, storage: Option<Arc<dyn WorldStorage>>) {
+        self.unreadable = false;
+
+        let saved = storage
+            .as_ref()
+            .and_then(|storage| match storage.load_metadata(REGIONS_KEY) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Could not read regions from storage: {}", e);
+                    self.unreadable = true;
+                    None
+                }
+            })
+            .and_then(|data| match serde_json::from_slice::<Vec<Region>>(&data) {
+                Ok(saved) => Some(saved),
+                Err(e) => {
+                    warn!("Could not parse regions from storage: {}", e);
+                    self.unreadable = true;
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        self.regions.clear();
+        self.index.clear();
+
+        saved.into_iter().for_each(|region| self.insert(region));
+
+        self.storage = storage;
+    }
+
+    /// Add a region, replacing the region of the same name. Returns the replaced region.
+    pub fn add(&mut self, region: Region) -> Option<Region> {
+        let replaced = self.take(&region.name);
+        self.insert(region);
+        self.save();
+        replaced
+    }
+
+    /// Remove a region by name.
+    pub fn remove(&mut self, name: &str) -> Option<Region> {
+        let removed = self.take(name);
+
+        if removed.is_some() {
+            self.save();
+        }
+
+        removed
+    }
+
+    /// Change a region by name, such as its members or flags. Returns whether the region exists.
+    pub fn update<F: FnOnce(&mut Region)>(&mut self, name: &str, change: F) -> bool {
+        let mut region = match self.take(name) {
+            Some(region) => region,
+            None => return false,
+        };
+
+        change(&mut region);
+        region.name = name.to_owned();
+
+        self.insert(region);
+        self.save();
+        true
+    }
+
+    /// Get a region by name.
+    pub fn get(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+
+    /// Every region of this world.
+    pub fn all(&self) -> impl Iterator<Item = &Region> {
+        self.regions.values()
+    }
+
+    /// The regions a voxel is in, highest priority first.
+    pub fn at(&self, voxel: &Vec3<i32>) -> Vec<&Region> {
+        let coords = ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, self.chunk_size);
+
+        let mut regions = self
+            .index
+            .get(&coords)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| self.regions.get(name))
+                    .filter(|region| region.area.contains(voxel, self.chunk_size))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        regions.sort_by_key(|region| std::cmp::Reverse(region.priority));
+        regions
+    }
+
+    /// Whether a client, or the server if `client_id` is `None`, may do something at a voxel. Every
+    /// region of the highest priority there has to allow it.
+    pub fn is_allowed(&self, voxel: &Vec3<i32>, flag: RegionFlag, client_id: Option<&str>) -> bool {
+        let regions = self.at(voxel);
+
+        let top = match regions.first() {
+            Some(region) => region.priority,
+            None => return true,
+        };
+
+        regions
+            .into_iter()
+            .take_while(|region| region.priority == top)
+            .all(|region| region.allows(flag, client_id))
+    }
+
+    /// Only let clients call a method where they are allowed a flag.
+    pub fn protect_method(&mut self, name: &str, flag: RegionFlag) {
+        self.methods.insert(name.to_lowercase(), flag);
+    }
+
+    /// Only let clients send an event where they are allowed a flag.
+    pub fn protect_event(&mut self, name: &str, flag: RegionFlag) {
+        self.events.insert(name.to_lowercase(), flag);
+    }
+
+    /// Save the current regions over the ones in the storage that could not be read, and keep
+    /// saving them from now on.
+    pub fn overwrite_saved(&mut self) {
+        self.unreadable = false;
+        self.save();
+    }
+
+    /// Write the regions to the storage, if there is one and the regions saved in it could be read.
+    pub fn save(&self) {
+        let storage = if let Some(storage) = &self.storage {
+            storage
+        } else {
+            return;
+        };
+
+        if self.unreadable {
+            warn!("Not saving over unreadable regions, see `Regions::overwrite_saved`.");
+            return;
+        }
+
+        let mut regions = self.regions.values().collect::<Vec<_>>();
+        regions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let j = serde_json::to_string(&regions).unwrap();
+
+        if let Err(e) = storage.save_metadata(REGIONS_KEY, j.as_bytes()) {
+            warn!("Could not save regions: {}", e);
+        }
+    }
+
+    fn insert(&mut self, region: Region) {
+        for coords in region.area.chunks(self.chunk_size) {
+            self.index
+                .entry(coords)
+                .or_default()
+                .push(region.name.to_owned());
+        }
+
+        self.regions.insert(region.name.to_owned(), region);
+    }
+
+    fn take(&mut self, name: &str) -> Option<Region> {
+        let region = self.regions.remove(name)?;
+
+        for coords in region.area.chunks(self.chunk_size) {
+            if let Some(names) = self.index.get_mut(&coords) {
+                names.retain(|n| n != name);
+
+                if names.is_empty() {
+                    self.index.remove(&coords);
+                }
+            }
+        }
+
+        Some(region)
+    }
+}
@@ -1,10 +1,18 @@
-use std::sync::Arc;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use hashbrown::{HashMap, HashSet};
 use log::info;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-use crate::{BlockFace, Vec3, VoxelAccess, VoxelUpdate};
+use crate::{
+    BlockDefinition, BlockFace, BlockFile, BlockProperty, BlockState, TextureAtlas, Vec3,
+    VoxelAccess, VoxelUpdate, MAX_BLOCK_STATES,
+};
 
 use super::voxels::Block;
 
@@ -36,8 +44,58 @@ impl Default for UV {
     }
 }
 
+/// A set of block IDs, one bit per ID, such as the blocks with a tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockTag {
+    bits: Vec<u64>,
+}
+
+impl BlockTag {
+    /// Whether a block ID is in this set.
+    pub fn contains(&self, id: u32) -> bool {
+        self.bits
+            .get(id as usize / 64)
+            .is_some_and(|word| word & (1 << (id % 64)) != 0)
+    }
+
+    /// The block IDs in this set, lowest first.
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.bits.iter().enumerate().flat_map(|(index, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| (index * 64 + bit) as u32)
+        })
+    }
+
+    /// Whether this set has no block IDs.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&word| word == 0)
+    }
+
+    fn insert(&mut self, id: u32) {
+        let index = id as usize / 64;
+
+        if index >= self.bits.len() {
+            self.bits.resize(index + 1, 0);
+        }
+
+        self.bits[index] |= 1 << (id % 64);
+    }
+
+    fn remove(&mut self, id: u32) {
+        if let Some(word) = self.bits.get_mut(id as usize / 64) {
+            *word &= !(1 << (id % 64));
+        }
+    }
+}
+
+/// Name of the event that sends the blocks of a reloaded registry to the clients, as `{ blocks, states }`.
+/// Clients swap in the new blocks and rebuild their block materials when they receive it.
+pub const REGISTRY_EVENT: &str = "vox-builtin:registry";
+
 /// A collection of blocks to use in a Voxelize server. One server has one registry and one
-/// registry only. Once a registry is added to a server, it cannot be changed.
+/// registry only. Once a registry is added to a server, its blocks can only be changed by reloading
+/// their block files through `World::reload_registry`.
 #[derive(Default, Clone)]
 pub struct Registry {
     /// Block records, name -> Block.
@@ -54,6 +112,22 @@ pub struct Registry {
 
     /// Map of name -> ID.
     type_map: HashMap<String, u32>,
+
+    /// Blocks with each tag, tag -> IDs.
+    tags: HashMap<String, BlockTag>,
+
+    /// States of the blocks with properties, id -> states indexed by state ID.
+    states: HashMap<u32, Vec<BlockState>>,
+
+    /// Block files and folders of block files, read again on `reload`.
+    sources: Vec<PathBuf>,
+
+    /// Names of the blocks defined in the block files, which are unregistered on `reload` once
+    /// taken out of them.
+    file_blocks: HashSet<String>,
+
+    /// Texture atlas that the UV ranges of block faces are taken from, see `set_atlas`.
+    atlas: Option<Arc<TextureAtlas>>,
 }
 
 impl Registry {
@@ -91,8 +165,28 @@ impl Registry {
         self.record_block(&air);
     }
 
-    /// Generate the UV coordinates of the blocks. Call this before the server starts!
+    /// Generate the UV coordinates of the blocks, from the texture atlas if one is set. Call this
+    /// before the server starts!
     pub fn generate(&mut self) {
+        let resolved = self
+            .blocks_by_id
+            .values()
+            .filter_map(|block| {
+                let mut patterns = block.dynamic_patterns.clone()?;
+
+                patterns
+                    .iter_mut()
+                    .flat_map(|pattern| pattern.parts.iter_mut())
+                    .for_each(|part| part.rule.resolve(self));
+
+                Some((block.id, patterns))
+            })
+            .collect::<Vec<_>>();
+
+        for (id, patterns) in resolved {
+            self.blocks_by_id.get_mut(&id).unwrap().dynamic_patterns = Some(patterns);
+        }
+
         let all_blocks = self.blocks_by_id.values_mut().collect::<Vec<_>>();
 
         let mut total_faces = 0;
@@ -123,7 +217,16 @@ impl Registry {
         let mut row = 0;
         let mut col = 0;
 
-        let mut run_face = |face: &mut BlockFace| {
+        let atlas = self.atlas.clone();
+
+        let mut run_face = |block_name: &str, face: &mut BlockFace| {
+            if let Some(atlas) = &atlas {
+                let entry = atlas.get(block_name, &face.name);
+                face.range = entry.range.clone();
+                face.page = entry.page;
+                return;
+            }
+
             if col >= count_per_side {
                 col = 0;
                 row += 1;
@@ -151,6 +254,7 @@ impl Registry {
                 start_v,
                 end_v,
             };
+            face.page = 0;
 
             col += 1;
         };
@@ -161,7 +265,7 @@ impl Registry {
                     continue;
                 }
 
-                run_face(face);
+                run_face(&block.name, face);
             }
 
             if let Some(dynamic_patterns) = block.dynamic_patterns.as_mut() {
@@ -171,6 +275,7 @@ impl Registry {
                             let existing = block.faces.iter_mut().find(|f| f.name == face.name);
                             if let Some(e) = existing {
                                 face.range = e.range.clone();
+                                face.page = e.page;
                             }
                         }
                     }
@@ -188,6 +293,17 @@ impl Registry {
         });
     }
 
+    /// Take the UV ranges of block faces from a texture atlas in `generate`, instead of laying them
+    /// out on a grid. Faces without a texture on the atlas get its unknown texture.
+    pub fn set_atlas(&mut self, atlas: &TextureAtlas) {
+        self.atlas = Some(Arc::new(atlas.to_owned()));
+    }
+
+    /// Get the texture atlas that the UV ranges of block faces are taken from, if any.
+    pub fn atlas(&self) -> Option<&TextureAtlas> {
+        self.atlas.as_deref()
+    }
+
     /// Register multiple blocks into this world. The block ID's are assigned to the length of the blocks at registration.
     pub fn register_blocks(&mut self, blocks: &[Block]) {
         blocks.into_iter().for_each(|block| {
@@ -220,6 +336,61 @@ impl Registry {
         self.record_block(&block);
     }
 
+    /// Register the blocks of a block file, see `BlockFile`. A block already registered under the same
+    /// name is replaced, keeping its ID and callbacks. The file is read again on `reload`. Returns the
+    /// number of blocks registered.
+    pub fn register_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        self.register_source(path.as_ref())
+    }
+
+    /// Register the blocks of every block file in a folder, in the order of their names, see
+    /// `register_file`. Files added to the folder later are picked up on `reload`.
+    pub fn register_dir<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<usize> {
+        self.register_source(dir.as_ref())
+    }
+
+    /// Read the block files and folders registered so far again, then generate the UV coordinates
+    /// again. Every block of the files is built again from its definition, and blocks taken out of
+    /// the files are unregistered. Nothing changes if any file fails to load. Returns the IDs of the
+    /// blocks added or changed, not including the ones unregistered.
+    pub fn reload(&mut self) -> io::Result<Vec<u32>> {
+        let mut next = self.clone();
+        next.file_blocks.clear();
+
+        for source in &self.sources {
+            next.load_source(source)?;
+        }
+
+        let removed: Vec<String> = self
+            .file_blocks
+            .difference(&next.file_blocks)
+            .cloned()
+            .collect();
+
+        for name in removed {
+            next.remove_block(&name);
+        }
+
+        next.generate();
+
+        let mut changed = next
+            .blocks_by_id
+            .iter()
+            .filter(|(id, block)| match self.blocks_by_id.get(*id) {
+                Some(old) => json!(old) != json!(block),
+                None => true,
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        changed.sort_unstable();
+
+        *self = next;
+
+        info!("Reloaded the registry, {} blocks changed.", changed.len());
+
+        Ok(changed)
+    }
+
     /// Get a block reference by block name.
     pub fn get_block_by_name(&self, name: &str) -> &Block {
         self.blocks_by_name
@@ -242,6 +413,80 @@ impl Registry {
             .unwrap_or_else(|| panic!("Block name not found: {name}"))
     }
 
+    /// The IDs of the blocks with a tag, if any block has it. Faster than `has_tag` in a hot loop.
+    pub fn tag(&self, tag: &str) -> Option<&BlockTag> {
+        self.tags
+            .get(tag)
+            .or_else(|| self.tags.get(&tag.to_lowercase()))
+    }
+
+    /// Check if a block has a tag by id.
+    pub fn has_tag(&self, id: u32, tag: &str) -> bool {
+        self.tag(tag).is_some_and(|ids| ids.contains(id))
+    }
+
+    /// Get the blocks with a tag, lowest ID first.
+    pub fn blocks_with_tag(&self, tag: &str) -> Vec<&Block> {
+        self.tag(tag)
+            .map(|ids| {
+                ids.ids()
+                    .filter_map(|id| self.blocks_by_id.get(&id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the property values of a state of a block by id. Returns `None` if the block has no
+    /// properties or no such state.
+    pub fn get_state(&self, id: u32, state: u32) -> Option<&BlockState> {
+        self.states.get(&id)?.get(state as usize)
+    }
+
+    /// Get the state ID of a block by id with a set of property values. Properties that are left out
+    /// take their default. Returns `None` if the block has no such properties or values.
+    pub fn get_state_id(&self, id: u32, properties: &BlockState) -> Option<u32> {
+        let states = self.states.get(&id)?;
+
+        let mut wanted = states[0].to_owned();
+
+        for (name, value) in properties {
+            if !wanted.contains_key(name) {
+                return None;
+            }
+
+            wanted.insert(name.to_owned(), value.to_owned());
+        }
+
+        states
+            .iter()
+            .position(|state| *state == wanted)
+            .map(|index| index as u32)
+    }
+
+    /// Get the state IDs of a block by id that have a set of property values.
+    pub fn get_matching_states(&self, id: u32, properties: &BlockState) -> Vec<u32> {
+        self.states
+            .get(&id)
+            .map(|states| {
+                states
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, state)| {
+                        properties
+                            .iter()
+                            .all(|(name, value)| state.get(name) == Some(value))
+                    })
+                    .map(|(index, _)| index as u32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the states of every block with properties, id -> states indexed by state ID.
+    pub fn get_state_table(&self) -> &HashMap<u32, Vec<BlockState>> {
+        &self.states
+    }
+
     /// Get block fluidity by id.
     pub fn get_fluiditiy_by_id(&self, id: u32) -> bool {
         self.get_block_by_id(id).is_fluid
@@ -330,10 +575,149 @@ impl Registry {
         uv_map
     }
 
+    /// Load a block file or folder and remember it for `reload`, leaving the registry as it was on error.
+    fn register_source(&mut self, path: &Path) -> io::Result<usize> {
+        let mut next = self.clone();
+        let count = next.load_source(path)?;
+
+        next.sources.push(path.to_path_buf());
+        *self = next;
+
+        Ok(count)
+    }
+
+    /// Register the blocks of a block file, or of every block file in a folder.
+    fn load_source(&mut self, path: &Path) -> io::Result<usize> {
+        if path.is_dir() {
+            let mut files = fs::read_dir(path)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|file| BlockFile::is_block_file(file))
+                .collect::<Vec<_>>();
+            files.sort();
+
+            let mut count = 0;
+
+            for file in files {
+                count += self.load_source(&file)?;
+            }
+
+            return Ok(count);
+        }
+
+        let file = BlockFile::load(path)?;
+
+        for definition in &file.blocks {
+            self.define_block(definition).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid block in {:?}: {}", path, e),
+                )
+            })?;
+        }
+
+        Ok(file.blocks.len())
+    }
+
+    /// Register a block from its definition, replacing the block of the same name but keeping its ID
+    /// and the callbacks that were set on it in Rust.
+    fn define_block(&mut self, definition: &BlockDefinition) -> Result<(), String> {
+        let lower_name = definition.name.to_lowercase();
+
+        if definition.id == Some(0) && lower_name != "air" {
+            return Err(format!(
+                "{} cannot take ID 0, which is Air.",
+                definition.name
+            ));
+        }
+
+        let states: usize = definition
+            .properties
+            .iter()
+            .flatten()
+            .map(|property| property.values.len())
+            .product();
+
+        if states > MAX_BLOCK_STATES {
+            return Err(format!(
+                "{} has {} states, more than the {} a voxel can hold.",
+                definition.name, states, MAX_BLOCK_STATES
+            ));
+        }
+
+        let mut block = BlockDefinition {
+            id: None,
+            ..definition.to_owned()
+        }
+        .to_block();
+
+        if let Some(existing) = self.blocks_by_name.get(&lower_name) {
+            if definition.id.is_some_and(|id| id != existing.id) {
+                return Err(format!(
+                    "{} is already registered with ID {}.",
+                    definition.name, existing.id
+                ));
+            }
+
+            block.id = existing.id;
+            block.is_dynamic = existing.is_dynamic;
+            block.dynamic_patterns = existing.dynamic_patterns.clone();
+            block.dynamic_fn = existing.dynamic_fn.clone();
+            block.is_active = existing.is_active;
+            block.active_ticker = existing.active_ticker.clone();
+            block.active_updater = existing.active_updater.clone();
+            block.random_tick = existing.random_tick.clone();
+            block.on_neighbor_changed = existing.on_neighbor_changed.clone();
+            block.on_signal = existing.on_signal.clone();
+        } else if let Some(id) = definition.id {
+            if let Some(other) = self.blocks_by_id.get(&id) {
+                return Err(format!(
+                    "ID {} of {} is taken by {}.",
+                    id, definition.name, other.name
+                ));
+            }
+
+            block.id = id;
+        } else {
+            block.id = (1..)
+                .find(|id| !self.blocks_by_id.contains_key(id))
+                .unwrap();
+        }
+
+        self.record_block(&block);
+        self.file_blocks.insert(lower_name);
+
+        Ok(())
+    }
+
+    /// Take a block out of the registry and out of its maps. Air is never removed.
+    fn remove_block(&mut self, lower_name: &str) {
+        let id = match self.type_map.get(lower_name) {
+            Some(&id) if id != 0 => id,
+            _ => return,
+        };
+
+        self.blocks_by_name.remove(lower_name);
+        self.blocks_by_id.remove(&id);
+        self.name_map.remove(&id);
+        self.type_map.remove(lower_name);
+        self.states.remove(&id);
+        self.textures.retain(|(texture_id, _, _)| *texture_id != id);
+
+        for ids in self.tags.values_mut() {
+            ids.remove(id);
+        }
+    }
+
     /// Record a block into the registry, adding this block into appropriate maps.
     fn record_block(&mut self, block: &Block) {
         let Block {
-            id, name, faces, ..
+            id,
+            name,
+            faces,
+            tags,
+            properties,
+            ..
         } = block;
 
         let lower_name = name.to_lowercase();
@@ -347,6 +731,22 @@ impl Registry {
         self.name_map.insert(*id, lower_name.clone());
         self.type_map.insert(lower_name.clone(), *id);
 
+        for ids in self.tags.values_mut() {
+            ids.remove(*id);
+        }
+
+        for tag in tags {
+            self.tags.entry(tag.to_lowercase()).or_default().insert(*id);
+        }
+
+        if properties.is_empty() {
+            self.states.remove(id);
+        } else {
+            self.states.insert(*id, BlockProperty::combine(properties));
+        }
+
+        self.textures.retain(|(texture_id, _, _)| texture_id != id);
+
         for (idx, side) in faces.iter().enumerate() {
             self.textures.insert((*id, idx, side.independent));
         }
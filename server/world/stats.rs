@@ -1,12 +1,15 @@
 use std::{
-    fs,
-    io::Write,
-    path::PathBuf,
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 
 use serde::{Deserialize, Serialize};
 
+use crate::WorldStorage;
+
+/// The metadata key the stats are saved under.
+pub const STATS_KEY: &str = "stats.json";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct StatsJson {
@@ -32,25 +35,19 @@ pub struct Stats {
     /// The time of the last tick.
     pub prev_time: SystemTime,
 
-    path: PathBuf,
-
-    saving: bool,
+    storage: Option<Arc<dyn WorldStorage>>,
 }
 
 impl Stats {
     /// Create a new statistics instance.
-    pub fn new(saving: bool, directory: &str, default_time: f32) -> Self {
-        let mut path = PathBuf::from(&directory);
-        path.push("stats.json");
-
+    pub fn new(storage: Option<Arc<dyn WorldStorage>>, default_time: f32) -> Self {
         Self {
             delta: 0.0,
             tick: 0,
             start_time: Instant::now(),
             prev_time: SystemTime::now(),
             time: default_time,
-            path,
-            saving,
+            storage,
         }
     }
 
@@ -71,24 +68,21 @@ impl Stats {
         self.time = time;
     }
 
+    /// Swap out the storage that stats are saved to.
+    pub fn set_storage(&mut self, storage: Option<Arc<dyn WorldStorage>>) {
+        self.storage = storage;
+    }
+
     pub fn save(&self) {
-        if !self.saving {
+        let storage = if let Some(storage) = &self.storage {
+            storage
+        } else {
             return;
-        }
+        };
 
-        if let Ok(mut file) = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&self.path)
-        {
-            let j = serde_json::to_string(&self.get_stats()).unwrap();
-            file.write_all(j.as_bytes())
-                .expect("Unable to write stats file.");
-        } else {
-            let mut file = fs::File::create(&self.path).expect("Unable to create stats file...");
-            let j = serde_json::to_string(&self.get_stats()).unwrap();
-            file.write_all(j.as_bytes())
-                .expect("Unable to write stats file.");
-        }
+        let j = serde_json::to_string(&self.get_stats()).unwrap();
+        storage
+            .save_metadata(STATS_KEY, j.as_bytes())
+            .expect("Unable to write stats file.");
     }
 }
@@ -0,0 +1,275 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use byteorder::{ByteOrder, LittleEndian};
+use libflate::zlib::Decoder;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{ChunkUtils, Vec2};
+
+use super::{
+    quarantine_name, read_checked, remove_checked, write_atomic, write_checked, ChunkRecord,
+    ChunkStorage, RegionStorage, WorldStorage, CHECKSUM_EXTENSION, TEMP_EXTENSION,
+};
+
+/// Legacy format of a saved chunk, one JSON file per chunk with base64 encoded zlib blobs.
+/// Only read to migrate old worlds into region files.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChunkFileData {
+    id: String,
+    voxels: String,
+    height_map: String,
+}
+
+/// The default world storage: region files under `chunks/`, one JSON file per entity under
+/// `entities/`, and one file per metadata key at the root of the save directory. Entity and
+/// metadata files each have a `.crc` sidecar holding their checksum. Corrupt chunks are moved
+/// to `quarantine/`.
+pub struct DirectoryStorage {
+    /// Root of the save directory.
+    folder: PathBuf,
+
+    /// Region files holding the chunks.
+    regions: RegionStorage,
+
+    /// Folder holding the entity files.
+    entities: PathBuf,
+
+    /// Folder holding the raw data of corrupt chunks.
+    quarantine: PathBuf,
+}
+
+impl DirectoryStorage {
+    /// Open a directory storage, creating its folders and migrating legacy chunk files if needed.
+    pub fn new(folder: &Path) -> Self {
+        let mut chunks = folder.to_owned();
+        chunks.push("chunks");
+
+        let mut entities = folder.to_owned();
+        entities.push("entities");
+
+        let mut quarantine = folder.to_owned();
+        quarantine.push("quarantine");
+
+        fs::create_dir_all(&entities).expect("Unable to create entities directory...");
+
+        let regions = RegionStorage::new(&chunks);
+        Self::migrate_legacy_chunks(&regions);
+
+        Self {
+            folder: folder.to_owned(),
+            regions,
+            entities,
+            quarantine,
+        }
+    }
+
+    /// Root of the save directory.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    fn entity_path(&self, id: &str) -> PathBuf {
+        let mut path = self.entities.clone();
+        path.push(format!("{}.json", id));
+        path
+    }
+
+    fn metadata_path(&self, key: &str) -> PathBuf {
+        let mut path = self.folder.clone();
+        path.push(key);
+        path
+    }
+
+    /// Move chunks saved in the legacy one-JSON-file-per-chunk format into region files.
+    fn migrate_legacy_chunks(regions: &RegionStorage) {
+        let paths = match fs::read_dir(regions.folder()) {
+            Ok(paths) => paths,
+            Err(_) => return,
+        };
+
+        let mut count = 0;
+
+        for path in paths.flatten() {
+            let path = path.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let (coords, record) = match Self::read_legacy_chunk(&path) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    warn!("Could not migrate legacy chunk file {:?}: {}", path, e);
+                    continue;
+                }
+            };
+
+            // A chunk already in a region file was migrated before, and may have been saved since.
+            if !regions.contains(&coords) {
+                if let Err(e) = regions.save(&coords, &record.encode()) {
+                    warn!("Could not migrate legacy chunk file {:?}: {}", path, e);
+                    continue;
+                }
+
+                count += 1;
+            }
+
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Could not remove legacy chunk file {:?}: {}", path, e);
+            }
+        }
+
+        if count > 0 {
+            info!("Migrated {} legacy chunk files into region files.", count);
+        }
+    }
+
+    /// Read a chunk saved in the legacy JSON format.
+    fn read_legacy_chunk(path: &Path) -> io::Result<(Vec2<i32>, ChunkRecord)> {
+        let coords = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(ChunkUtils::try_parse_chunk_name)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Invalid chunk file name.")
+            })?;
+
+        let file = File::open(path)?;
+        let data: ChunkFileData = serde_json::from_reader(BufReader::new(file))?;
+
+        let decode_base64 = |base: &str| -> io::Result<Vec<u32>> {
+            if base.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let decoded = STANDARD
+                .decode(base)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let mut decoder = Decoder::new(&decoded[..])?;
+            let mut buf = Vec::new();
+            decoder.read_to_end(&mut buf)?;
+            let mut data = vec![0; buf.len() / 4];
+            LittleEndian::read_u32_into(&buf, &mut data);
+            Ok(data)
+        };
+
+        Ok((
+            coords,
+            ChunkRecord {
+                id: data.id,
+                voxels: decode_base64(&data.voxels)?,
+                extras: vec![],
+                height_map: decode_base64(&data.height_map)?,
+                lights: vec![],
+                palette: vec![],
+                states: vec![],
+                ticks: vec![],
+            },
+        ))
+    }
+}
+
+impl ChunkStorage for DirectoryStorage {
+    fn load_chunk(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        self.regions.load(coords)
+    }
+
+    fn save_chunk(&self, coords: &Vec2<i32>, data: &[u8]) -> io::Result<()> {
+        self.regions.save(coords, data)
+    }
+
+    fn delete_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        self.regions.delete(coords)
+    }
+
+    fn list_chunks(&self) -> io::Result<Vec<Vec2<i32>>> {
+        self.regions.list()
+    }
+
+    fn has_chunk(&self, coords: &Vec2<i32>) -> bool {
+        self.regions.contains(coords)
+    }
+}
+
+impl WorldStorage for DirectoryStorage {
+    fn load_entity(&self, id: &str) -> io::Result<Option<Vec<u8>>> {
+        read_checked(&self.entity_path(id))
+    }
+
+    fn save_entity(&self, id: &str, data: &[u8]) -> io::Result<()> {
+        write_checked(&self.entity_path(id), data)
+    }
+
+    fn delete_entity(&self, id: &str) -> io::Result<()> {
+        remove_checked(&self.entity_path(id))
+    }
+
+    fn list_entities(&self) -> io::Result<Vec<String>> {
+        let mut ids = vec![];
+
+        for entry in fs::read_dir(&self.entities)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            if let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                ids.push(id.to_owned());
+            }
+        }
+
+        Ok(ids)
+    }
+
+    fn load_metadata(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        read_checked(&self.metadata_path(key))
+    }
+
+    fn save_metadata(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        write_checked(&self.metadata_path(key), data)
+    }
+
+    fn delete_metadata(&self, key: &str) -> io::Result<()> {
+        remove_checked(&self.metadata_path(key))
+    }
+
+    fn list_metadata(&self) -> io::Result<Vec<String>> {
+        let mut keys = vec![];
+
+        for entry in fs::read_dir(&self.folder)? {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            if let Some(key) = entry.file_name().to_str() {
+                if !key.ends_with(TEMP_EXTENSION) && !key.ends_with(CHECKSUM_EXTENSION) {
+                    keys.push(key.to_owned());
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    fn quarantine_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        if let Some(blob) = self.regions.load_raw(coords)? {
+            fs::create_dir_all(&self.quarantine)?;
+
+            let mut path = self.quarantine.clone();
+            path.push(quarantine_name(coords));
+            write_atomic(&path, &blob)?;
+        }
+
+        self.regions.delete(coords)
+    }
+}
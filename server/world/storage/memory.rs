@@ -0,0 +1,94 @@
+use std::{io, sync::RwLock};
+
+use hashbrown::HashMap;
+
+use crate::Vec2;
+
+use super::{ChunkStorage, WorldStorage};
+
+/// A world storage that keeps everything in memory. Nothing touches the disk, and
+/// everything is lost when the storage is dropped.
+#[derive(Default)]
+pub struct MemoryStorage {
+    chunks: RwLock<HashMap<Vec2<i32>, Vec<u8>>>,
+    entities: RwLock<HashMap<String, Vec<u8>>>,
+    metadata: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    /// Create an empty in-memory storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ChunkStorage for MemoryStorage {
+    fn load_chunk(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.chunks.read().unwrap().get(coords).cloned())
+    }
+
+    fn save_chunk(&self, coords: &Vec2<i32>, data: &[u8]) -> io::Result<()> {
+        self.chunks
+            .write()
+            .unwrap()
+            .insert(coords.to_owned(), data.to_vec());
+        Ok(())
+    }
+
+    fn delete_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        self.chunks.write().unwrap().remove(coords);
+        Ok(())
+    }
+
+    fn list_chunks(&self) -> io::Result<Vec<Vec2<i32>>> {
+        Ok(self.chunks.read().unwrap().keys().cloned().collect())
+    }
+
+    fn has_chunk(&self, coords: &Vec2<i32>) -> bool {
+        self.chunks.read().unwrap().contains_key(coords)
+    }
+}
+
+impl WorldStorage for MemoryStorage {
+    fn load_entity(&self, id: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.entities.read().unwrap().get(id).cloned())
+    }
+
+    fn save_entity(&self, id: &str, data: &[u8]) -> io::Result<()> {
+        self.entities
+            .write()
+            .unwrap()
+            .insert(id.to_owned(), data.to_vec());
+        Ok(())
+    }
+
+    fn delete_entity(&self, id: &str) -> io::Result<()> {
+        self.entities.write().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn list_entities(&self) -> io::Result<Vec<String>> {
+        Ok(self.entities.read().unwrap().keys().cloned().collect())
+    }
+
+    fn load_metadata(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.metadata.read().unwrap().get(key).cloned())
+    }
+
+    fn save_metadata(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.metadata
+            .write()
+            .unwrap()
+            .insert(key.to_owned(), data.to_vec());
+        Ok(())
+    }
+
+    fn delete_metadata(&self, key: &str) -> io::Result<()> {
+        self.metadata.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn list_metadata(&self) -> io::Result<Vec<String>> {
+        Ok(self.metadata.read().unwrap().keys().cloned().collect())
+    }
+}
@@ -0,0 +1,264 @@
+mod directory;
+mod memory;
+mod record;
+mod region;
+mod single_file;
+mod snapshots;
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Vec2;
+
+pub use directory::*;
+pub use memory::*;
+pub use record::*;
+pub use region::*;
+pub use single_file::*;
+pub use snapshots::*;
+
+/// Persistence of raw chunk data. Chunks are stored as opaque bytes, encoded and
+/// decoded by the `Chunks` manager.
+pub trait ChunkStorage: Send + Sync {
+    /// Load the data of a chunk. Returns `None` if the chunk has never been saved.
+    fn load_chunk(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>>;
+
+    /// Save the data of a chunk, overwriting any previous data.
+    fn save_chunk(&self, coords: &Vec2<i32>, data: &[u8]) -> io::Result<()>;
+
+    /// Delete the data of a chunk. Deleting a chunk that does not exist is not an error.
+    fn delete_chunk(&self, coords: &Vec2<i32>) -> io::Result<()>;
+
+    /// List the coordinates of all the saved chunks.
+    fn list_chunks(&self) -> io::Result<Vec<Vec2<i32>>>;
+
+    /// Check whether a chunk has been saved.
+    fn has_chunk(&self, coords: &Vec2<i32>) -> bool {
+        matches!(self.load_chunk(coords), Ok(Some(_)))
+    }
+}
+
+/// Persistence of everything a world saves: chunks, entities and world metadata such as
+/// `stats.json`. Implement this to plug in a custom backend through `World::set_storage`.
+pub trait WorldStorage: ChunkStorage {
+    /// Load the data of an entity by its ID. Returns `None` if the entity has never been saved.
+    fn load_entity(&self, id: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Save the data of an entity, overwriting any previous data.
+    fn save_entity(&self, id: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Delete the data of an entity. Deleting an entity that does not exist is not an error.
+    fn delete_entity(&self, id: &str) -> io::Result<()>;
+
+    /// List the IDs of all the saved entities.
+    fn list_entities(&self) -> io::Result<Vec<String>>;
+
+    /// Load a piece of world metadata by key. Returns `None` if it has never been saved.
+    fn load_metadata(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Save a piece of world metadata, overwriting any previous data.
+    fn save_metadata(&self, key: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Delete a piece of world metadata. Deleting a key that does not exist is not an error.
+    fn delete_metadata(&self, key: &str) -> io::Result<()>;
+
+    /// List the keys of all the saved world metadata.
+    fn list_metadata(&self) -> io::Result<Vec<String>>;
+
+    /// Move a corrupt chunk out of the way so that it can be generated again, keeping whatever
+    /// can still be read of it for manual recovery. By default, the readable data is kept as a
+    /// metadata entry named after `quarantine_name`.
+    fn quarantine_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        if let Ok(Some(data)) = self.load_chunk(coords) {
+            self.save_metadata(&quarantine_name(coords), &data)?;
+        }
+
+        self.delete_chunk(coords)
+    }
+}
+
+/// Extension appended to files while they are being written by `write_atomic`.
+pub const TEMP_EXTENSION: &str = ".tmp";
+
+/// Name under which a quarantined chunk is kept, unique per chunk and time of quarantine.
+pub fn quarantine_name(coords: &Vec2<i32>) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or_default();
+
+    format!("quarantine.{}.{}.{}.bin", coords.0, coords.1, time)
+}
+
+/// Extension of the sidecar files that hold the CRC32 checksum of entity and metadata files,
+/// so that the files themselves stay plain JSON.
+pub const CHECKSUM_EXTENSION: &str = ".crc";
+
+/// Path of the sidecar file holding the checksum of a file.
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.to_owned().into_os_string();
+    checksum_path.push(CHECKSUM_EXTENSION);
+    PathBuf::from(checksum_path)
+}
+
+/// Write a file like `write_atomic`, along with a sidecar file holding its checksum so that
+/// damage is caught when it is read back with `read_checked`.
+pub fn write_checked(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temp_path = path.to_owned().into_os_string();
+    temp_path.push(TEMP_EXTENSION);
+    let temp_path = PathBuf::from(temp_path);
+
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+
+    // The checksum goes first, so that a crash before the rename below leaves a complete temporary
+    // file matching it, which `read_checked` finishes moving into place.
+    write_atomic(
+        &checksum_path(path),
+        format!("{:08x}", crc32fast::hash(data)).as_bytes(),
+    )?;
+    fs::rename(&temp_path, path)?;
+    sync_parent(path);
+
+    Ok(())
+}
+
+/// Read a file written by `write_checked`, failing with `io::ErrorKind::InvalidData` if it does
+/// not match its checksum. Returns `None` if the file does not exist. Files without a checksum,
+/// such as ones edited by hand with the sidecar removed, are returned as is.
+pub fn read_checked(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let data = read_optional(path)?;
+
+    let expected = match read_optional(&checksum_path(path))? {
+        Some(checksum) => std::str::from_utf8(&checksum)
+            .ok()
+            .and_then(|checksum| u32::from_str_radix(checksum.trim(), 16).ok()),
+        None => return Ok(data),
+    };
+
+    if let Some(data) = &data {
+        if expected == Some(crc32fast::hash(data)) {
+            return Ok(Some(data.to_owned()));
+        }
+    }
+
+    // A crash between writing the checksum and renaming the data leaves the new data behind.
+    let mut temp_path = path.to_owned().into_os_string();
+    temp_path.push(TEMP_EXTENSION);
+    let temp_path = PathBuf::from(temp_path);
+
+    if let Some(temp) = read_optional(&temp_path)? {
+        if expected == Some(crc32fast::hash(&temp)) {
+            fs::rename(&temp_path, path)?;
+            sync_parent(path);
+            return Ok(Some(temp));
+        }
+    }
+
+    if data.is_none() {
+        return Ok(None);
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Checksum of {:?} does not match.", path),
+    ))
+}
+
+/// Remove a file written by `write_checked` along with its checksum. Removing a file that does
+/// not exist is not an error.
+pub fn remove_checked(path: &Path) -> io::Result<()> {
+    remove_optional(path)?;
+    remove_optional(&checksum_path(path))
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn remove_optional(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Write a file so that a crash never leaves it half written: the data goes to a temporary file
+/// that is synced to disk, then renamed over the target.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temp_path = path.to_owned().into_os_string();
+    temp_path.push(TEMP_EXTENSION);
+    let temp_path = PathBuf::from(temp_path);
+
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+
+    fs::rename(&temp_path, path)?;
+    sync_parent(path);
+
+    Ok(())
+}
+
+/// Make a rename into a folder durable. Directories cannot be opened for syncing on every platform.
+fn sync_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+/// The built-in storage backends a world can be configured with.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageBackend {
+    /// Region files for chunks and one file per entity, inside `save_dir`. This is the default.
+    #[default]
+    Directory,
+
+    /// Everything in a single `world.vxw` file inside `save_dir`.
+    SingleFile,
+
+    /// Everything kept in memory, lost when the server stops. Useful for tests.
+    Memory,
+}
+
+impl StorageBackend {
+    /// Open this kind of storage at a save directory.
+    pub fn open(&self, save_dir: &str) -> Arc<dyn WorldStorage> {
+        match self {
+            Self::Directory => Arc::new(DirectoryStorage::new(&PathBuf::from(save_dir))),
+            Self::SingleFile => {
+                let mut path = PathBuf::from(save_dir);
+                path.push(SINGLE_FILE_NAME);
+
+                Arc::new(
+                    SingleFileStorage::open(&path).expect("Unable to open world storage file..."),
+                )
+            }
+            Self::Memory => Arc::new(MemoryStorage::new()),
+        }
+    }
+
+    /// Whether this backend writes to `save_dir`.
+    pub fn is_on_disk(&self) -> bool {
+        !matches!(self, Self::Memory)
+    }
+}
@@ -0,0 +1,308 @@
+use std::io;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::{BlockProperty, Vec3};
+
+/// Version of the binary chunk record layout. Records from version 2 on end with a checksum.
+const RECORD_VERSION: u32 = 2;
+
+const TAG_ID: u8 = 1;
+const TAG_VOXELS: u8 = 2;
+const TAG_HEIGHT_MAP: u8 = 3;
+const TAG_LIGHTS: u8 = 4;
+const TAG_PALETTE: u8 = 5;
+const TAG_TICKS: u8 = 6;
+const TAG_EXTRAS: u8 = 7;
+const TAG_STATES: u8 = 8;
+
+/// Size of a scheduled tick in the ticks section.
+const TICK_SIZE: usize = 20;
+
+/// CRC32 of every byte before this section. Always the last section of a record.
+const TAG_CHECKSUM: u8 = 255;
+
+/// The persisted form of a chunk, encoded as a list of tagged sections so that
+/// new sections can be added without breaking older saves.
+#[derive(Debug, Default, Clone)]
+pub struct ChunkRecord {
+    /// ID of the chunk.
+    pub id: String,
+
+    /// Raw voxel values of the chunk.
+    pub voxels: Vec<u32>,
+
+    /// Upper 32 bits of the voxel values of the chunk. Empty unless it was saved with
+    /// `VoxelEncoding::Wide`.
+    pub extras: Vec<u32>,
+
+    /// Height map of the chunk. Empty if it should be recalculated on load.
+    pub height_map: Vec<u32>,
+
+    /// Raw light values of the chunk. Empty if the chunk should be relit on load.
+    pub lights: Vec<u32>,
+
+    /// Block ID -> block name of every block in the chunk at the time it was saved, used to
+    /// remap IDs if the registry changed since. Empty for chunks saved without one.
+    pub palette: Vec<(u32, String)>,
+
+    /// Block ID -> properties of every block in the chunk with properties at the time it was saved,
+    /// used to remap state IDs if the properties changed since. Empty for chunks saved without one.
+    pub states: Vec<(u32, Vec<BlockProperty>)>,
+
+    /// Scheduled block ticks in the chunk, as each voxel and the number of ticks it had left.
+    pub ticks: Vec<(Vec3<i32>, u64)>,
+}
+
+impl ChunkRecord {
+    /// Encode this record into bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            (self.voxels.len() + self.extras.len() + self.height_map.len() + self.lights.len()) * 4
+                + 64,
+        );
+        bytes.extend_from_slice(&RECORD_VERSION.to_le_bytes());
+
+        write_section(&mut bytes, TAG_ID, self.id.as_bytes());
+        write_section(&mut bytes, TAG_VOXELS, &u32s_to_bytes(&self.voxels));
+        write_section(&mut bytes, TAG_HEIGHT_MAP, &u32s_to_bytes(&self.height_map));
+
+        if !self.lights.is_empty() {
+            write_section(&mut bytes, TAG_LIGHTS, &u32s_to_bytes(&self.lights));
+        }
+
+        if !self.extras.is_empty() {
+            write_section(&mut bytes, TAG_EXTRAS, &u32s_to_bytes(&self.extras));
+        }
+
+        if !self.palette.is_empty() {
+            write_section(&mut bytes, TAG_PALETTE, &palette_to_bytes(&self.palette));
+        }
+
+        if !self.states.is_empty() {
+            write_section(&mut bytes, TAG_STATES, &states_to_bytes(&self.states));
+        }
+
+        if !self.ticks.is_empty() {
+            write_section(&mut bytes, TAG_TICKS, &ticks_to_bytes(&self.ticks));
+        }
+
+        let checksum = crc32fast::hash(&bytes);
+        write_section(&mut bytes, TAG_CHECKSUM, &checksum.to_le_bytes());
+
+        bytes
+    }
+
+    /// Decode a record from bytes. Unknown sections are skipped.
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the record is truncated or its checksum does not match.
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 4 {
+            return Err(invalid_data("Chunk record is truncated."));
+        }
+
+        let version = LittleEndian::read_u32(&bytes[0..4]);
+        if version > RECORD_VERSION {
+            return Err(invalid_data(&format!(
+                "Unsupported chunk record version: {}",
+                version
+            )));
+        }
+
+        let mut record = ChunkRecord::default();
+        let mut cursor = 4;
+        let mut verified = false;
+
+        while cursor < bytes.len() {
+            if cursor + 5 > bytes.len() {
+                return Err(invalid_data("Chunk record section header is truncated."));
+            }
+
+            let tag = bytes[cursor];
+            let length = LittleEndian::read_u32(&bytes[cursor + 1..cursor + 5]) as usize;
+            cursor += 5;
+
+            if cursor + length > bytes.len() {
+                return Err(invalid_data("Chunk record section is truncated."));
+            }
+
+            let section = &bytes[cursor..cursor + length];
+            cursor += length;
+
+            match tag {
+                TAG_ID => {
+                    record.id = String::from_utf8(section.to_vec())
+                        .map_err(|_| invalid_data("Chunk record ID is not valid UTF-8."))?;
+                }
+                TAG_VOXELS => record.voxels = bytes_to_u32s(section)?,
+                TAG_HEIGHT_MAP => record.height_map = bytes_to_u32s(section)?,
+                TAG_LIGHTS => record.lights = bytes_to_u32s(section)?,
+                TAG_PALETTE => record.palette = bytes_to_palette(section)?,
+                TAG_TICKS => record.ticks = bytes_to_ticks(section)?,
+                TAG_EXTRAS => record.extras = bytes_to_u32s(section)?,
+                TAG_STATES => record.states = bytes_to_states(section)?,
+                TAG_CHECKSUM => {
+                    if length != 4 || cursor != bytes.len() {
+                        return Err(invalid_data("Chunk record checksum is malformed."));
+                    }
+
+                    let expected = LittleEndian::read_u32(section);
+                    if crc32fast::hash(&bytes[..cursor - 9]) != expected {
+                        return Err(invalid_data("Chunk record checksum does not match."));
+                    }
+
+                    verified = true;
+                }
+                _ => {}
+            }
+        }
+
+        if version >= 2 && !verified {
+            return Err(invalid_data("Chunk record is missing its checksum."));
+        }
+
+        Ok(record)
+    }
+}
+
+fn write_section(bytes: &mut Vec<u8>, tag: u8, section: &[u8]) {
+    bytes.push(tag);
+    bytes.extend_from_slice(&(section.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(section);
+}
+
+fn u32s_to_bytes(data: &[u32]) -> Vec<u8> {
+    let mut bytes = vec![0; data.len() * 4];
+    LittleEndian::write_u32_into(data, &mut bytes);
+    bytes
+}
+
+fn bytes_to_u32s(bytes: &[u8]) -> io::Result<Vec<u32>> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(invalid_data("Chunk record section is not a list of u32s."));
+    }
+
+    let mut data = vec![0; bytes.len() / 4];
+    LittleEndian::read_u32_into(bytes, &mut data);
+    Ok(data)
+}
+
+/// Palette entries are laid out as `[u32 id][u16 name length][name]`.
+fn palette_to_bytes(palette: &[(u32, String)]) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for (id, name) in palette {
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+
+    bytes
+}
+
+fn bytes_to_palette(bytes: &[u8]) -> io::Result<Vec<(u32, String)>> {
+    let mut palette = vec![];
+    let mut cursor = 0;
+
+    while cursor < bytes.len() {
+        if cursor + 6 > bytes.len() {
+            return Err(invalid_data("Chunk record palette is truncated."));
+        }
+
+        let id = LittleEndian::read_u32(&bytes[cursor..cursor + 4]);
+        let length = LittleEndian::read_u16(&bytes[cursor + 4..cursor + 6]) as usize;
+        cursor += 6;
+
+        if cursor + length > bytes.len() {
+            return Err(invalid_data("Chunk record palette is truncated."));
+        }
+
+        let name = String::from_utf8(bytes[cursor..cursor + length].to_vec())
+            .map_err(|_| invalid_data("Chunk record block name is not valid UTF-8."))?;
+        cursor += length;
+
+        palette.push((id, name));
+    }
+
+    Ok(palette)
+}
+
+/// Block states are laid out as `[u32 id][u32 properties length][properties as JSON]`.
+fn states_to_bytes(states: &[(u32, Vec<BlockProperty>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+
+    for (id, properties) in states {
+        let properties = serde_json::to_vec(properties).unwrap();
+
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes.extend_from_slice(&(properties.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&properties);
+    }
+
+    bytes
+}
+
+fn bytes_to_states(bytes: &[u8]) -> io::Result<Vec<(u32, Vec<BlockProperty>)>> {
+    let mut states = vec![];
+    let mut cursor = 0;
+
+    while cursor < bytes.len() {
+        if cursor + 8 > bytes.len() {
+            return Err(invalid_data("Chunk record states are truncated."));
+        }
+
+        let id = LittleEndian::read_u32(&bytes[cursor..cursor + 4]);
+        let length = LittleEndian::read_u32(&bytes[cursor + 4..cursor + 8]) as usize;
+        cursor += 8;
+
+        if cursor + length > bytes.len() {
+            return Err(invalid_data("Chunk record states are truncated."));
+        }
+
+        let properties = serde_json::from_slice(&bytes[cursor..cursor + length])
+            .map_err(|_| invalid_data("Chunk record block properties are malformed."))?;
+        cursor += length;
+
+        states.push((id, properties));
+    }
+
+    Ok(states)
+}
+
+/// Scheduled ticks are laid out as `[i32 x][i32 y][i32 z][u64 ticks left]`.
+fn ticks_to_bytes(ticks: &[(Vec3<i32>, u64)]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(ticks.len() * TICK_SIZE);
+
+    for (Vec3(vx, vy, vz), delay) in ticks {
+        bytes.extend_from_slice(&vx.to_le_bytes());
+        bytes.extend_from_slice(&vy.to_le_bytes());
+        bytes.extend_from_slice(&vz.to_le_bytes());
+        bytes.extend_from_slice(&delay.to_le_bytes());
+    }
+
+    bytes
+}
+
+fn bytes_to_ticks(bytes: &[u8]) -> io::Result<Vec<(Vec3<i32>, u64)>> {
+    if !bytes.len().is_multiple_of(TICK_SIZE) {
+        return Err(invalid_data("Chunk record ticks are truncated."));
+    }
+
+    Ok(bytes
+        .chunks_exact(TICK_SIZE)
+        .map(|tick| {
+            (
+                Vec3(
+                    LittleEndian::read_i32(&tick[0..4]),
+                    LittleEndian::read_i32(&tick[4..8]),
+                    LittleEndian::read_i32(&tick[8..12]),
+                ),
+                LittleEndian::read_u64(&tick[12..20]),
+            )
+        })
+        .collect())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
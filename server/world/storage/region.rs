@@ -0,0 +1,507 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use hashbrown::HashMap;
+use libflate::zlib::{Decoder, Encoder};
+
+use crate::Vec2;
+
+/// How many chunks wide (and long) a single region file is.
+pub const REGION_SIZE: i32 = 32;
+
+/// Number of chunk slots in a region file.
+const REGION_CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+
+/// Region files are allocated in sectors of this many bytes.
+const SECTOR_SIZE: u64 = 4096;
+
+/// Magic bytes at the start of every region file.
+const REGION_MAGIC: &[u8; 4] = b"VXRG";
+
+/// Version of the region file layout.
+const REGION_VERSION: u32 = 1;
+
+/// Magic + version, followed by one `(sector offset, byte length)` pair per chunk slot.
+const HEADER_BYTES: u64 = 8 + REGION_CHUNKS as u64 * 8;
+
+/// Number of sectors reserved for the header at the start of the file.
+const HEADER_SECTORS: u32 = HEADER_BYTES.div_ceil(SECTOR_SIZE) as u32;
+
+/// Extension of region files on disk.
+const REGION_EXTENSION: &str = "vxr";
+
+/// Maximum number of region files kept open at the same time.
+const MAX_OPEN_REGIONS: usize = 64;
+
+/// Per-chunk compression scheme, stored as the first byte of every chunk blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RegionCompression {
+    /// Stored as-is.
+    None = 0,
+
+    /// Compressed with zlib.
+    Zlib = 1,
+}
+
+impl RegionCompression {
+    fn from_u8(value: u8) -> io::Result<Self> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zlib),
+            _ => Err(invalid_data(&format!(
+                "Unknown region chunk compression: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// A single region file, holding a `REGION_SIZE` x `REGION_SIZE` grid of chunks.
+///
+/// The file starts with a header containing an offset table, followed by sector-aligned
+/// chunk blobs. Chunks are never overwritten in place: a new copy goes to the first free run
+/// of sectors (or the end of the file) and is synced to disk before the offset table points
+/// to it, so a crash leaves either the old or the new chunk intact.
+pub struct RegionFile {
+    /// The underlying file handle.
+    file: File,
+
+    /// Offset table, slot -> (first sector, length in bytes).
+    table: Vec<(u32, u32)>,
+
+    /// Which sectors of the file are currently in use.
+    used: Vec<bool>,
+
+    /// Compression used for newly written chunks.
+    compression: RegionCompression,
+}
+
+impl RegionFile {
+    /// Open a region file, creating an empty one if it does not exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let file_len = file.metadata()?.len();
+
+        let mut table = vec![(0, 0); REGION_CHUNKS];
+
+        if file_len == 0 {
+            let mut header = Vec::with_capacity((HEADER_SECTORS as u64 * SECTOR_SIZE) as usize);
+            header.extend_from_slice(REGION_MAGIC);
+            header.extend_from_slice(&REGION_VERSION.to_le_bytes());
+            header.resize((HEADER_SECTORS as u64 * SECTOR_SIZE) as usize, 0);
+            file.write_all(&header)?;
+            file.sync_all()?;
+        } else {
+            if file_len < HEADER_BYTES {
+                return Err(invalid_data("Region file header is truncated."));
+            }
+
+            let mut header = vec![0; HEADER_BYTES as usize];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header)?;
+
+            if &header[0..4] != REGION_MAGIC {
+                return Err(invalid_data("Not a voxelize region file."));
+            }
+
+            let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            if version != REGION_VERSION {
+                return Err(invalid_data(&format!(
+                    "Unsupported region file version: {}",
+                    version
+                )));
+            }
+
+            for (slot, entry) in table.iter_mut().enumerate() {
+                let start = 8 + slot * 8;
+                let offset = u32::from_le_bytes(header[start..start + 4].try_into().unwrap());
+                let length = u32::from_le_bytes(header[start + 4..start + 8].try_into().unwrap());
+                *entry = (offset, length);
+            }
+        }
+
+        let total_sectors = file
+            .metadata()?
+            .len()
+            .div_ceil(SECTOR_SIZE)
+            .max(HEADER_SECTORS as u64);
+        let mut used = vec![false; total_sectors as usize];
+        used[..HEADER_SECTORS as usize].fill(true);
+
+        for &(offset, length) in &table {
+            if length == 0 {
+                continue;
+            }
+
+            let end = offset as usize + sectors_for(length) as usize;
+            if offset < HEADER_SECTORS || end > used.len() {
+                return Err(invalid_data("Region file offset table is corrupted."));
+            }
+
+            used[offset as usize..end].fill(true);
+        }
+
+        Ok(Self {
+            file,
+            table,
+            used,
+            compression: RegionCompression::Zlib,
+        })
+    }
+
+    /// Configure the compression used for chunks written from now on.
+    pub fn set_compression(&mut self, compression: RegionCompression) {
+        self.compression = compression;
+    }
+
+    /// Check whether a chunk slot has any data.
+    pub fn has(&self, slot: usize) -> bool {
+        self.table[slot].1 != 0
+    }
+
+    /// List all the slots that have data in this region.
+    pub fn slots(&self) -> Vec<usize> {
+        (0..REGION_CHUNKS).filter(|&slot| self.has(slot)).collect()
+    }
+
+    /// Read and decompress the data of a chunk slot. Returns `None` if the slot is empty, and
+    /// fails with `io::ErrorKind::InvalidData` if the stored blob is damaged.
+    pub fn read(&mut self, slot: usize) -> io::Result<Option<Vec<u8>>> {
+        let blob = match self.read_raw(slot)? {
+            Some(blob) => blob,
+            None => return Ok(None),
+        };
+
+        let data = match RegionCompression::from_u8(blob[0])? {
+            RegionCompression::None => blob[1..].to_vec(),
+            RegionCompression::Zlib => {
+                let mut data = Vec::new();
+                Decoder::new(&blob[1..])
+                    .and_then(|mut decoder| decoder.read_to_end(&mut data))
+                    .map_err(|e| invalid_data(&format!("Chunk data is damaged: {}", e)))?;
+                data
+            }
+        };
+
+        Ok(Some(data))
+    }
+
+    /// Read the stored blob of a chunk slot as-is, without decompressing it.
+    pub fn read_raw(&mut self, slot: usize) -> io::Result<Option<Vec<u8>>> {
+        let (offset, length) = self.table[slot];
+
+        if length == 0 {
+            return Ok(None);
+        }
+
+        let mut blob = vec![0; length as usize];
+        self.file
+            .seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE))?;
+        self.file.read_exact(&mut blob).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                invalid_data("Chunk data is truncated.")
+            } else {
+                e
+            }
+        })?;
+
+        Ok(Some(blob))
+    }
+
+    /// Compress and write the data of a chunk slot to fresh sectors, then release the old ones.
+    pub fn write(&mut self, slot: usize, data: &[u8]) -> io::Result<()> {
+        let mut blob = vec![self.compression as u8];
+
+        match self.compression {
+            RegionCompression::None => blob.extend_from_slice(data),
+            RegionCompression::Zlib => {
+                let mut encoder = Encoder::new(blob)?;
+                encoder.write_all(data)?;
+                blob = encoder.finish().into_result()?;
+            }
+        }
+
+        if blob.len() > u32::MAX as usize {
+            return Err(invalid_data("Chunk data is too large for a region file."));
+        }
+
+        let length = blob.len() as u32;
+        let needed = sectors_for(length);
+        let (old_offset, old_length) = self.table[slot];
+
+        // The old sectors stay marked as used, so the new copy never overlaps them.
+        let offset = self.allocate(needed);
+        self.mark(offset, needed, true);
+
+        let padded = needed as usize * SECTOR_SIZE as usize;
+        blob.resize(padded, 0);
+
+        self.file
+            .seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE))?;
+        self.file.write_all(&blob)?;
+        self.file.sync_data()?;
+
+        self.write_entry(slot, offset, length)?;
+
+        if old_length != 0 {
+            self.mark(old_offset, sectors_for(old_length), false);
+        }
+
+        Ok(())
+    }
+
+    /// Remove the data of a chunk slot.
+    pub fn delete(&mut self, slot: usize) -> io::Result<()> {
+        let (offset, length) = self.table[slot];
+
+        if length == 0 {
+            return Ok(());
+        }
+
+        self.mark(offset, sectors_for(length), false);
+        self.write_entry(slot, 0, 0)
+    }
+
+    /// Update an entry of the offset table, both in memory and on disk.
+    fn write_entry(&mut self, slot: usize, offset: u32, length: u32) -> io::Result<()> {
+        self.table[slot] = (offset, length);
+
+        let mut entry = [0; 8];
+        entry[0..4].copy_from_slice(&offset.to_le_bytes());
+        entry[4..8].copy_from_slice(&length.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(8 + slot as u64 * 8))?;
+        self.file.write_all(&entry)?;
+        self.file.sync_data()
+    }
+
+    /// Find the first run of free sectors that is long enough, or the end of the file.
+    fn allocate(&self, count: u32) -> u32 {
+        let count = count as usize;
+        let mut run = 0;
+
+        for (index, &used) in self.used.iter().enumerate() {
+            if used {
+                run = 0;
+                continue;
+            }
+
+            run += 1;
+
+            if run == count {
+                return (index + 1 - count) as u32;
+            }
+        }
+
+        (self.used.len() - run) as u32
+    }
+
+    /// Mark a run of sectors as used or free.
+    fn mark(&mut self, offset: u32, count: u32, used: bool) {
+        let end = (offset + count) as usize;
+
+        if end > self.used.len() {
+            self.used.resize(end, false);
+        }
+
+        self.used[offset as usize..end].fill(used);
+    }
+}
+
+/// A folder of region files, each holding a `REGION_SIZE` x `REGION_SIZE` grid of chunks.
+pub struct RegionStorage {
+    /// The folder that contains all the region files.
+    folder: PathBuf,
+
+    /// Region files that are currently open, region coords -> region file.
+    regions: Mutex<HashMap<Vec2<i32>, Arc<Mutex<RegionFile>>>>,
+}
+
+impl RegionStorage {
+    /// Create a region storage in a folder, creating the folder if needed.
+    pub fn new(folder: &Path) -> Self {
+        fs::create_dir_all(folder).expect("Unable to create region directory...");
+
+        Self {
+            folder: folder.to_owned(),
+            regions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The folder this region storage lives in.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Map a chunk coordinate to its region coordinate and its slot within the region.
+    pub fn locate(coords: &Vec2<i32>) -> (Vec2<i32>, usize) {
+        let region = Vec2(
+            coords.0.div_euclid(REGION_SIZE),
+            coords.1.div_euclid(REGION_SIZE),
+        );
+        let lx = coords.0.rem_euclid(REGION_SIZE);
+        let lz = coords.1.rem_euclid(REGION_SIZE);
+
+        (region, (lx + lz * REGION_SIZE) as usize)
+    }
+
+    /// Check whether a chunk has been saved.
+    pub fn contains(&self, coords: &Vec2<i32>) -> bool {
+        let (region, slot) = Self::locate(coords);
+
+        match self.region(&region, false) {
+            Ok(Some(region)) => region.lock().unwrap().has(slot),
+            _ => false,
+        }
+    }
+
+    /// Load the raw data of a chunk. Returns `None` if the chunk has never been saved.
+    pub fn load(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        let (region, slot) = Self::locate(coords);
+
+        match self.region(&region, false)? {
+            Some(region) => region.lock().unwrap().read(slot),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the stored blob of a chunk as-is, even if it is damaged.
+    pub fn load_raw(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        let (region, slot) = Self::locate(coords);
+
+        match self.region(&region, false)? {
+            Some(region) => region.lock().unwrap().read_raw(slot),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the raw data of a chunk.
+    pub fn save(&self, coords: &Vec2<i32>, data: &[u8]) -> io::Result<()> {
+        let (region, slot) = Self::locate(coords);
+        let region = self.region(&region, true)?.unwrap();
+        let mut region = region.lock().unwrap();
+        region.write(slot, data)
+    }
+
+    /// Delete the data of a chunk.
+    pub fn delete(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        let (region, slot) = Self::locate(coords);
+
+        match self.region(&region, false)? {
+            Some(region) => region.lock().unwrap().delete(slot),
+            None => Ok(()),
+        }
+    }
+
+    /// List the coordinates of all the saved chunks.
+    pub fn list(&self) -> io::Result<Vec<Vec2<i32>>> {
+        let mut list = vec![];
+
+        for entry in fs::read_dir(&self.folder)? {
+            let path = entry?.path();
+
+            let region = match Self::parse_region_path(&path) {
+                Some(region) => region,
+                None => continue,
+            };
+
+            if let Some(file) = self.region(&region, false)? {
+                let file = file.lock().unwrap();
+
+                for slot in file.slots() {
+                    let slot = slot as i32;
+                    list.push(Vec2(
+                        region.0 * REGION_SIZE + slot % REGION_SIZE,
+                        region.1 * REGION_SIZE + slot / REGION_SIZE,
+                    ));
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// Close all open region files.
+    pub fn close(&self) {
+        self.regions.lock().unwrap().clear();
+    }
+
+    fn region_path(&self, region: &Vec2<i32>) -> PathBuf {
+        let mut path = self.folder.clone();
+        path.push(format!("r.{}.{}.{}", region.0, region.1, REGION_EXTENSION));
+        path
+    }
+
+    fn parse_region_path(path: &Path) -> Option<Vec2<i32>> {
+        if path.extension()?.to_str()? != REGION_EXTENSION {
+            return None;
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+        let mut parts = stem.split('.');
+
+        if parts.next()? != "r" {
+            return None;
+        }
+
+        let rx = parts.next()?.parse().ok()?;
+        let rz = parts.next()?.parse().ok()?;
+
+        Some(Vec2(rx, rz))
+    }
+
+    /// Get an open region file, opening it from disk if needed.
+    fn region(
+        &self,
+        region: &Vec2<i32>,
+        create: bool,
+    ) -> io::Result<Option<Arc<Mutex<RegionFile>>>> {
+        let mut regions = self.regions.lock().unwrap();
+
+        if let Some(file) = regions.get(region) {
+            return Ok(Some(file.clone()));
+        }
+
+        let path = self.region_path(region);
+
+        if !create && !path.exists() {
+            return Ok(None);
+        }
+
+        // Only close regions nobody else holds, so there is never more than one handle per file.
+        if regions.len() >= MAX_OPEN_REGIONS {
+            let evict = regions
+                .iter()
+                .find(|(_, file)| Arc::strong_count(file) == 1)
+                .map(|(coords, _)| coords.to_owned());
+
+            if let Some(evict) = evict {
+                regions.remove(&evict);
+            }
+        }
+
+        let file = Arc::new(Mutex::new(RegionFile::open(&path)?));
+        regions.insert(region.to_owned(), file.clone());
+
+        Ok(Some(file))
+    }
+}
+
+fn sectors_for(length: u32) -> u32 {
+    (length as u64).div_ceil(SECTOR_SIZE) as u32
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,472 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use hashbrown::HashMap;
+use log::warn;
+
+use crate::Vec2;
+
+use super::{quarantine_name, ChunkStorage, WorldStorage};
+
+/// Name of the file used by the single-file backend inside `save_dir`.
+pub const SINGLE_FILE_NAME: &str = "world.vxw";
+
+/// Magic bytes at the start of a single-file world.
+const FILE_MAGIC: &[u8; 4] = b"VXWF";
+
+/// Version of the single-file layout. Version 2 added a checksum to every record, and older
+/// files are upgraded by compacting them when opened.
+const FILE_VERSION: u32 = 2;
+
+/// Length of the magic + version header.
+const FILE_HEADER_BYTES: u64 = 8;
+
+/// Data length that marks a record as deleted.
+const TOMBSTONE: u32 = u32::MAX;
+
+/// The file is compacted once it holds this many dead bytes and more dead bytes than live ones.
+const COMPACT_THRESHOLD: u64 = 4 * 1024 * 1024;
+
+const KIND_CHUNK: u8 = 0;
+const KIND_ENTITY: u8 = 1;
+const KIND_METADATA: u8 = 2;
+const KIND_QUARANTINE: u8 = 3;
+
+type RecordKey = (u8, String);
+
+/// Kind, key, data length and checksum (if the layout has one) of a record.
+type RecordHeader = (u8, String, u32, Option<u32>);
+
+struct SingleFileInner {
+    file: File,
+
+    /// Record key -> (offset of the data, length of the data, checksum of the data if known).
+    index: HashMap<RecordKey, (u64, u32, Option<u32>)>,
+
+    /// Total length of the file.
+    len: u64,
+
+    /// Bytes taken by records that have been overwritten or deleted.
+    dead: u64,
+}
+
+/// A world storage that keeps everything in one append-only file. Each save appends a
+/// checksummed record and syncs it to disk, and the file is compacted once enough old
+/// records pile up. Corrupt chunks are kept as quarantine records.
+pub struct SingleFileStorage {
+    path: PathBuf,
+    inner: Mutex<SingleFileInner>,
+}
+
+impl SingleFileStorage {
+    /// Open a single-file storage, creating the file if it does not exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let (inner, version) = Self::open_inner(path)?;
+
+        let storage = Self {
+            path: path.to_owned(),
+            inner: Mutex::new(inner),
+        };
+
+        if version < FILE_VERSION {
+            storage.compact()?;
+        }
+
+        Ok(storage)
+    }
+
+    /// Path of the underlying file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the file with only the live records.
+    pub fn compact(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        self.compact_inner(&mut inner)
+    }
+
+    /// Open the file and index its records. Also returns the layout version of the file.
+    fn open_inner(path: &Path) -> io::Result<(SingleFileInner, u32)> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let file_len = file.metadata()?.len();
+
+        if file_len == 0 {
+            file.write_all(FILE_MAGIC)?;
+            file.write_all(&FILE_VERSION.to_le_bytes())?;
+            file.sync_all()?;
+
+            return Ok((
+                SingleFileInner {
+                    file,
+                    index: HashMap::new(),
+                    len: FILE_HEADER_BYTES,
+                    dead: 0,
+                },
+                FILE_VERSION,
+            ));
+        }
+
+        let mut reader = BufReader::new(&mut file);
+        let mut header = [0; FILE_HEADER_BYTES as usize];
+        reader.read_exact(&mut header)?;
+
+        if &header[0..4] != FILE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a voxelize world file.",
+            ));
+        }
+
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version > FILE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported world file version: {}", version),
+            ));
+        }
+
+        let mut index = HashMap::new();
+        let mut offset = FILE_HEADER_BYTES;
+        let mut dead = 0;
+
+        // Scan all the records, keeping the latest one of each key.
+        loop {
+            let record = match read_record_header(&mut reader, version) {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    warn!(
+                        "World file {:?} ends with a truncated record, dropping it.",
+                        path
+                    );
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let (kind, key, length, checksum) = record;
+            let header_len = header_len(key.len(), version);
+            let data_len = if length == TOMBSTONE {
+                0
+            } else {
+                length as u64
+            };
+
+            if offset + header_len + data_len > file_len {
+                warn!(
+                    "World file {:?} ends with a truncated record, dropping it.",
+                    path
+                );
+                break;
+            }
+
+            reader.seek_relative(data_len as i64)?;
+
+            if let Some((_, old_length, _)) = index.remove(&(kind, key.clone())) {
+                dead += header_len + old_length as u64;
+            }
+
+            if length == TOMBSTONE {
+                dead += header_len;
+            } else {
+                index.insert((kind, key), (offset + header_len, length, checksum));
+            }
+
+            offset += header_len + data_len;
+        }
+
+        drop(reader);
+
+        if offset < file_len {
+            file.set_len(offset)?;
+        }
+
+        Ok((
+            SingleFileInner {
+                file,
+                index,
+                len: offset,
+                dead,
+            },
+            version,
+        ))
+    }
+
+    /// Load the data of a record, failing with `io::ErrorKind::InvalidData` if its checksum does not match.
+    fn load(&self, kind: u8, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let (data, checksum) = match self.read(kind, key)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        if checksum.is_some_and(|checksum| checksum != crc32fast::hash(&data)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Checksum of world file record {:?} does not match.", key),
+            ));
+        }
+
+        Ok(Some(data))
+    }
+
+    /// Read the data of a record and its stored checksum, without verifying it.
+    fn read(&self, kind: u8, key: &str) -> io::Result<Option<(Vec<u8>, Option<u32>)>> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let (offset, length, checksum) = match inner.index.get(&(kind, key.to_owned())) {
+            Some(&entry) => entry,
+            None => return Ok(None),
+        };
+
+        let mut data = vec![0; length as usize];
+        inner.file.seek(SeekFrom::Start(offset))?;
+        inner.file.read_exact(&mut data)?;
+
+        Ok(Some((data, checksum)))
+    }
+
+    fn save(&self, kind: u8, key: &str, data: Option<&[u8]>) -> io::Result<()> {
+        if data.is_some_and(|data| data.len() >= TOMBSTONE as usize) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Record is too large for a world file.",
+            ));
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let record_key = (kind, key.to_owned());
+
+        let existing = inner.index.get(&record_key).copied();
+
+        if existing.is_none() && data.is_none() {
+            return Ok(());
+        }
+
+        let old_dead = existing
+            .map(|(_, old_length, _)| header_len(key.len(), FILE_VERSION) + old_length as u64);
+
+        let length = data.map_or(TOMBSTONE, |data| data.len() as u32);
+        let checksum = data.map_or(0, crc32fast::hash);
+        let mut bytes = record_header(kind, key, length, checksum);
+        let header_len = bytes.len() as u64;
+
+        if let Some(data) = data {
+            bytes.extend_from_slice(data);
+        }
+
+        let offset = inner.len;
+        inner.file.seek(SeekFrom::Start(offset))?;
+        inner.file.write_all(&bytes)?;
+        inner.file.sync_data()?;
+        inner.len += bytes.len() as u64;
+
+        // The old record is only replaced once the new one is safely on disk.
+        inner.dead += old_dead.unwrap_or(0);
+
+        if data.is_some() {
+            inner
+                .index
+                .insert(record_key, (offset + header_len, length, Some(checksum)));
+        } else {
+            inner.index.remove(&record_key);
+            inner.dead += header_len;
+        }
+
+        let live = inner.len - inner.dead;
+        if inner.dead >= COMPACT_THRESHOLD && inner.dead > live {
+            self.compact_inner(&mut inner)?;
+        }
+
+        Ok(())
+    }
+
+    fn keys(&self, kind: u8) -> Vec<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .index
+            .keys()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, key)| key.to_owned())
+            .collect()
+    }
+
+    fn compact_inner(&self, inner: &mut SingleFileInner) -> io::Result<()> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".compact");
+        let temp_path = PathBuf::from(temp_path);
+
+        {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            writer.write_all(FILE_MAGIC)?;
+            writer.write_all(&FILE_VERSION.to_le_bytes())?;
+
+            let mut entries: Vec<_> = inner
+                .index
+                .iter()
+                .map(|(key, &entry)| (key.to_owned(), entry))
+                .collect();
+            entries.sort_by_key(|(_, (offset, _, _))| *offset);
+
+            // Damaged records are copied as they are, keeping their old checksum so that
+            // they are still reported as damaged.
+            for ((kind, key), (offset, length, checksum)) in entries {
+                let mut data = vec![0; length as usize];
+                inner.file.seek(SeekFrom::Start(offset))?;
+                inner.file.read_exact(&mut data)?;
+
+                let checksum = checksum.unwrap_or_else(|| crc32fast::hash(&data));
+                writer.write_all(&record_header(kind, &key, length, checksum))?;
+                writer.write_all(&data)?;
+            }
+
+            writer.into_inner()?.sync_all()?;
+        }
+
+        fs::rename(&temp_path, &self.path)?;
+        *inner = Self::open_inner(&self.path)?.0;
+
+        Ok(())
+    }
+}
+
+fn chunk_key(coords: &Vec2<i32>) -> String {
+    format!("{},{}", coords.0, coords.1)
+}
+
+fn parse_chunk_key(key: &str) -> Option<Vec2<i32>> {
+    let (x, z) = key.split_once(',')?;
+    Some(Vec2(x.parse().ok()?, z.parse().ok()?))
+}
+
+fn record_header(kind: u8, key: &str, length: u32, checksum: u32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(header_len(key.len(), FILE_VERSION) as usize);
+    bytes.push(kind);
+    bytes.extend_from_slice(&(key.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(key.as_bytes());
+    bytes.extend_from_slice(&length.to_le_bytes());
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Length of a record header: kind, key length, key, data length and, from version 2, checksum.
+fn header_len(key_len: usize, version: u32) -> u64 {
+    1 + 2 + key_len as u64 + 4 + if version >= 2 { 4 } else { 0 }
+}
+
+/// Read the header of the next record. Returns `None` at the end of the file.
+fn read_record_header(reader: &mut impl Read, version: u32) -> io::Result<Option<RecordHeader>> {
+    let mut kind = [0; 1];
+
+    if reader.read(&mut kind)? == 0 {
+        return Ok(None);
+    }
+
+    let mut key_len = [0; 2];
+    reader.read_exact(&mut key_len)?;
+
+    let mut key = vec![0; u16::from_le_bytes(key_len) as usize];
+    reader.read_exact(&mut key)?;
+    let key = String::from_utf8(key)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Record key is not UTF-8."))?;
+
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+
+    let checksum = if version >= 2 {
+        let mut checksum = [0; 4];
+        reader.read_exact(&mut checksum)?;
+        Some(u32::from_le_bytes(checksum))
+    } else {
+        None
+    };
+
+    Ok(Some((kind[0], key, u32::from_le_bytes(length), checksum)))
+}
+
+impl ChunkStorage for SingleFileStorage {
+    fn load_chunk(&self, coords: &Vec2<i32>) -> io::Result<Option<Vec<u8>>> {
+        self.load(KIND_CHUNK, &chunk_key(coords))
+    }
+
+    fn save_chunk(&self, coords: &Vec2<i32>, data: &[u8]) -> io::Result<()> {
+        self.save(KIND_CHUNK, &chunk_key(coords), Some(data))
+    }
+
+    fn delete_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        self.save(KIND_CHUNK, &chunk_key(coords), None)
+    }
+
+    fn list_chunks(&self) -> io::Result<Vec<Vec2<i32>>> {
+        Ok(self
+            .keys(KIND_CHUNK)
+            .iter()
+            .filter_map(|key| parse_chunk_key(key))
+            .collect())
+    }
+
+    fn has_chunk(&self, coords: &Vec2<i32>) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .index
+            .contains_key(&(KIND_CHUNK, chunk_key(coords)))
+    }
+}
+
+impl WorldStorage for SingleFileStorage {
+    fn load_entity(&self, id: &str) -> io::Result<Option<Vec<u8>>> {
+        self.load(KIND_ENTITY, id)
+    }
+
+    fn save_entity(&self, id: &str, data: &[u8]) -> io::Result<()> {
+        self.save(KIND_ENTITY, id, Some(data))
+    }
+
+    fn delete_entity(&self, id: &str) -> io::Result<()> {
+        self.save(KIND_ENTITY, id, None)
+    }
+
+    fn list_entities(&self) -> io::Result<Vec<String>> {
+        Ok(self.keys(KIND_ENTITY))
+    }
+
+    fn load_metadata(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        self.load(KIND_METADATA, key)
+    }
+
+    fn save_metadata(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.save(KIND_METADATA, key, Some(data))
+    }
+
+    fn delete_metadata(&self, key: &str) -> io::Result<()> {
+        self.save(KIND_METADATA, key, None)
+    }
+
+    fn list_metadata(&self) -> io::Result<Vec<String>> {
+        Ok(self.keys(KIND_METADATA))
+    }
+
+    fn quarantine_chunk(&self, coords: &Vec2<i32>) -> io::Result<()> {
+        if let Some((data, _)) = self.read(KIND_CHUNK, &chunk_key(coords))? {
+            self.save(KIND_QUARANTINE, &quarantine_name(coords), Some(&data))?;
+        }
+
+        self.delete_chunk(coords)
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ChunkStorage, DirectoryStorage, WorldStorage};
+
+/// Name of the folder inside `save_dir` that snapshots are kept in by default.
+pub const SNAPSHOTS_FOLDER: &str = "snapshots";
+
+/// The metadata key a snapshot keeps its own information under. Written last, so a snapshot
+/// without it was never finished.
+pub const SNAPSHOT_INFO_KEY: &str = "snapshot.json";
+
+/// Prefix of metadata keys holding quarantined chunks, which are never copied into snapshots.
+const QUARANTINE_PREFIX: &str = "quarantine.";
+
+/// Information about a world snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    /// Name of the snapshot, which is also the name of its folder.
+    pub name: String,
+
+    /// Name of the world the snapshot was taken of.
+    pub world: String,
+
+    /// When the snapshot was taken, in seconds since the unix epoch.
+    pub created_at: u64,
+
+    /// Number of chunks in the snapshot.
+    pub chunks: usize,
+
+    /// Number of entities in the snapshot.
+    pub entities: usize,
+}
+
+/// A folder of world snapshots. Each snapshot is a complete copy of a world's storage, kept as a
+/// `DirectoryStorage` no matter which backend the world itself saves to.
+pub struct Snapshots {
+    folder: PathBuf,
+}
+
+impl Snapshots {
+    /// Manage the snapshots inside a folder. The folder is created when the first snapshot is taken.
+    pub fn new(folder: &Path) -> Self {
+        Self {
+            folder: folder.to_owned(),
+        }
+    }
+
+    /// The folder the snapshots are kept in.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Copy everything in a storage into a new snapshot, named after the current local time.
+    /// The copy is made in a temporary folder that is only renamed into place once complete.
+    pub fn create(&self, world: &str, storage: &dyn WorldStorage) -> io::Result<SnapshotInfo> {
+        fs::create_dir_all(&self.folder)?;
+
+        let base = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let mut name = base.clone();
+        let mut count = 1;
+
+        while self.path(&name).exists() {
+            count += 1;
+            name = format!("{}_{}", base, count);
+        }
+
+        let mut temp_path = self.path(&name).into_os_string();
+        temp_path.push(super::TEMP_EXTENSION);
+        let temp_path = PathBuf::from(temp_path);
+
+        if temp_path.exists() {
+            fs::remove_dir_all(&temp_path)?;
+        }
+
+        let info = {
+            let snapshot = DirectoryStorage::new(&temp_path);
+
+            let chunks = storage.list_chunks()?;
+            for coords in &chunks {
+                if let Some(data) = storage.load_chunk(coords)? {
+                    snapshot.save_chunk(coords, &data)?;
+                }
+            }
+
+            let entities = storage.list_entities()?;
+            for id in &entities {
+                if let Some(data) = storage.load_entity(id)? {
+                    snapshot.save_entity(id, &data)?;
+                }
+            }
+
+            for key in storage.list_metadata()? {
+                if key == SNAPSHOT_INFO_KEY || key.starts_with(QUARANTINE_PREFIX) {
+                    continue;
+                }
+
+                if let Some(data) = storage.load_metadata(&key)? {
+                    snapshot.save_metadata(&key, &data)?;
+                }
+            }
+
+            let info = SnapshotInfo {
+                name: name.clone(),
+                world: world.to_owned(),
+                created_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|time| time.as_secs())
+                    .unwrap_or_default(),
+                chunks: chunks.len(),
+                entities: entities.len(),
+            };
+
+            snapshot.save_metadata(SNAPSHOT_INFO_KEY, &serde_json::to_vec(&info)?)?;
+
+            info
+        };
+
+        fs::rename(&temp_path, self.path(&name))?;
+
+        Ok(info)
+    }
+
+    /// List the finished snapshots, oldest first.
+    pub fn list(&self) -> io::Result<Vec<SnapshotInfo>> {
+        let entries = match fs::read_dir(&self.folder) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut snapshots = vec![];
+
+        for entry in entries {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let mut path = entry.path();
+            path.push(SNAPSHOT_INFO_KEY);
+
+            if let Ok(data) = fs::read(&path) {
+                if let Ok(info) = serde_json::from_slice::<SnapshotInfo>(&data) {
+                    snapshots.push(info);
+                }
+            }
+        }
+
+        snapshots.sort_by(|a, b| (a.created_at, &a.name).cmp(&(b.created_at, &b.name)));
+
+        Ok(snapshots)
+    }
+
+    /// Open a finished snapshot to read from.
+    pub fn open(&self, name: &str) -> io::Result<DirectoryStorage> {
+        let path = self.checked_path(name)?;
+        let snapshot = DirectoryStorage::new(&path);
+
+        if snapshot.load_metadata(SNAPSHOT_INFO_KEY)?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Snapshot {:?} was never finished.", name),
+            ));
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Delete a snapshot.
+    pub fn delete(&self, name: &str) -> io::Result<()> {
+        fs::remove_dir_all(self.checked_path(name)?)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        let mut path = self.folder.clone();
+        path.push(name);
+        path
+    }
+
+    /// Path of an existing snapshot, refusing names that would point outside of the folder.
+    fn checked_path(&self, name: &str) -> io::Result<PathBuf> {
+        let path = self.path(name);
+
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') || !path.is_dir()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No snapshot named {:?}.", name),
+            ));
+        }
+
+        Ok(path)
+    }
+}
@@ -169,6 +169,8 @@ impl<'a> System<'a> for ChunkGeneratingSystem {
                         max_height: config.max_height,
                         sub_chunks: config.sub_chunks,
                         size: config.chunk_size,
+                        paletted: config.paletted_chunks,
+                        encoding: config.voxel_encoding,
                     },
                 );
 
@@ -293,8 +295,21 @@ impl<'a> System<'a> for ChunkGeneratingSystem {
             chunk.status = ChunkStatus::Ready;
             let is_updating = r#type == MessageType::Update;
 
+            // Updated chunks are not sent again, unless they were relit and clients need the new lights.
+            let is_relit = is_updating
+                && chunk.has_lights
+                && chunks
+                    .raw(&chunk.coords)
+                    .is_some_and(|old_chunk| !old_chunk.has_lights);
+
             if !is_updating {
                 chunks.add_chunk_to_send(&chunk.coords, &r#type, false);
+            } else if is_relit {
+                chunks.add_chunk_to_send(&chunk.coords, &MessageType::Load, false);
+
+                if config.saving {
+                    chunks.add_chunk_to_save(&chunk.coords, false);
+                }
             }
 
             chunks.renew(chunk, is_updating);
@@ -335,6 +350,13 @@ impl<'a> System<'a> for ChunkGeneratingSystem {
                 }
 
                 if let Some(blocks) = pipeline.leftovers.get(&n_coords) {
+                    // Saved lights no longer match the voxels once leftovers land in a chunk.
+                    if let Some(n_chunk) = chunks.raw_mut(&n_coords) {
+                        if n_chunk.status != ChunkStatus::Ready {
+                            n_chunk.has_lights = false;
+                        }
+                    }
+
                     for (j, (voxel, val)) in blocks.iter().enumerate() {
                         let Vec3(vx, vy, vz) = *voxel;
                         chunks.set_raw_voxel(vx, vy, vz, *val);
@@ -378,14 +400,28 @@ impl<'a> System<'a> for ChunkGeneratingSystem {
             let len = ready_chunks.len();
             let processes = ready_chunks
                 .into_iter()
-                .map(|(coords, chunk)| {
+                .map(|(coords, mut chunk)| {
                     let mut space = chunks
                         .make_space(&coords, config.max_light_level as usize)
                         .needs_height_maps()
                         .needs_voxels();
 
-                    if chunk.meshes.is_some() {
+                    // Chunks loaded with their saved lights skip relighting, as long as every
+                    // chunk that light could reach from them has lights too.
+                    let lit = chunk.has_lights
+                        && chunks
+                            .light_traversed_chunks(&coords)
+                            .iter()
+                            .all(|n_coords| {
+                                chunks
+                                    .raw(n_coords)
+                                    .is_some_and(|n_chunk| n_chunk.has_lights)
+                            });
+
+                    if chunk.meshes.is_some() || lit {
                         space = space.needs_lights()
+                    } else {
+                        chunk.has_lights = false;
                     }
 
                     let space = space.strict().build();
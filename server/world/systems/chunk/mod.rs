@@ -1,13 +1,17 @@
 mod current;
 mod generating;
+mod random_ticking;
 mod requests;
 mod saving;
 mod sending;
+mod unloading;
 mod updating;
 
 pub use current::CurrentChunkSystem;
 pub use generating::ChunkGeneratingSystem;
+pub use random_ticking::ChunkRandomTickingSystem;
 pub use requests::ChunkRequestsSystem;
 pub use saving::ChunkSavingSystem;
 pub use sending::ChunkSendingSystem;
+pub use unloading::ChunkUnloadingSystem;
 pub use updating::ChunkUpdatingSystem;
@@ -0,0 +1,85 @@
+use hashbrown::HashSet;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use specs::{Join, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    Chunks, ClientFlag, CurrentChunkComp, Registry, Vec2, Vec3, VoxelAccess, VoxelUpdate,
+    WorldConfig,
+};
+
+/// Gives `config.random_tick_speed` random voxels of every sub chunk near a client a random tick,
+/// calling `Block::random_tick` of the blocks that are picked.
+pub struct ChunkRandomTickingSystem;
+
+impl<'a> System<'a> for ChunkRandomTickingSystem {
+    type SystemData = (
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        WriteExpect<'a, Chunks>,
+        ReadStorage<'a, ClientFlag>,
+        ReadStorage<'a, CurrentChunkComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (config, registry, mut chunks, client_flags, curr_chunks) = data;
+
+        if config.random_tick_speed == 0
+            || !registry
+                .blocks_by_id
+                .values()
+                .any(|block| block.random_tick.is_some())
+        {
+            return;
+        }
+
+        let radius = config.random_tick_radius as i32;
+        let mut ticking = HashSet::new();
+
+        for (_, curr_chunk) in (&client_flags, &curr_chunks).join() {
+            let Vec2(cx, cz) = curr_chunk.coords;
+
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    let coords = Vec2(cx + x, cz + z);
+
+                    if chunks.is_chunk_ready(&coords) {
+                        ticking.insert(coords);
+                    }
+                }
+            }
+        }
+
+        let chunk_size = config.chunk_size;
+        let sub_chunk_height = config.max_height / config.sub_chunks;
+
+        let updates: Vec<VoxelUpdate> = ticking
+            .into_par_iter()
+            .flat_map(|Vec2(cx, cz)| {
+                let mut rng = fastrand::Rng::new();
+                let mut updates = vec![];
+
+                for level in 0..config.sub_chunks {
+                    for _ in 0..config.random_tick_speed {
+                        let voxel = Vec3(
+                            cx * chunk_size as i32 + rng.usize(..chunk_size) as i32,
+                            (level * sub_chunk_height + rng.usize(..sub_chunk_height)) as i32,
+                            cz * chunk_size as i32 + rng.usize(..chunk_size) as i32,
+                        );
+
+                        let id = chunks.get_voxel(voxel.0, voxel.1, voxel.2);
+
+                        if let Some(random_tick) = &registry.get_block_by_id(id).random_tick {
+                            updates.extend(random_tick(voxel, &*chunks, &registry));
+                        }
+                    }
+                }
+
+                updates
+            })
+            .collect();
+
+        if !updates.is_empty() {
+            chunks.update_voxels(&updates);
+        }
+    }
+}
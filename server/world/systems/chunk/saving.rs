@@ -1,14 +1,18 @@
 use specs::{ReadExpect, System, WriteExpect};
 
-use crate::{Chunks, WorldConfig};
+use crate::{Chunks, Registry, WorldConfig};
 
 pub struct ChunkSavingSystem;
 
 impl<'a> System<'a> for ChunkSavingSystem {
-    type SystemData = (ReadExpect<'a, WorldConfig>, WriteExpect<'a, Chunks>);
+    type SystemData = (
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        WriteExpect<'a, Chunks>,
+    );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (config, mut chunks) = data;
+        let (config, registry, mut chunks) = data;
 
         if !config.saving {
             return;
@@ -20,10 +24,12 @@ impl<'a> System<'a> for ChunkSavingSystem {
             count += 1;
 
             if let Some(coords) = chunks.to_save.pop_front() {
-                if !chunks.save(&coords) {
+                if !chunks.save(&coords, &registry) {
                     chunks.add_chunk_to_save(&coords, false);
                 }
             }
         }
+
+        chunks.save_stale_lights();
     }
 }
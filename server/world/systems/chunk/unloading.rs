@@ -0,0 +1,225 @@
+use hashbrown::HashSet;
+use log::warn;
+use specs::{
+    Entities, LazyUpdate, Read, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage,
+};
+
+use crate::{
+    Bookkeeping, ChunkInterests, ChunkStatus, ChunkUtils, Chunks, CurrentChunkComp, ETypeComp,
+    EntitiesSaver, EntityFlag, IDComp, JsonComp, Mesher, MetadataComp, Pipeline, Registry, Stats,
+    Vec2, VoxelComp, WorldConfig,
+};
+
+/// Unloads chunks that no client has been interested in for `config.unload_chunks_after` ticks,
+/// saving them first if they have unsaved changes. Block entities are unloaded along with their
+/// chunk, and revived once it is loaded again.
+pub struct ChunkUnloadingSystem;
+
+impl<'a> System<'a> for ChunkUnloadingSystem {
+    type SystemData = (
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Registry>,
+        ReadExpect<'a, Stats>,
+        ReadExpect<'a, ChunkInterests>,
+        ReadExpect<'a, Pipeline>,
+        ReadExpect<'a, Mesher>,
+        ReadExpect<'a, EntitiesSaver>,
+        WriteExpect<'a, Bookkeeping>,
+        WriteExpect<'a, Chunks>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadStorage<'a, IDComp>,
+        ReadStorage<'a, ETypeComp>,
+        ReadStorage<'a, JsonComp>,
+        WriteStorage<'a, MetadataComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            config,
+            registry,
+            stats,
+            interests,
+            pipeline,
+            mesher,
+            entities_saver,
+            mut bookkeeping,
+            mut chunks,
+            entities,
+            lazy,
+            ids,
+            etypes,
+            jsons,
+            mut metadatas,
+        ) = data;
+
+        if config.unload_chunks_after == 0 {
+            return;
+        }
+
+        // Revive the block entities of chunks that were loaded again.
+        let reloaded: Vec<Vec2<i32>> = chunks
+            .unloaded_block_entities
+            .keys()
+            .filter(|&coords| chunks.map.contains_key(coords))
+            .cloned()
+            .collect();
+
+        for coords in reloaded {
+            for (id, etype, metadata) in chunks.unloaded_block_entities.remove(&coords).unwrap() {
+                let voxel = metadata.get::<VoxelComp>("voxel").unwrap_or_default();
+                let json = metadata
+                    .get::<JsonComp>("json")
+                    .unwrap_or(JsonComp::new("{}"));
+
+                let entity = entities.create();
+                chunks.block_entities.insert(voxel.0.clone(), entity);
+                bookkeeping.unloaded.remove(&id);
+
+                lazy.insert(entity, IDComp::new(&id));
+                lazy.insert(entity, EntityFlag);
+                lazy.insert(entity, CurrentChunkComp::default());
+                lazy.insert(entity, ETypeComp::new(&etype, true));
+                lazy.insert(entity, json);
+                lazy.insert(entity, voxel);
+                lazy.insert(entity, metadata);
+            }
+        }
+
+        // Interested chunks need every chunk light could traverse into to stay loaded.
+        let mut needed = HashSet::new();
+
+        for coords in interests.map.keys() {
+            needed.extend(chunks.light_traversed_chunks(coords));
+        }
+
+        // Keep the preloaded area around the origin.
+        if config.preload {
+            let radius = config.preload_radius as i32;
+
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    needed.insert(Vec2(x, z));
+                }
+            }
+        }
+
+        for (voxel, _) in chunks.updates.iter() {
+            needed.insert(ChunkUtils::map_voxel_to_chunk(
+                voxel.0,
+                voxel.1,
+                voxel.2,
+                config.chunk_size,
+            ));
+        }
+
+        let tick = stats.tick;
+        let mut to_unload = vec![];
+
+        // Forget about chunks that were removed some other way.
+        let Chunks {
+            map, idle_since, ..
+        } = &mut *chunks;
+        idle_since.retain(|coords, _| map.contains_key(coords));
+
+        let coords_list: Vec<Vec2<i32>> = chunks.map.keys().cloned().collect();
+
+        for coords in coords_list {
+            if needed.contains(&coords) {
+                chunks.idle_since.remove(&coords);
+                continue;
+            }
+
+            let since = *chunks.idle_since.entry(coords.clone()).or_insert(tick);
+
+            if tick - since < config.unload_chunks_after {
+                continue;
+            }
+
+            // Chunks still being generated or meshed are left alone until they are done.
+            let done = chunks.raw(&coords).is_some_and(|chunk| {
+                chunk.status == ChunkStatus::Ready
+                    && !pipeline.has_chunk(&coords)
+                    && !mesher.has_chunk(&coords)
+            });
+
+            if done {
+                to_unload.push(coords);
+            }
+        }
+
+        if to_unload.is_empty() {
+            return;
+        }
+
+        // Save everything before unloading anything, so that chunks unloaded together do not
+        // mark each other's lights as stale. Chunks with scheduled ticks are saved to keep them,
+        // and chunks that fail to save are kept loaded until they do.
+        if config.saving {
+            to_unload.retain(|coords| {
+                !(chunks.to_save.contains(coords) || chunks.ticks.has_chunk(coords))
+                    || chunks.save(coords, &registry)
+            });
+
+            chunks.save_stale_lights();
+        }
+
+        let to_unload: HashSet<Vec2<i32>> = to_unload.into_iter().collect();
+
+        // Save the block entities of unloaded chunks, and keep them around to revive later.
+        let block_entities: Vec<_> = chunks
+            .block_entities
+            .iter()
+            .filter(|(voxel, _)| {
+                to_unload.contains(&ChunkUtils::map_voxel_to_chunk(
+                    voxel.0,
+                    voxel.1,
+                    voxel.2,
+                    config.chunk_size,
+                ))
+            })
+            .map(|(voxel, entity)| (voxel.to_owned(), *entity))
+            .collect();
+
+        for (voxel, entity) in block_entities {
+            chunks.block_entities.remove(&voxel);
+
+            let (id, etype, metadata) = match (
+                ids.get(entity),
+                etypes.get(entity),
+                metadatas.get_mut(entity),
+            ) {
+                (Some(id), Some(etype), Some(metadata)) => {
+                    (id.0.to_owned(), etype.0.to_owned(), metadata)
+                }
+                _ => continue,
+            };
+
+            // The metadata might not have caught up with a block entity created this tick.
+            metadata.set("voxel", &VoxelComp::new(voxel.0, voxel.1, voxel.2));
+            if let Some(json) = jsons.get(entity) {
+                metadata.set("json", json);
+            }
+
+            entities_saver.save(&id, &etype, true, metadata);
+
+            let coords =
+                ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, config.chunk_size);
+            chunks
+                .unloaded_block_entities
+                .entry(coords)
+                .or_default()
+                .push((id.to_owned(), etype, metadata.to_owned()));
+
+            bookkeeping.unloaded.insert(id);
+
+            if let Err(e) = entities.delete(entity) {
+                warn!("Could not unload block entity at {:?}: {}", voxel, e);
+            }
+        }
+
+        for coords in &to_unload {
+            chunks.unload(coords);
+        }
+    }
+}
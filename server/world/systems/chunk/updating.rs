@@ -1,15 +1,19 @@
 use std::collections::VecDeque;
 
+use hashbrown::{HashMap, HashSet};
+
 use log::info;
 use nanoid::nanoid;
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use specs::{Entities, LazyUpdate, ReadExpect, System, WorldExt, WriteExpect};
 
 use crate::{
-    BlockUtils, ChunkUtils, Chunks, ClientFilter, CollisionsComp, CurrentChunkComp, ETypeComp,
-    EntityFlag, IDComp, JsonComp, LightColor, LightNode, Lights, Mesher, Message, MessageQueue,
-    MessageType, MetadataComp, Registry, Stats, UpdateProtocol, Vec2, Vec3, VoxelAccess, VoxelComp,
-    VoxelUpdate, WorldConfig,
+    falling_block_body, BlockUtils, ChunkUtils, Chunks, ClientFilter, CollisionsComp,
+    CurrentChunkComp, ETypeComp, EntityFlag, FallingBlockComp, FluidUtils, History, IDComp,
+    JsonComp, LightColor, LightNode, Lights, Mesher, Message, MessageQueue, MessageType,
+    MetadataComp, NeighborChange, PositionComp, Registry, RigidBodyComp, SignalNode, Signals,
+    Stats, UpdateProtocol, Vec2, Vec3, VoxelAccess, VoxelComp, VoxelUpdate, WorldConfig,
+    FALLING_BLOCK_ETYPE, SYSTEM_SOURCE,
 };
 
 pub const VOXEL_NEIGHBORS: [[i32; 3]; 6] = [
@@ -38,6 +42,7 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
         WriteExpect<'a, Chunks>,
         WriteExpect<'a, Mesher>,
         ReadExpect<'a, LazyUpdate>,
+        WriteExpect<'a, History>,
         Entities<'a>,
     );
 
@@ -50,6 +55,7 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
             mut chunks,
             mut mesher,
             mut lazy,
+            mut history,
             mut entities,
         ) = data;
 
@@ -75,24 +81,40 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                 updates.push_back(chunks.updates.pop_front().unwrap());
             }
 
+            let mut detached = HashSet::new();
+            let mut signal_changes = HashSet::new();
+            let mut powered = HashSet::new();
+
             while let Some((voxel, raw)) = updates.pop_front() {
                 let Vec3(vx, vy, vz) = voxel;
+                let depth = chunks.neighbor_depths.remove(&voxel);
+                let source = chunks.update_sources.remove(&voxel);
 
                 let updated_id = BlockUtils::extract_id(raw);
                 let rotation = BlockUtils::extract_rotation(raw);
                 let stage = BlockUtils::extract_stage(raw);
                 let coords = ChunkUtils::map_voxel_to_chunk(vx, vy, vz, config.chunk_size);
 
+                // Dropped updates take their block entity data with them, so it cannot leak into a
+                // later update of the same voxel.
                 if vy < 0 || vy >= config.max_height as i32 || !registry.has_type(updated_id) {
+                    chunks.block_entity_data.remove(&voxel);
                     continue;
                 }
 
                 if !chunks.is_chunk_ready(&coords) {
+                    chunks.block_entity_data.remove(&voxel);
                     continue;
                 }
 
                 if mesher.map.contains(&coords) {
                     chunks.update_voxel(&voxel, raw);
+                    if let Some(depth) = depth {
+                        chunks.neighbor_depths.insert(voxel.to_owned(), depth);
+                    }
+                    if let Some(source) = source {
+                        chunks.update_sources.insert(voxel, source);
+                    }
                     continue;
                 }
 
@@ -106,11 +128,18 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
 
                 if !ready {
                     chunks.update_voxel(&voxel, raw);
+                    if let Some(depth) = depth {
+                        chunks.neighbor_depths.insert(voxel.to_owned(), depth);
+                    }
+                    if let Some(source) = source {
+                        chunks.update_sources.insert(voxel, source);
+                    }
                     continue;
                 }
 
                 let current_id = chunks.get_voxel(vx, vy, vz);
                 if registry.is_air(updated_id) && registry.is_air(current_id) {
+                    chunks.block_entity_data.remove(&voxel);
                     continue;
                 }
 
@@ -129,6 +158,8 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                     });
                 }
 
+                let block_entity_data = chunks.block_entity_data.remove(&voxel);
+
                 // need to add an entity
                 if updated_type.is_entity {
                     let entity = entities.create();
@@ -151,7 +182,10 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                     lazy.insert(entity, MetadataComp::new());
                     lazy.insert(entity, VoxelComp::new(voxel.0, voxel.1, voxel.2));
                     lazy.insert(entity, CurrentChunkComp::default());
-                    lazy.insert(entity, JsonComp::new("{}"));
+                    lazy.insert(
+                        entity,
+                        JsonComp::new(block_entity_data.as_deref().unwrap_or("{}")),
+                    );
                 }
 
                 let current_transparency = current_type.get_rotated_transparency(&rotation);
@@ -161,6 +195,9 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                     updated_type.is_transparent
                 };
 
+                let old_raw = chunks.get_raw_voxel(vx, vy, vz);
+                let old_power = Signals::get_power(&*chunks, &voxel, &registry);
+
                 chunks.set_voxel(vx, vy, vz, updated_id);
 
                 if stage != 0 {
@@ -176,10 +213,116 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                     chunks.mark_voxel_active(&Vec3(vx, vy, vz), ticks + current_tick);
                 }
 
+                // Fluids at and around the changed voxel may flow somewhere new.
+                for [dx, dy, dz] in [[0, 0, 0]].iter().chain(VOXEL_NEIGHBORS.iter()) {
+                    let n_voxel = Vec3(vx + dx, vy + dy, vz + dz);
+                    let n_id = chunks.get_voxel(n_voxel.0, n_voxel.1, n_voxel.2);
+
+                    if let Some(rate) = FluidUtils::flow_rate(n_id, &registry) {
+                        let at = current_tick + rate;
+
+                        if chunks
+                            .ticks
+                            .get(&n_voxel)
+                            .is_none_or(|scheduled| scheduled > at)
+                        {
+                            chunks.mark_voxel_active(&n_voxel, at);
+                        }
+                    }
+                }
+
                 if updated_type.rotatable || updated_type.y_rotatable {
                     chunks.set_voxel_rotation(vx, vy, vz, &rotation);
                 }
 
+                // Tell the blocks around the voxel about the change, unless it ends a long chain of them.
+                let depth = depth.unwrap_or(0);
+                let new_raw = chunks.get_raw_voxel(vx, vy, vz);
+
+                if new_raw != old_raw {
+                    history.record(
+                        source.as_deref().unwrap_or(SYSTEM_SOURCE),
+                        current_tick,
+                        &voxel,
+                        old_raw,
+                        new_raw,
+                    );
+                }
+
+                if new_raw != old_raw && depth < config.max_neighbor_update_depth {
+                    for [dx, dy, dz] in VOXEL_NEIGHBORS {
+                        let n_voxel = Vec3(vx + dx, vy + dy, vz + dz);
+                        let n_id = chunks.get_voxel(n_voxel.0, n_voxel.1, n_voxel.2);
+
+                        if registry.get_block_by_id(n_id).on_neighbor_changed.is_some() {
+                            let change = NeighborChange {
+                                voxel: n_voxel,
+                                offset: Vec3(-dx, -dy, -dz),
+                                old: old_raw,
+                                new: new_raw,
+                            };
+
+                            chunks.neighbor_changes.push_back((change, depth));
+                        }
+                    }
+                }
+
+                // Take away the power the voxel gave off, then power it up again as what it is now.
+                if old_power > 0 || current_type.is_signal_block() || updated_type.is_signal_block()
+                {
+                    let mut changed = vec![];
+
+                    if updated_type.signal_conductor {
+                        chunks.set_voxel_stage(vx, vy, vz, 0);
+                    }
+
+                    if old_power > 0 {
+                        changed.extend(Signals::remove_power(
+                            &mut *chunks,
+                            &voxel,
+                            old_power,
+                            &registry,
+                            &config,
+                        ));
+                    }
+
+                    if updated_type.signal_conductor {
+                        let level = Signals::get_received_power(&*chunks, &voxel, &registry)
+                            .saturating_sub(1);
+                        chunks.set_voxel_stage(vx, vy, vz, level);
+                    }
+
+                    let level = Signals::get_power(&*chunks, &voxel, &registry);
+
+                    if level > 0 {
+                        changed.extend(Signals::flood_power(
+                            &mut *chunks,
+                            VecDeque::from([SignalNode {
+                                voxel: [vx, vy, vz],
+                                level,
+                            }]),
+                            &registry,
+                            &config,
+                        ));
+                    }
+
+                    for c_voxel in changed {
+                        if powered.insert(c_voxel.clone()) {
+                            results.push(UpdateProtocol {
+                                vx: c_voxel.0,
+                                vy: c_voxel.1,
+                                vz: c_voxel.2,
+                                voxel: 0,
+                                light: 0,
+                            });
+                        }
+
+                        signal_changes.insert(c_voxel);
+                    }
+
+                    signal_changes.insert(voxel.clone());
+                }
+
                 // updating the height map
                 if registry.is_air(updated_id) {
                     if vy == height as i32 {
@@ -435,6 +578,46 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                     voxel: 0,
                     light: 0,
                 });
+
+                // Gravity blocks with nothing solid below them fall as entities, leaving air behind.
+                for g_voxel in [voxel.clone(), Vec3(vx, vy + 1, vz)] {
+                    let Vec3(gx, gy, gz) = g_voxel;
+
+                    if gy <= 0 || gy >= max_height || detached.contains(&g_voxel) {
+                        continue;
+                    }
+
+                    let g_raw = chunks.get_raw_voxel(gx, gy, gz);
+                    let below = registry.get_block_by_id(chunks.get_voxel(gx, gy - 1, gz));
+
+                    if !registry
+                        .get_block_by_id(BlockUtils::extract_id(g_raw))
+                        .gravity
+                        || (!below.is_empty && !below.is_fluid)
+                    {
+                        continue;
+                    }
+
+                    let body = falling_block_body(&Vec3(
+                        gx as f32 + 0.5,
+                        gy as f32 + 0.5,
+                        gz as f32 + 0.5,
+                    ));
+
+                    let entity = entities.create();
+                    lazy.insert(entity, IDComp::new(&nanoid!()));
+                    lazy.insert(entity, EntityFlag);
+                    lazy.insert(entity, ETypeComp::new(FALLING_BLOCK_ETYPE, false));
+                    lazy.insert(entity, MetadataComp::new());
+                    lazy.insert(entity, CurrentChunkComp::default());
+                    lazy.insert(entity, CollisionsComp::new());
+                    lazy.insert(entity, PositionComp(body.get_position()));
+                    lazy.insert(entity, RigidBodyComp::new(&body));
+                    lazy.insert(entity, FallingBlockComp::new(g_raw));
+
+                    detached.insert(g_voxel.clone());
+                    updates.push_back((g_voxel, 0));
+                }
             }
 
             if !red_flood.is_empty() {
@@ -524,50 +707,168 @@ impl<'a> System<'a> for ChunkUpdatingSystem {
                 let new_message = Message::new(&MessageType::Update).updates(&results).build();
                 message_queue.push((new_message, ClientFilter::All));
             }
-        }
 
-        let active_voxels = chunks.active_voxels.clone();
+            // Let the signal consumers at and around the voxels whose power changed know.
+            let mut consumers = HashSet::new();
 
-        let active_voxels: Vec<(Option<(u64, Vec3<i32>)>, Vec<VoxelUpdate>)> = active_voxels
-            .into_par_iter()
-            .map(|(active_at, voxel)| {
-                if active_at > current_tick {
-                    return (Some((active_at, voxel)), vec![]);
+            for Vec3(vx, vy, vz) in signal_changes {
+                for [dx, dy, dz] in [[0, 0, 0]].iter().chain(VOXEL_NEIGHBORS.iter()) {
+                    let c_voxel = Vec3(vx + dx, vy + dy, vz + dz);
+                    let c_id = chunks.get_voxel(c_voxel.0, c_voxel.1, c_voxel.2);
+
+                    if registry.get_block_by_id(c_id).on_signal.is_some() {
+                        consumers.insert(c_voxel);
+                    }
                 }
+            }
 
-                let Vec3(vx, vy, vz) = voxel;
-                let id = chunks.get_voxel(vx, vy, vz);
-                let block = registry.get_block_by_id(id);
+            let signal_updates: Vec<VoxelUpdate> = consumers
+                .into_iter()
+                .flat_map(|c_voxel| {
+                    let Vec3(cx, cy, cz) = c_voxel;
+                    let block = registry.get_block_by_id(chunks.get_voxel(cx, cy, cz));
+                    let power = Signals::get_received_power(&*chunks, &c_voxel, &registry);
 
-                if block.active_updater.is_none() {
-                    return (None, vec![]);
+                    (block.on_signal.as_ref().unwrap())(c_voxel, power, &*chunks, &registry)
+                })
+                .collect();
+
+            if !signal_updates.is_empty() {
+                chunks.update_voxels(&signal_updates);
+            }
+        }
+
+        let changes = config
+            .max_neighbor_updates_per_tick
+            .min(chunks.neighbor_changes.len());
+
+        if changes > 0 {
+            let changes: Vec<(NeighborChange, u32)> =
+                chunks.neighbor_changes.drain(..changes).collect();
+
+            let follow_ups: Vec<(VoxelUpdate, u32)> = changes
+                .into_par_iter()
+                .flat_map(|(change, depth)| {
+                    let Vec3(vx, vy, vz) = change.voxel;
+
+                    if !chunks.is_chunk_ready(&ChunkUtils::map_voxel_to_chunk(
+                        vx,
+                        vy,
+                        vz,
+                        config.chunk_size,
+                    )) {
+                        return vec![];
+                    }
+
+                    match &registry
+                        .get_block_by_id(chunks.get_voxel(vx, vy, vz))
+                        .on_neighbor_changed
+                    {
+                        Some(on_neighbor_changed) => {
+                            on_neighbor_changed(&change, &*chunks, &registry)
+                                .into_iter()
+                                .map(|update| (update, depth + 1))
+                                .collect()
+                        }
+                        None => vec![],
+                    }
+                })
+                .collect();
+
+            if !follow_ups.is_empty() {
+                let mut updates = Vec::with_capacity(follow_ups.len());
+
+                for ((voxel, raw), depth) in follow_ups {
+                    chunks
+                        .neighbor_depths
+                        .entry(voxel.clone())
+                        .and_modify(|existing| *existing = (*existing).max(depth))
+                        .or_insert(depth);
+                    updates.push((voxel, raw));
                 }
 
-                let updates = (&block.active_updater.as_ref().unwrap())(
-                    Vec3(vx, vy, vz),
-                    &*chunks,
-                    &registry,
-                );
+                chunks.update_voxels(&updates);
+            }
+        }
 
-                (None, updates)
-            })
-            .collect();
+        let due = chunks.ticks.take_due(current_tick);
+
+        // Chunks that are still loading tick as soon as they are ready.
+        let (due, waiting): (Vec<Vec3<i32>>, Vec<Vec3<i32>>) = due.into_iter().partition(|voxel| {
+            chunks.is_chunk_ready(&ChunkUtils::map_voxel_to_chunk(
+                voxel.0,
+                voxel.1,
+                voxel.2,
+                config.chunk_size,
+            ))
+        });
 
-        let active_voxels: Vec<(u64, Vec3<i32>)> = active_voxels
-            .into_iter()
-            .filter_map(|(active_at, updates)| {
-                if !updates.is_empty() {
-                    chunks.update_voxels(&updates);
+        for voxel in waiting {
+            chunks.mark_voxel_active(&voxel, current_tick + 1);
+        }
+
+        // Fluids flow in batches per chunk, so that a flood does not hold up lighting and meshing.
+        let mut fluids: HashMap<Vec2<i32>, Vec<Vec3<i32>>> = HashMap::new();
+        let mut others = vec![];
+
+        for voxel in due {
+            let Vec3(vx, vy, vz) = voxel;
+
+            if FluidUtils::flow_rate(chunks.get_voxel(vx, vy, vz), &registry).is_some() {
+                fluids
+                    .entry(ChunkUtils::map_voxel_to_chunk(
+                        vx,
+                        vy,
+                        vz,
+                        config.chunk_size,
+                    ))
+                    .or_default()
+                    .push(voxel);
+            } else {
+                others.push(voxel);
+            }
+        }
+
+        for batch in fluids.values_mut() {
+            if batch.len() > config.max_fluid_ticks_per_chunk {
+                for voxel in batch.drain(config.max_fluid_ticks_per_chunk..) {
+                    chunks.mark_voxel_active(&voxel, current_tick + 1);
                 }
+            }
+        }
 
-                if let Some((active_at, voxel)) = active_at {
-                    return Some((active_at, voxel));
+        let mut updates: Vec<VoxelUpdate> = others
+            .into_par_iter()
+            .flat_map(|voxel| {
+                let Vec3(vx, vy, vz) = voxel;
+                let id = chunks.get_voxel(vx, vy, vz);
+                let block = registry.get_block_by_id(id);
+
+                match &block.active_updater {
+                    Some(updater) => updater(Vec3(vx, vy, vz), &*chunks, &registry),
+                    None => vec![],
                 }
+            })
+            .collect();
 
-                None
+        let fluid_updates: Vec<Vec<VoxelUpdate>> = fluids
+            .into_par_iter()
+            .map(|(_, batch)| {
+                batch
+                    .iter()
+                    .flat_map(|voxel| FluidUtils::flow(voxel, &*chunks, &registry))
+                    .collect()
             })
             .collect();
 
-        chunks.active_voxels = active_voxels;
+        for batch in fluid_updates {
+            updates.extend(batch);
+        }
+
+        if !updates.is_empty() {
+            chunks.update_voxels(&updates);
+        }
+
+        history.flush();
     }
 }
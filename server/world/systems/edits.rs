@@ -0,0 +1,110 @@
+use hashbrown::HashMap;
+use serde_json::json;
+use specs::{ReadExpect, System, WriteExpect};
+
+use crate::{
+    ChunkUtils, Chunks, ClientFilter, EditJob, Edits, EventBuilder, Events, Vec3, VoxelAccess,
+    WorldConfig, EDIT_PROGRESS_EVENT,
+};
+
+/// Feeds queued world edits into the voxel update queue, as much as fits within
+/// `config.max_updates_per_tick`, and tells each client how far along its edits are.
+pub struct EditsSystem;
+
+impl<'a> System<'a> for EditsSystem {
+    type SystemData = (
+        ReadExpect<'a, WorldConfig>,
+        WriteExpect<'a, Chunks>,
+        WriteExpect<'a, Edits>,
+        WriteExpect<'a, Events>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (config, mut chunks, mut edits, mut events) = data;
+
+        if edits.jobs.is_empty() {
+            return;
+        }
+
+        let mut budget = config
+            .max_updates_per_tick
+            .saturating_sub(chunks.updates.len());
+
+        if budget == 0 {
+            return;
+        }
+
+        // Voxels still waiting in the update queue are changed before the edit gets to them.
+        let mut pending: HashMap<Vec3<i32>, u64> = chunks.updates.iter().cloned().collect();
+        let mut finished: Vec<EditJob> = vec![];
+
+        while budget > 0 {
+            let job = match edits.jobs.front_mut() {
+                Some(job) => job,
+                None => break,
+            };
+
+            let start = job.applied + job.skipped;
+            let end = (start + budget).min(job.updates.len());
+            let mut updates = vec![];
+
+            for (voxel, raw) in &job.updates[start..end] {
+                let Vec3(vx, vy, vz) = *voxel;
+
+                if vy < 0
+                    || vy >= config.max_height as i32
+                    || !chunks.is_chunk_ready(&ChunkUtils::map_voxel_to_chunk(
+                        vx,
+                        vy,
+                        vz,
+                        config.chunk_size,
+                    ))
+                {
+                    job.skipped += 1;
+                    continue;
+                }
+
+                job.applied += 1;
+
+                let old = pending
+                    .get(voxel)
+                    .copied()
+                    .unwrap_or_else(|| chunks.get_raw_voxel(vx, vy, vz));
+
+                if old == *raw {
+                    continue;
+                }
+
+                pending.insert(voxel.to_owned(), *raw);
+                job.changes.push((voxel.to_owned(), old, *raw));
+                updates.push((voxel.to_owned(), *raw));
+            }
+
+            if !updates.is_empty() {
+                chunks.update_voxels_as(&job.client_id, &updates);
+            }
+
+            budget -= end - start;
+
+            events.dispatch(
+                EventBuilder::new(EDIT_PROGRESS_EVENT)
+                    .payload(json!({
+                        "id": job.id,
+                        "applied": job.applied,
+                        "skipped": job.skipped,
+                        "total": job.updates.len(),
+                    }))
+                    .filter(ClientFilter::Direct(job.client_id.to_owned()))
+                    .build(),
+            );
+
+            if end == job.updates.len() {
+                finished.extend(edits.jobs.pop_front());
+            }
+        }
+
+        for job in finished {
+            edits.finish(job);
+        }
+    }
+}
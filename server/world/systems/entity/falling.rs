@@ -0,0 +1,102 @@
+use hashbrown::HashSet;
+use log::warn;
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    Chunks, FallingBlockComp, Registry, RigidBody, RigidBodyComp, Vec3, VoxelAccess, WorldConfig,
+    AABB,
+};
+
+/// Entity type of blocks that are falling.
+pub const FALLING_BLOCK_ETYPE: &str = "vox-builtin:falling-block";
+
+/// Create the rigid body of a falling block centered at a position. It is a little smaller than a
+/// voxel so that it does not catch on the walls it falls past.
+pub fn falling_block_body(position: &Vec3<f32>) -> RigidBody {
+    let mut body = RigidBody::new(
+        &AABB::new()
+            .scale_x(0.98)
+            .scale_y(0.98)
+            .scale_z(0.98)
+            .build(),
+    )
+    .build();
+    body.set_position(position.0, position.1, position.2);
+    body
+}
+
+/// Turns falling blocks that have landed back into voxels, on top of whatever they landed in.
+pub struct FallingBlocksSystem;
+
+impl<'a> System<'a> for FallingBlocksSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Registry>,
+        ReadExpect<'a, WorldConfig>,
+        WriteExpect<'a, Chunks>,
+        ReadStorage<'a, FallingBlockComp>,
+        ReadStorage<'a, RigidBodyComp>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, registry, config, mut chunks, falling_blocks, bodies) = data;
+
+        let max_height = config.max_height as i32;
+
+        // Voxels with an update still queued, which a landing block must not overwrite.
+        let mut pending: HashSet<Vec3<i32>> = chunks
+            .updates
+            .iter()
+            .map(|(voxel, _)| voxel.clone())
+            .collect();
+
+        for (entity, falling_block, body) in (&entities, &falling_blocks, &bodies).join() {
+            let Vec3(px, py, pz) = body.0.get_position();
+
+            // Fell out of the world.
+            if py < 0.0 {
+                entities
+                    .delete(entity)
+                    .expect("Failed to delete falling block");
+                continue;
+            }
+
+            if body.0.at_rest_y() >= 0 {
+                continue;
+            }
+
+            let (vx, vz) = (px.floor() as i32, pz.floor() as i32);
+            let mut vy = py.floor() as i32;
+
+            // A block can sink into a voxel that another falling block has just landed in, so it
+            // lands on top of whatever is there instead.
+            let mut landed = false;
+
+            while vy < max_height {
+                let current = registry.get_block_by_id(chunks.get_voxel(vx, vy, vz));
+                let voxel = Vec3(vx, vy, vz);
+
+                if (current.is_empty || current.is_fluid) && !pending.contains(&voxel) {
+                    chunks.update_voxel(&voxel, falling_block.0);
+                    pending.insert(voxel);
+                    landed = true;
+                    break;
+                }
+
+                vy += 1;
+            }
+
+            if !landed {
+                warn!(
+                    "Falling block {} at {:?} found no room to land and was dropped.",
+                    falling_block.0,
+                    Vec3(vx, py.floor() as i32, vz)
+                );
+            }
+
+            entities
+                .delete(entity)
+                .expect("Failed to delete falling block");
+        }
+    }
+}
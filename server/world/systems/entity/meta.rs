@@ -1,7 +1,7 @@
 use specs::{ReadStorage, System, WriteStorage};
 
 use crate::world::components::{
-    DirectionComp, EntityFlag, JsonComp, MetadataComp, PositionComp, VoxelComp,
+    DirectionComp, EntityFlag, FallingBlockComp, JsonComp, MetadataComp, PositionComp, VoxelComp,
 };
 
 pub struct EntitiesMetaSystem;
@@ -13,6 +13,7 @@ impl<'a> System<'a> for EntitiesMetaSystem {
         ReadStorage<'a, DirectionComp>,
         ReadStorage<'a, VoxelComp>,
         ReadStorage<'a, JsonComp>,
+        ReadStorage<'a, FallingBlockComp>,
         WriteStorage<'a, MetadataComp>,
     );
 
@@ -20,7 +21,7 @@ impl<'a> System<'a> for EntitiesMetaSystem {
         use rayon::prelude::*;
         use specs::ParJoin;
 
-        let (flag, positions, directions, voxels, jsons, mut metadatas) = data;
+        let (flag, positions, directions, voxels, jsons, falling_blocks, mut metadatas) = data;
 
         (&positions, &mut metadatas, &flag)
             .par_join()
@@ -40,5 +41,11 @@ impl<'a> System<'a> for EntitiesMetaSystem {
                 metadata.set("voxel", voxel);
                 metadata.set("json", json);
             });
+
+        (&falling_blocks, &mut metadatas, &flag)
+            .par_join()
+            .for_each(|(falling_block, metadata, _)| {
+                metadata.set("block", falling_block);
+            });
     }
 }
@@ -1,5 +1,7 @@
+mod falling;
 mod meta;
 mod sending;
 
+pub use falling::*;
 pub use meta::*;
 pub use sending::*;
@@ -85,7 +85,9 @@ impl<'a> System<'a> for EntitiesSendingSystem {
                     return;
                 }
 
-                entities_saver.remove(id);
+                if !bookkeeping.unloaded.remove(id) {
+                    entities_saver.remove(id);
+                }
 
                 if let Some((collider_handle, body_handle)) = old_entity_handlers.get(ent) {
                     physics.unregister(body_handle, collider_handle);
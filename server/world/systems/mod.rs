@@ -1,6 +1,7 @@
 mod broadcast;
 mod chunk;
 mod cleanup;
+mod edits;
 mod entity;
 mod events;
 mod peers;
@@ -13,6 +14,7 @@ mod path;
 pub use broadcast::*;
 pub use chunk::*;
 pub use cleanup::*;
+pub use edits::*;
 pub use entity::*;
 pub use events::*;
 pub use peers::*;
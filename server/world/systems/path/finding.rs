@@ -9,6 +9,12 @@ use crate::{
 use log::warn;
 use specs::{ReadExpect, ReadStorage, System, WriteStorage};
 
+/// Blocks with this tag are walked through when finding paths even if they are solid, such as doors.
+pub const PATH_PASSABLE_TAG: &str = "path-passable";
+
+/// Blocks with this tag are neither walked through nor stood on when finding paths, such as fire.
+pub const PATH_AVOIDED_TAG: &str = "path-avoided";
+
 pub struct PathFindingSystem;
 
 impl<'a> System<'a> for PathFindingSystem {
@@ -29,19 +35,25 @@ impl<'a> System<'a> for PathFindingSystem {
 
         let voxel_cache = Arc::new(Mutex::new(HashMap::new()));
 
+        let passable = registry.tag(PATH_PASSABLE_TAG);
+        let avoided = registry.tag(PATH_AVOIDED_TAG);
+
         let get_is_voxel_passable = |vx: i32, vy: i32, vz: i32| {
             let key = (vx, vy, vz);
             let mut cache = voxel_cache.lock().unwrap();
             *cache.entry(key).or_insert_with(|| {
                 let voxel = chunks.get_voxel(vx, vy, vz);
                 let block = registry.get_block_by_id(voxel);
-                block.is_passable || block.is_fluid
+                (block.is_passable || block.is_fluid || passable.is_some_and(|t| t.contains(voxel)))
+                    && !avoided.is_some_and(|t| t.contains(voxel))
             })
         };
 
         // Returns whether or not a block can be stepped on
         let walkable = |vx: i32, vy: i32, vz: i32, h: f32| {
-            if get_is_voxel_passable(vx, vy, vz) {
+            if get_is_voxel_passable(vx, vy, vz)
+                || avoided.is_some_and(|t| t.contains(chunks.get_voxel(vx, vy, vz)))
+            {
                 return false;
             }
 
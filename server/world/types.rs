@@ -22,4 +22,20 @@ impl LightColor {
 }
 
 /// Denoting a change in block in the world.
-pub type VoxelUpdate = (Vec3<i32>, u32);
+pub type VoxelUpdate = (Vec3<i32>, u64);
+
+/// A change to a voxel next to a block, given to `Block::on_neighbor_changed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeighborChange {
+    /// The voxel of the block being told about the change.
+    pub voxel: Vec3<i32>,
+
+    /// Offset from the block to the voxel that changed.
+    pub offset: Vec3<i32>,
+
+    /// Raw value of the changed voxel before the change.
+    pub old: u64,
+
+    /// Raw value of the changed voxel after the change.
+    pub new: u64,
+}
@@ -1,43 +1,108 @@
-use crate::world::voxels::{BlockRotation, ROTATION_MASK, STAGE_MASK, Y_ROTATION_MASK};
+use crate::world::voxels::{
+    BlockRotation, VoxelEncoding, ID_MASK, MAX_BLOCK_STATES, ROTATION_MASK, STAGE_MASK, STATE_MASK,
+    Y_ROTATION_MASK,
+};
+
+/// Highest block ID a voxel can hold, with `VoxelEncoding::Wide`.
+pub const MAX_VOXEL_ID: u32 = u32::MAX;
+
+/// Highest stage a voxel can hold, with `VoxelEncoding::Wide`.
+pub const MAX_VOXEL_STAGE: u32 = 0xFFFF;
 
 /// A set of utility functions for block operations.
 pub struct BlockUtils;
 
 impl BlockUtils {
     /// Extract the bits in voxel that stores the voxel id.
-    pub fn extract_id(voxel: u32) -> u32 {
-        voxel & 0xFFFF
+    pub fn extract_id(voxel: u64) -> u32 {
+        ((voxel & 0xFFFF) | ((voxel >> 16) & 0xFFFF0000)) as u32
+    }
+
+    /// Insert a voxel id into voxel value. Only the lower 16 bits of the id are kept, as in
+    /// `VoxelEncoding::Packed` worlds, see `insert_wide_id`.
+    pub fn insert_id(voxel: u64, id: u32) -> u64 {
+        (voxel & ID_MASK) | (id & 0xFFFF) as u64
     }
 
-    /// Insert a voxel id into voxel value.
-    pub fn insert_id(voxel: u32, id: u32) -> u32 {
-        (voxel & 0xFFFF0000) | (id & 0xFFFF)
+    /// Insert a voxel id of up to 32 bits into voxel value, for `VoxelEncoding::Wide` worlds.
+    pub fn insert_wide_id(voxel: u64, id: u32) -> u64 {
+        let id = id as u64;
+        (voxel & ID_MASK) | (id & 0xFFFF) | ((id & 0xFFFF0000) << 16)
     }
 
     /// Extract the bits in voxel that stores the voxel rotation.
-    pub fn extract_rotation(voxel: u32) -> BlockRotation {
-        let rotation = (voxel >> 16) & 0xF;
-        let y_rot = (voxel >> 20) & 0xF;
+    pub fn extract_rotation(voxel: u64) -> BlockRotation {
+        let rotation = ((voxel >> 16) & 0xF) as u32;
+        let y_rot = ((voxel >> 20) & 0xF) as u32;
         BlockRotation::encode(rotation, y_rot)
     }
 
     /// Insert a voxel rotation into voxel value.
-    pub fn insert_rotation(voxel: u32, rotation: &BlockRotation) -> u32 {
+    pub fn insert_rotation(voxel: u64, rotation: &BlockRotation) -> u64 {
         let (rotation, y_rot) = BlockRotation::decode(rotation);
-        let value = (voxel & ROTATION_MASK) | ((rotation & 0xF) << 16);
-        (value & Y_ROTATION_MASK) | ((y_rot & 0xF) << 20)
+        let value = (voxel & ROTATION_MASK) | (((rotation & 0xF) as u64) << 16);
+        (value & Y_ROTATION_MASK) | (((y_rot & 0xF) as u64) << 20)
     }
 
     /// Extract the bits in voxel that stores the stage value.
-    pub fn extract_stage(voxel: u32) -> u32 {
-        (voxel >> 24) & 0xF
+    pub fn extract_stage(voxel: u64) -> u32 {
+        (((voxel >> 24) & 0xF) | ((voxel >> 44) & 0xFFF0)) as u32
     }
 
     /// Insert a voxel stage into voxel value. Panics if the stage passed in overflows 15.
-    pub fn insert_stage(voxel: u32, stage: u32) -> u32 {
+    pub fn insert_stage(voxel: u64, stage: u32) -> u64 {
         assert!(stage <= 15, "Maximum stage is 15");
 
-        (voxel & STAGE_MASK) | (stage << 24)
+        (voxel & STAGE_MASK) | ((stage as u64) << 24)
+    }
+
+    /// Insert a voxel stage of up to 16 bits into voxel value, for `VoxelEncoding::Wide` worlds.
+    /// Panics if the stage passed in overflows `MAX_VOXEL_STAGE`.
+    pub fn insert_wide_stage(voxel: u64, stage: u32) -> u64 {
+        assert!(
+            stage <= MAX_VOXEL_STAGE,
+            "Maximum stage is {}",
+            MAX_VOXEL_STAGE
+        );
+
+        let stage = stage as u64;
+        (voxel & STAGE_MASK) | ((stage & 0xF) << 24) | ((stage & 0xFFF0) << 44)
+    }
+
+    /// Extract the bits in voxel that stores the state ID.
+    pub fn extract_state(voxel: u64) -> u32 {
+        (((voxel >> 28) & 0xF) | ((voxel >> 56) & 0xF0)) as u32
+    }
+
+    /// Insert a state ID into voxel value. Panics if the state passed in overflows 15.
+    pub fn insert_state(voxel: u64, state: u32) -> u64 {
+        assert!(state <= 15, "Maximum state is 15");
+
+        (voxel & STATE_MASK) | ((state as u64) << 28)
+    }
+
+    /// Insert a state ID of up to 8 bits into voxel value, for `VoxelEncoding::Wide` worlds. Panics
+    /// if the state passed in overflows `MAX_BLOCK_STATES`.
+    pub fn insert_wide_state(voxel: u64, state: u32) -> u64 {
+        assert!(
+            (state as usize) < MAX_BLOCK_STATES,
+            "Maximum state is {}",
+            MAX_BLOCK_STATES - 1
+        );
+
+        let state = state as u64;
+        (voxel & STATE_MASK) | ((state & 0xF) << 28) | ((state & 0xF0) << 56)
+    }
+
+    /// Split a voxel value into its lower 32 bits and its upper 32 bits, as they are kept in chunks
+    /// and sent to clients.
+    pub fn split(voxel: u64) -> (u32, u32) {
+        (voxel as u32, (voxel >> 32) as u32)
+    }
+
+    /// Join the lower 32 bits and the upper 32 bits of a voxel back into its value.
+    pub fn join(lower: u32, upper: u32) -> u64 {
+        lower as u64 | ((upper as u64) << 32)
     }
 }
 
@@ -46,6 +111,8 @@ pub struct VoxelPacker {
     id: u32,
     rotation: BlockRotation,
     stage: u32,
+    state: u32,
+    encoding: VoxelEncoding,
 }
 
 impl VoxelPacker {
@@ -68,11 +135,23 @@ impl VoxelPacker {
         self
     }
 
-    pub fn pack(&self) -> u32 {
+    pub fn with_state(mut self, state: u32) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Pack the id, stage and state for a world with this encoding. Defaults to `VoxelEncoding::Packed`.
+    pub fn with_encoding(mut self, encoding: VoxelEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn pack(&self) -> u64 {
         let mut voxel = 0;
-        voxel = BlockUtils::insert_id(voxel, self.id);
+        voxel = self.encoding.insert_id(voxel, self.id);
         voxel = BlockUtils::insert_rotation(voxel, &self.rotation);
-        voxel = BlockUtils::insert_stage(voxel, self.stage);
+        voxel = self.encoding.insert_stage(voxel, self.stage);
+        voxel = self.encoding.insert_state(voxel, self.state);
         voxel
     }
 }
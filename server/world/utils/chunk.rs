@@ -31,6 +31,12 @@ impl ChunkUtils {
         Vec2(vec[0].parse().unwrap(), vec[1].parse().unwrap())
     }
 
+    /// Parse a chunk coordinate from a chunk representation, or `None` if it is not one.
+    pub fn try_parse_chunk_name(name: &str) -> Option<Vec2<i32>> {
+        let (cx, cz) = name.split_once(get_concat())?;
+        Some(Vec2(cx.parse().ok()?, cz.parse().ok()?))
+    }
+
     /// Generate a voxel representation from a voxel coordinate.
     pub fn get_voxel_name(vx: i32, vy: i32, vz: i32) -> String {
         let concat = get_concat();
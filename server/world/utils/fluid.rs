@@ -0,0 +1,151 @@
+use crate::{BlockUtils, Registry, Vec3, VoxelAccess, VoxelUpdate};
+
+/// Stage bit of fluid that is falling from the fluid above it.
+pub const FLUID_FALLING: u32 = 8;
+
+/// Highest level flowing fluid can have, and so the furthest a fluid can flow from its source.
+pub const MAX_FLUID_LEVEL: u32 = 7;
+
+const HORIZONTAL_NEIGHBORS: [[i32; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
+
+/// A set of utility functions for flowing fluids. The stage of a fluid voxel holds its level in the
+/// lower 3 bits, which is 0 for sources and the distance to the source for flowing fluid, and
+/// whether it is falling in the `FLUID_FALLING` bit.
+pub struct FluidUtils;
+
+impl FluidUtils {
+    /// Extract the level of a fluid voxel.
+    pub fn extract_level(voxel: u64) -> u32 {
+        BlockUtils::extract_stage(voxel) & MAX_FLUID_LEVEL
+    }
+
+    /// Whether a fluid voxel is falling.
+    pub fn is_falling(voxel: u64) -> bool {
+        BlockUtils::extract_stage(voxel) & FLUID_FALLING != 0
+    }
+
+    /// Whether a fluid voxel is a source.
+    pub fn is_source(voxel: u64) -> bool {
+        BlockUtils::extract_stage(voxel) == 0
+    }
+
+    /// Create the voxel value of a fluid at a level.
+    pub fn fluid(id: u32, level: u32, falling: bool) -> u64 {
+        let falling = if falling { FLUID_FALLING } else { 0 };
+        BlockUtils::insert_stage(
+            BlockUtils::insert_wide_id(0, id),
+            level.min(MAX_FLUID_LEVEL) | falling,
+        )
+    }
+
+    /// Number of ticks between each step of a block's flow, if it is a fluid that flows.
+    pub fn flow_rate(id: u32, registry: &Registry) -> Option<u64> {
+        registry
+            .blocks_by_id
+            .get(&id)
+            .filter(|block| block.is_fluid && block.fluid_flow_rate > 0)
+            .map(|block| block.fluid_flow_rate)
+    }
+
+    /// One step of fluid flow at a voxel. Flowing fluid first settles on the level its neighbors
+    /// feed it with, drying up once nothing does. Settled fluid falls into the empty voxel below it,
+    /// and only otherwise spreads into the empty voxels around it one level higher, up to the block's
+    /// `fluid_range`. Fluid fed by two sources over solid ground becomes a source if the block
+    /// has `fluid_regenerates`.
+    pub fn flow(
+        voxel: &Vec3<i32>,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+    ) -> Vec<VoxelUpdate> {
+        let Vec3(vx, vy, vz) = *voxel;
+
+        let raw = space.get_raw_voxel(vx, vy, vz);
+        let id = BlockUtils::extract_id(raw);
+
+        let block = match registry.blocks_by_id.get(&id) {
+            Some(block) if block.is_fluid && block.fluid_flow_rate > 0 => block,
+            _ => return vec![],
+        };
+
+        let range = block.fluid_range.clamp(1, MAX_FLUID_LEVEL);
+        let same = |raw: u64| BlockUtils::extract_id(raw) == id;
+        let replaceable = |raw: u64| {
+            registry
+                .blocks_by_id
+                .get(&BlockUtils::extract_id(raw))
+                .is_some_and(|block| block.is_empty && !block.is_fluid)
+        };
+
+        let below = space.get_raw_voxel(vx, vy - 1, vz);
+        let source = Self::is_source(raw);
+
+        if !source {
+            let desired = if same(space.get_raw_voxel(vx, vy + 1, vz)) {
+                Self::fluid(id, 0, true)
+            } else {
+                let mut lowest = None;
+                let mut sources = 0;
+
+                for [dx, dz] in HORIZONTAL_NEIGHBORS {
+                    let neighbor = space.get_raw_voxel(vx + dx, vy, vz + dz);
+
+                    if !same(neighbor) {
+                        continue;
+                    }
+
+                    if Self::is_source(neighbor) {
+                        sources += 1;
+                    }
+
+                    let level = if Self::is_falling(neighbor) {
+                        0
+                    } else {
+                        Self::extract_level(neighbor)
+                    };
+
+                    lowest = Some(lowest.map_or(level, |lowest: u32| lowest.min(level)));
+                }
+
+                let supported = !replaceable(below) && (!same(below) || Self::is_source(below));
+
+                if block.fluid_regenerates && sources >= 2 && supported {
+                    Self::fluid(id, 0, false)
+                } else {
+                    match lowest {
+                        Some(level) if level < range => Self::fluid(id, level + 1, false),
+                        _ => 0,
+                    }
+                }
+            };
+
+            if desired != raw {
+                return vec![(voxel.to_owned(), desired)];
+            }
+        }
+
+        if vy > 0 && replaceable(below) {
+            return vec![(Vec3(vx, vy - 1, vz), Self::fluid(id, 0, true))];
+        }
+
+        // Fluid only spreads over solid ground or a source of itself.
+        if same(below) && !Self::is_source(below) {
+            return vec![];
+        }
+
+        let next = if source || Self::is_falling(raw) {
+            1
+        } else {
+            Self::extract_level(raw) + 1
+        };
+
+        if next > range {
+            return vec![];
+        }
+
+        HORIZONTAL_NEIGHBORS
+            .iter()
+            .filter(|[dx, dz]| replaceable(space.get_raw_voxel(vx + dx, vy, vz + dz)))
+            .map(|[dx, dz]| (Vec3(vx + dx, vy, vz + dz), Self::fluid(id, next, false)))
+            .collect()
+    }
+}
@@ -1,11 +1,13 @@
 mod astar;
 mod block;
 mod chunk;
+mod fluid;
 mod kdtree;
 mod light;
 
 pub use astar::*;
 pub use block::*;
 pub use chunk::ChunkUtils;
+pub use fluid::*;
 pub use kdtree::KdTree;
 pub use light::LightUtils;
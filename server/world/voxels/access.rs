@@ -1,16 +1,22 @@
-use crate::{BlockUtils, LightColor, LightUtils, Ndarray};
+use crate::{BlockUtils, LightColor, LightUtils, Ndarray, PropertyValue, Registry};
 
-use super::block::BlockRotation;
+use super::{block::BlockRotation, chunk::VoxelEncoding};
 
 #[allow(unused)]
 pub trait VoxelAccess {
+    /// How many bits of voxel ids, stages and states `set_voxel`, `set_voxel_stage` and
+    /// `set_voxel_state` keep.
+    fn voxel_encoding(&self) -> VoxelEncoding {
+        VoxelEncoding::Packed
+    }
+
     /// Get the raw voxel data at the voxel coordinate. Zero is returned if chunk DNE.
-    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u64 {
         todo!("Voxel access `get_raw_voxel` is not implemented.");
     }
 
     /// Set the raw voxel data at the voxel coordinate. Returns false couldn't set.
-    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, voxel: u32) -> bool {
+    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, voxel: u64) -> bool {
         todo!("Voxel access `set_raw_voxel` is not implemented.");
     }
 
@@ -31,7 +37,7 @@ pub trait VoxelAccess {
 
     /// Set the voxel type at a voxel coordinate. Returns false couldn't set.
     fn set_voxel(&mut self, vx: i32, vy: i32, vz: i32, id: u32) -> bool {
-        let value = BlockUtils::insert_id(0, id);
+        let value = self.voxel_encoding().insert_id(0, id);
         self.set_raw_voxel(vx, vy, vz, value)
     }
 
@@ -57,10 +63,67 @@ pub trait VoxelAccess {
 
     /// Set the voxel stage at a voxel coordinate. Does nothing if chunk isn't found.
     fn set_voxel_stage(&mut self, vx: i32, vy: i32, vz: i32, stage: u32) -> bool {
-        let value = BlockUtils::insert_stage(self.get_raw_voxel(vx, vy, vz), stage);
+        let value = self
+            .voxel_encoding()
+            .insert_stage(self.get_raw_voxel(vx, vy, vz), stage);
+        self.set_raw_voxel(vx, vy, vz, value)
+    }
+
+    /// Get the state ID at a voxel coordinate, see `Registry::get_state`.
+    fn get_voxel_state(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        BlockUtils::extract_state(self.get_raw_voxel(vx, vy, vz))
+    }
+
+    /// Set the state ID at a voxel coordinate. Does nothing if chunk isn't found.
+    fn set_voxel_state(&mut self, vx: i32, vy: i32, vz: i32, state: u32) -> bool {
+        let value = self
+            .voxel_encoding()
+            .insert_state(self.get_raw_voxel(vx, vy, vz), state);
         self.set_raw_voxel(vx, vy, vz, value)
     }
 
+    /// Get a named property of the voxel at a voxel coordinate. Returns `None` if its block does not
+    /// have the property.
+    fn get_state(
+        &self,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        property: &str,
+        registry: &Registry,
+    ) -> Option<PropertyValue> {
+        let id = self.get_voxel(vx, vy, vz);
+        let state = registry.get_state(id, self.get_voxel_state(vx, vy, vz))?;
+
+        state.get(property).cloned()
+    }
+
+    /// Set a named property of the voxel at a voxel coordinate, keeping its other properties. Returns
+    /// false if its block does not have the property or the value.
+    fn set_state(
+        &mut self,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        property: &str,
+        value: PropertyValue,
+        registry: &Registry,
+    ) -> bool {
+        let id = self.get_voxel(vx, vy, vz);
+
+        let mut state = match registry.get_state(id, self.get_voxel_state(vx, vy, vz)) {
+            Some(state) if state.contains_key(property) => state.to_owned(),
+            _ => return false,
+        };
+
+        state.insert(property.to_owned(), value);
+
+        match registry.get_state_id(id, &state) {
+            Some(state) => self.set_voxel_state(vx, vy, vz, state),
+            None => false,
+        }
+    }
+
     /// Get the sunlight level at a voxel position. Returns 0 if chunk does not exist.
     fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
         LightUtils::extract_sunlight(self.get_raw_light(vx, vy, vz))
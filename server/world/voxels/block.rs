@@ -9,16 +9,22 @@ use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    BlockUtils, LightColor, LightUtils, Registry, Vec2, Vec3, VoxelAccess, VoxelUpdate, AABB, UV,
+    BlockProperty, BlockState, BlockUtils, LightColor, LightUtils, NeighborChange, Registry, Vec2,
+    Vec3, VoxelAccess, VoxelUpdate, AABB, MAX_BLOCK_STATES, MAX_FLUID_LEVEL, MAX_SIGNAL_LEVEL, UV,
 };
 
-/// Base class to extract voxel data from a single u32
+/// Base class to extract voxel data from a single u64
 ///
 /// Bit lineup as such (from right to left):
 /// - `1 - 16 bits`: ID (0x0000FFFF)
 /// - `17 - 20 bit`: rotation (0x000F0000)
 /// - `21 - 24 bit`: y rotation (0x00F00000)
-/// - `25 - 32 bit`: stage (0x0F000000)
+/// - `25 - 28 bit`: stage (0x0F000000)
+/// - `29 - 32 bit`: state (0xF0000000)
+/// - `33 - 48 bit`: upper ID bits (0x0000FFFF_00000000)
+/// - `49 - 60 bit`: upper stage bits (0x0FFF0000_00000000)
+///
+/// Only `VoxelEncoding::Wide` worlds keep the upper 32 bits.
 
 pub const PY_ROTATION: u32 = 0;
 pub const NY_ROTATION: u32 = 1;
@@ -29,9 +35,14 @@ pub const NZ_ROTATION: u32 = 5;
 
 pub const Y_ROT_SEGMENTS: u32 = 16;
 
-pub const ROTATION_MASK: u32 = 0xFFF0FFFF;
-pub const Y_ROTATION_MASK: u32 = 0xFF0FFFFF;
-pub const STAGE_MASK: u32 = 0xF0FFFFFF;
+/// Ticks between each step of a fluid's flow, unless configured otherwise.
+const DEFAULT_FLUID_FLOW_RATE: u64 = 5;
+
+pub const ID_MASK: u64 = 0xFFFF0000_FFFF0000;
+pub const ROTATION_MASK: u64 = 0xFFFFFFFF_FFF0FFFF;
+pub const Y_ROTATION_MASK: u64 = 0xFFFFFFFF_FF0FFFFF;
+pub const STAGE_MASK: u64 = 0xF000FFFF_F0FFFFFF;
+pub const STATE_MASK: u64 = 0x0FFFFFFF_0FFFFFFF;
 
 /// Block rotation enumeration. There are 6 possible rotations: `(px, nx, py, ny, pz, nz)`. Default rotation is PY.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -325,6 +336,10 @@ pub struct BlockFace {
     pub dir: [i32; 3],
     pub corners: [CornerData; 4],
     pub range: UV,
+
+    /// Index of the `TextureAtlas` page that `range` is on. Always 0 without an atlas.
+    #[serde(default)]
+    pub page: usize,
 }
 
 impl BlockFace {
@@ -342,6 +357,7 @@ impl BlockFace {
             dir,
             corners,
             range: UV::default(),
+            page: 0,
         }
     }
 
@@ -570,6 +586,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -607,6 +624,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -644,6 +662,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -681,6 +700,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -721,6 +741,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -754,6 +775,7 @@ impl DiagonalFacesBuilder {
                     independent: false,
                     isolated: false,
                     range: UV::default(),
+                    page: 0,
                     corners: [
                         CornerData {
                             pos: [
@@ -1034,6 +1056,7 @@ impl SixFacesBuilder {
                 independent: is_px_independent,
                 isolated: is_px_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [
@@ -1082,6 +1105,7 @@ impl SixFacesBuilder {
                 independent: is_py_independent,
                 isolated: is_py_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [offset_x, 1.0 * scale_y + offset_y, 1.0 * scale_z + offset_z],
@@ -1130,6 +1154,7 @@ impl SixFacesBuilder {
                 independent: is_pz_independent,
                 isolated: is_pz_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [offset_x, offset_y, 1.0 * scale_z + offset_z],
@@ -1178,6 +1203,7 @@ impl SixFacesBuilder {
                 independent: is_nx_independent,
                 isolated: is_nx_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [offset_x, 1.0 * scale_y + offset_y, offset_z],
@@ -1222,6 +1248,7 @@ impl SixFacesBuilder {
                 independent: is_ny_independent,
                 isolated: is_ny_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [1.0 * scale_x + offset_x, offset_y, 1.0 * scale_z + offset_z],
@@ -1266,6 +1293,7 @@ impl SixFacesBuilder {
                 independent: is_nz_independent,
                 isolated: is_nz_isolated,
                 range: UV::default(),
+                page: 0,
                 corners: [
                     CornerData {
                         pos: [1.0 * scale_x + offset_x, offset_y, offset_z],
@@ -1357,7 +1385,7 @@ impl std::ops::AddAssign<&Self> for BlockFaces {
 #[derive(Default, Debug, Clone)]
 pub struct Neighbors {
     pub center: Vec3<i32>,
-    map: HashMap<Vec3<i32>, [u32; 2]>,
+    map: HashMap<Vec3<i32>, (u64, u32)>,
 }
 
 impl Neighbors {
@@ -1370,7 +1398,7 @@ impl Neighbors {
                 for z in -1..=1 {
                     let voxel = space.get_raw_voxel(vx + x, vy + y, vz + z);
                     let light = space.get_raw_light(vx + x, vy + y, vz + z);
-                    map.insert(Vec3(x, y, z), [voxel, light]);
+                    map.insert(Vec3(x, y, z), (voxel, light));
                 }
             }
         }
@@ -1379,32 +1407,32 @@ impl Neighbors {
     }
 
     pub fn get_voxel(&self, offset: &Vec3<i32>) -> u32 {
-        let value = *self.map.get(offset).unwrap_or(&[0, 0]);
-        BlockUtils::extract_id(value[0])
+        let value = *self.map.get(offset).unwrap_or(&(0, 0));
+        BlockUtils::extract_id(value.0)
     }
 
     pub fn get_rotation(&self, offset: &Vec3<i32>) -> BlockRotation {
-        let value = *self.map.get(offset).unwrap_or(&[0, 0]);
-        BlockUtils::extract_rotation(value[0])
+        let value = *self.map.get(offset).unwrap_or(&(0, 0));
+        BlockUtils::extract_rotation(value.0)
     }
 
     pub fn get_stage(&self, offset: &Vec3<i32>) -> u32 {
-        let value = *self.map.get(offset).unwrap_or(&[0, 0]);
-        BlockUtils::extract_stage(value[0])
+        let value = *self.map.get(offset).unwrap_or(&(0, 0));
+        BlockUtils::extract_stage(value.0)
     }
 
     pub fn get_sunlight(&self, offset: &Vec3<i32>) -> u32 {
-        let value = *self.map.get(offset).unwrap_or(&[0, 0]);
-        LightUtils::extract_sunlight(value[1])
+        let value = *self.map.get(offset).unwrap_or(&(0, 0));
+        LightUtils::extract_sunlight(value.1)
     }
 
     pub fn get_torch_light(&self, offset: &Vec3<i32>, color: &LightColor) -> u32 {
-        let value = *self.map.get(offset).unwrap_or(&[0, 0]);
+        let value = *self.map.get(offset).unwrap_or(&(0, 0));
 
         match *color {
-            LightColor::Red => LightUtils::extract_red_light(value[1]),
-            LightColor::Green => LightUtils::extract_green_light(value[1]),
-            LightColor::Blue => LightUtils::extract_blue_light(value[1]),
+            LightColor::Red => LightUtils::extract_red_light(value.1),
+            LightColor::Green => LightUtils::extract_green_light(value.1),
+            LightColor::Blue => LightUtils::extract_blue_light(value.1),
             LightColor::Sunlight => panic!("Getting torch light of Sunlight!"),
         }
     }
@@ -1417,6 +1445,10 @@ pub struct BlockSimpleRule {
     pub id: Option<u32>,
     pub rotation: Option<BlockRotation>,
     pub stage: Option<u32>,
+
+    /// State ID of the voxel, see `Registry::get_state`.
+    #[serde(default)]
+    pub state: Option<u32>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -1428,6 +1460,86 @@ pub enum BlockRule {
         logic: BlockRuleLogic,
         rules: Vec<BlockRule>,
     },
+    /// Matches when the voxel at `offset` has a tag. `Registry::generate` turns this into the IDs
+    /// with the tag, as that is all the clients know about.
+    Tag {
+        offset: Vec3<i32>,
+        tag: String,
+    },
+    /// Matches when the voxel at `offset` has these property values, and is of block `id` if given.
+    /// `Registry::generate` turns this into the state IDs that match, as that is all the clients know about.
+    State {
+        offset: Vec3<i32>,
+        id: Option<u32>,
+        properties: BlockState,
+    },
+}
+
+impl BlockRule {
+    /// Replace the tag and state rules within this rule with rules matching IDs and state IDs.
+    pub(crate) fn resolve(&mut self, registry: &Registry) {
+        match self {
+            BlockRule::Tag { offset, tag } => {
+                let rules = registry
+                    .blocks_with_tag(tag)
+                    .into_iter()
+                    .map(|block| {
+                        BlockRule::Simple(BlockSimpleRule {
+                            offset: offset.to_owned(),
+                            id: Some(block.id),
+                            rotation: None,
+                            stage: None,
+                            state: None,
+                        })
+                    })
+                    .collect();
+
+                *self = BlockRule::Combination {
+                    logic: BlockRuleLogic::Or,
+                    rules,
+                };
+            }
+            BlockRule::State {
+                offset,
+                id,
+                properties,
+            } => {
+                let mut ids = match id {
+                    Some(id) => vec![*id],
+                    None => registry.get_state_table().keys().cloned().collect(),
+                };
+                ids.sort_unstable();
+
+                let rules = ids
+                    .into_iter()
+                    .flat_map(|id| {
+                        registry
+                            .get_matching_states(id, properties)
+                            .into_iter()
+                            .map(move |state| (id, state))
+                    })
+                    .map(|(id, state)| {
+                        BlockRule::Simple(BlockSimpleRule {
+                            offset: offset.to_owned(),
+                            id: Some(id),
+                            rotation: None,
+                            stage: None,
+                            state: Some(state),
+                        })
+                    })
+                    .collect();
+
+                *self = BlockRule::Combination {
+                    logic: BlockRuleLogic::Or,
+                    rules,
+                };
+            }
+            BlockRule::Combination { rules, .. } => {
+                rules.iter_mut().for_each(|rule| rule.resolve(registry));
+            }
+            BlockRule::None | BlockRule::Simple(_) => {}
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -1454,6 +1566,18 @@ pub struct BlockDynamicPattern {
     pub parts: Vec<BlockConditionalPart>,
 }
 
+/// Callback of `Block::random_tick`.
+pub type RandomTickFn =
+    Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + Send + Sync>;
+
+/// Callback of `Block::on_neighbor_changed`.
+pub type NeighborChangedFn =
+    Arc<dyn Fn(&NeighborChange, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + Send + Sync>;
+
+/// Callback of `Block::on_signal`.
+pub type SignalFn =
+    Arc<dyn Fn(Vec3<i32>, u32, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + Send + Sync>;
+
 /// Serializable struct representing block data.
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -1478,6 +1602,28 @@ pub struct Block {
     /// Is the block a fluid?
     pub is_fluid: bool,
 
+    /// Number of ticks between each step of this fluid's flow. Zero keeps the fluid still.
+    pub fluid_flow_rate: u64,
+
+    /// How many voxels this fluid flows away from its source, up to `MAX_FLUID_LEVEL`.
+    pub fluid_range: u32,
+
+    /// Whether flowing fluid fed by two sources over solid ground becomes a source itself.
+    pub fluid_regenerates: bool,
+
+    /// Does the block fall as an entity when nothing solid is below it, like sand or gravel?
+    pub gravity: bool,
+
+    /// Power this block gives off as a signal source, up to `MAX_SIGNAL_LEVEL`. Zero if it is not one.
+    pub signal_power: u32,
+
+    /// Does the block carry signals? The power it carries is kept as its stage.
+    pub signal_conductor: bool,
+
+    /// Which sides of the block signals go through, rotated with the block.
+    /// The order is: px, py, pz, nx, ny, nz.
+    pub signal_faces: [bool; 6],
+
     /// Does the block emit light?
     pub is_light: bool,
 
@@ -1517,6 +1663,12 @@ pub struct Block {
 
     pub is_entity: bool,
 
+    /// Tags of the block, such as "logs" or "replaceable", in lowercase. See `Registry::has_tag`.
+    pub tags: Vec<String>,
+
+    /// Named properties of the block, such as whether a door is open. See `Registry::get_state`.
+    pub properties: Vec<BlockProperty>,
+
     /// Whether or not this block has dynamic aabb and face generation. This is
     /// automatically generated by the engine, and if `true`, client-side code
     /// should also have a corresponding dynamic function.
@@ -1544,6 +1696,19 @@ pub struct Block {
         Option<Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> u64 + Send + Sync>>,
 
     pub is_active: bool,
+
+    /// Called when a voxel of this block is picked for a random tick, returning voxel updates to make.
+    #[serde(skip)]
+    pub random_tick: Option<RandomTickFn>,
+
+    /// Called when a voxel next to a voxel of this block changes, returning voxel updates to make.
+    #[serde(skip)]
+    pub on_neighbor_changed: Option<NeighborChangedFn>,
+
+    /// Called with the power a voxel of this block receives whenever the signals around it change,
+    /// returning voxel updates to make.
+    #[serde(skip)]
+    pub on_signal: Option<SignalFn>,
 }
 
 impl Block {
@@ -1555,6 +1720,22 @@ impl Block {
         self.red_light_level > 0 || self.green_light_level > 0 || self.blue_light_level > 0
     }
 
+    /// Whether light goes through or comes out of this block any differently than another block.
+    pub fn lights_differently(&self, other: &Block) -> bool {
+        self.is_transparent != other.is_transparent
+            || self.is_opaque != other.is_opaque
+            || self.is_light != other.is_light
+            || self.light_reduce != other.light_reduce
+            || self.red_light_level != other.red_light_level
+            || self.green_light_level != other.green_light_level
+            || self.blue_light_level != other.blue_light_level
+    }
+
+    /// Whether this block is a signal source, conductor or consumer.
+    pub fn is_signal_block(&self) -> bool {
+        self.signal_power > 0 || self.signal_conductor || self.on_signal.is_some()
+    }
+
     pub fn get_aabbs(
         &self,
         pos: &Vec3<i32>,
@@ -1564,7 +1745,8 @@ impl Block {
         if self.is_dynamic {
             if let Some(dynamic_patterns) = &self.dynamic_patterns {
                 for pattern in dynamic_patterns {
-                    let (_, aabbs, __) = Block::match_dynamic_pattern(pattern, pos, space);
+                    let (_, aabbs, __) =
+                        Block::match_dynamic_pattern(pattern, pos, space, registry);
                     if aabbs.len() > 0 {
                         return aabbs.to_owned();
                     }
@@ -1587,7 +1769,8 @@ impl Block {
         if self.is_dynamic {
             if let Some(dynamic_patterns) = &self.dynamic_patterns {
                 for pattern in dynamic_patterns {
-                    let (faces, _, __) = Block::match_dynamic_pattern(pattern, pos, space);
+                    let (faces, _, __) =
+                        Block::match_dynamic_pattern(pattern, pos, space, registry);
                     if faces.len() > 0 {
                         return faces.to_owned();
                     }
@@ -1619,13 +1802,14 @@ impl Block {
         pattern: &BlockDynamicPattern,
         pos: &Vec3<i32>,
         space: &dyn VoxelAccess,
+        registry: &Registry,
     ) -> (Vec<BlockFace>, Vec<AABB>, [bool; 6]) {
         let mut combined_faces = Vec::new();
         let mut combined_aabbs = Vec::new();
         let mut combined_transparency = [false; 6];
 
         for part in &pattern.parts {
-            if Self::evaluate_rule(&part.rule, pos, space) {
+            if Self::evaluate_rule(&part.rule, pos, space, registry) {
                 combined_faces.extend(part.faces.clone());
                 combined_aabbs.extend(part.aabbs.clone());
                 for (i, &is_transparent) in part.is_transparent.iter().enumerate() {
@@ -1637,7 +1821,12 @@ impl Block {
         (combined_faces, combined_aabbs, combined_transparency)
     }
 
-    fn evaluate_rule(rule: &BlockRule, pos: &Vec3<i32>, space: &dyn VoxelAccess) -> bool {
+    fn evaluate_rule(
+        rule: &BlockRule,
+        pos: &Vec3<i32>,
+        space: &dyn VoxelAccess,
+        registry: &Registry,
+    ) -> bool {
         match rule {
             BlockRule::None => true,
             BlockRule::Simple(simple_rule) => {
@@ -1660,19 +1849,45 @@ impl Block {
                     stage == rule_stage
                 });
 
-                id_match && rotation_match && stage_match
+                let state_match = simple_rule.state.is_none_or(|rule_state| {
+                    let state = space.get_voxel_state(vx, vy, vz);
+                    state == rule_state
+                });
+
+                id_match && rotation_match && stage_match && state_match
+            }
+            BlockRule::Tag { offset, tag } => registry.has_tag(
+                space.get_voxel(pos.0 + offset.0, pos.1 + offset.1, pos.2 + offset.2),
+                tag,
+            ),
+            BlockRule::State {
+                offset,
+                id,
+                properties,
+            } => {
+                let (vx, vy, vz) = (pos.0 + offset.0, pos.1 + offset.1, pos.2 + offset.2);
+                let voxel_id = space.get_voxel(vx, vy, vz);
+
+                id.is_none_or(|id| id == voxel_id)
+                    && registry
+                        .get_state(voxel_id, space.get_voxel_state(vx, vy, vz))
+                        .is_some_and(|state| {
+                            properties
+                                .iter()
+                                .all(|(name, value)| state.get(name) == Some(value))
+                        })
             }
             BlockRule::Combination { logic, rules } => {
                 match logic {
                     BlockRuleLogic::And => rules
                         .iter()
-                        .all(|rule| Self::evaluate_rule(rule, pos, space)),
+                        .all(|rule| Self::evaluate_rule(rule, pos, space, registry)),
                     BlockRuleLogic::Or => rules
                         .iter()
-                        .any(|rule| Self::evaluate_rule(rule, pos, space)),
+                        .any(|rule| Self::evaluate_rule(rule, pos, space, registry)),
                     BlockRuleLogic::Not => !rules
                         .iter()
-                        .any(|rule| Self::evaluate_rule(rule, pos, space)),
+                        .any(|rule| Self::evaluate_rule(rule, pos, space, registry)),
                     // Extend with other logic types as needed
                 }
             }
@@ -1683,12 +1898,13 @@ impl Block {
         pattern: &BlockDynamicPattern,
         pos: &Vec3<i32>,
         space: &dyn VoxelAccess,
+        registry: &Registry,
     ) -> (Vec<BlockFace>, Vec<AABB>, [bool; 6]) {
-        Self::evaluate_dynamic_pattern(&pattern, pos, space)
+        Self::evaluate_dynamic_pattern(&pattern, pos, space, registry)
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum YRotatableSegments {
     All,
     Eight,
@@ -1710,6 +1926,13 @@ pub struct BlockBuilder {
     y_rotatable_segments: YRotatableSegments,
     is_empty: bool,
     is_fluid: bool,
+    fluid_flow_rate: u64,
+    fluid_range: u32,
+    fluid_regenerates: bool,
+    gravity: bool,
+    signal_power: u32,
+    signal_conductor: bool,
+    signal_faces: [bool; 6],
     is_passable: bool,
     red_light_level: u32,
     green_light_level: u32,
@@ -1725,6 +1948,8 @@ pub struct BlockBuilder {
     is_ny_transparent: bool,
     is_nz_transparent: bool,
     is_entity: bool,
+    tags: Vec<String>,
+    properties: Vec<BlockProperty>,
     light_reduce: bool,
     dynamic_patterns: Option<Vec<BlockDynamicPattern>>,
     dynamic_fn: Option<
@@ -1738,6 +1963,9 @@ pub struct BlockBuilder {
         Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + Send + Sync>,
     >,
     active_ticker: Option<Arc<dyn Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> u64 + Send + Sync>>,
+    random_tick: Option<RandomTickFn>,
+    on_neighbor_changed: Option<NeighborChangedFn>,
+    on_signal: Option<SignalFn>,
 }
 
 impl BlockBuilder {
@@ -1747,6 +1975,9 @@ impl BlockBuilder {
             name: name.to_owned(),
             faces: BlockFaces::six_faces().build().to_vec(),
             aabbs: vec![AABB::new().build()],
+            fluid_flow_rate: DEFAULT_FLUID_FLOW_RATE,
+            fluid_range: MAX_FLUID_LEVEL,
+            signal_faces: [true; 6],
             ..Default::default()
         }
     }
@@ -1790,6 +2021,54 @@ impl BlockBuilder {
         self
     }
 
+    /// Configure the number of ticks between each step of this fluid's flow. Zero keeps the fluid
+    /// still. Default is 5 ticks.
+    pub fn fluid_flow_rate(mut self, fluid_flow_rate: u64) -> Self {
+        self.fluid_flow_rate = fluid_flow_rate;
+        self
+    }
+
+    /// Configure how many voxels this fluid flows away from its source. Default is `MAX_FLUID_LEVEL`.
+    pub fn fluid_range(mut self, fluid_range: u32) -> Self {
+        self.fluid_range = fluid_range.clamp(1, MAX_FLUID_LEVEL);
+        self
+    }
+
+    /// Configure whether flowing fluid fed by two sources over solid ground becomes a source
+    /// itself. Default is false.
+    pub fn fluid_regenerates(mut self, fluid_regenerates: bool) -> Self {
+        self.fluid_regenerates = fluid_regenerates;
+        self
+    }
+
+    /// Configure whether the block falls as an entity when nothing solid is below it, landing
+    /// back as a voxel. Default is false.
+    pub fn gravity(mut self, gravity: bool) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Configure the power this block gives off as a signal source, such as a switch that is on.
+    /// Default is 0, which is not a source.
+    pub fn signal_power(mut self, signal_power: u32) -> Self {
+        self.signal_power = signal_power.min(MAX_SIGNAL_LEVEL);
+        self
+    }
+
+    /// Configure whether this block carries signals, like a wire. The power it carries is kept as
+    /// its stage. Default is false.
+    pub fn signal_conductor(mut self, signal_conductor: bool) -> Self {
+        self.signal_conductor = signal_conductor;
+        self
+    }
+
+    /// Configure which sides of this block signals go through, in the order px, py, pz, nx, ny, nz.
+    /// Default is all sides.
+    pub fn signal_faces(mut self, signal_faces: [bool; 6]) -> Self {
+        self.signal_faces = signal_faces;
+        self
+    }
+
     /// Configure whether or not this block can be passed through. Default is false.
     pub fn is_passable(mut self, is_plant: bool) -> Self {
         self.is_passable = is_plant;
@@ -1952,11 +2231,94 @@ impl BlockBuilder {
         self
     }
 
+    /// Configure the function called when a voxel of this block gets a random tick, such as for
+    /// crops growing or grass spreading. The returned updates go through the voxel update queue.
+    pub fn random_tick<
+        F: Fn(Vec3<i32>, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + 'static + Send + Sync,
+    >(
+        mut self,
+        random_tick: F,
+    ) -> Self {
+        self.random_tick = Some(Arc::new(random_tick));
+        self
+    }
+
+    /// Configure the function called when a voxel next to a voxel of this block changes, such as
+    /// for torches breaking once the block holding them is gone. The returned updates go through
+    /// the voxel update queue.
+    pub fn on_neighbor_changed<
+        F: Fn(&NeighborChange, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate>
+            + 'static
+            + Send
+            + Sync,
+    >(
+        mut self,
+        on_neighbor_changed: F,
+    ) -> Self {
+        self.on_neighbor_changed = Some(Arc::new(on_neighbor_changed));
+        self
+    }
+
+    /// Configure the function called with the power a voxel of this block receives whenever the
+    /// signals around it change, such as for doors opening while powered. The returned updates go
+    /// through the voxel update queue.
+    pub fn on_signal<
+        F: Fn(Vec3<i32>, u32, &dyn VoxelAccess, &Registry) -> Vec<VoxelUpdate> + 'static + Send + Sync,
+    >(
+        mut self,
+        on_signal: F,
+    ) -> Self {
+        self.on_signal = Some(Arc::new(on_signal));
+        self
+    }
+
     pub fn is_entity(mut self, is_entity: bool) -> Self {
         self.is_entity = is_entity;
         self
     }
 
+    /// Add a tag to this block, such as "logs" or "climbable". Tags are case-insensitive.
+    pub fn tag(mut self, tag: &str) -> Self {
+        let tag = tag.to_lowercase();
+
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+
+        self
+    }
+
+    /// Add a named property to this block. Panics if the block would have more than
+    /// `MAX_BLOCK_STATES` combinations of property values. Worlds with `VoxelEncoding::Packed` only
+    /// hold blocks of up to 16 of them.
+    pub fn property(mut self, property: BlockProperty) -> Self {
+        self.properties.push(property);
+
+        let count: usize = self
+            .properties
+            .iter()
+            .map(|property| property.values.len())
+            .product();
+
+        if count > MAX_BLOCK_STATES {
+            panic!(
+                "{} would have {} states, more than the {} a voxel can hold.",
+                self.name, count, MAX_BLOCK_STATES
+            );
+        }
+
+        self
+    }
+
+    /// Add several tags to this block, see `tag`.
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        for tag in tags {
+            self = self.tag(tag);
+        }
+
+        self
+    }
+
     /// Construct a block instance, ready to be added into the registry.
     pub fn build(self) -> Block {
         Block {
@@ -1967,6 +2329,13 @@ impl BlockBuilder {
             y_rotatable_segments: self.y_rotatable_segments,
             is_empty: self.is_empty,
             is_fluid: self.is_fluid,
+            fluid_flow_rate: self.fluid_flow_rate,
+            fluid_range: self.fluid_range,
+            fluid_regenerates: self.fluid_regenerates,
+            gravity: self.gravity,
+            signal_power: self.signal_power,
+            signal_conductor: self.signal_conductor,
+            signal_faces: self.signal_faces,
             is_light: self.red_light_level > 0
                 || self.green_light_level > 0
                 || self.blue_light_level > 0,
@@ -1999,7 +2368,12 @@ impl BlockBuilder {
             is_active: self.active_updater.is_some() && self.active_ticker.is_some(),
             active_ticker: self.active_ticker,
             active_updater: self.active_updater,
+            random_tick: self.random_tick,
+            on_neighbor_changed: self.on_neighbor_changed,
+            on_signal: self.on_signal,
             is_entity: self.is_entity,
+            tags: self.tags,
+            properties: self.properties,
         }
     }
 }
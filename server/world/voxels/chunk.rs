@@ -1,10 +1,14 @@
 use std::ops::Range;
 
 use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
 
-use crate::{ChunkProtocol, ChunkUtils, MeshProtocol, Ndarray, Registry, Vec2, Vec3, VoxelUpdate};
+use crate::{
+    BlockUtils, ChunkProtocol, ChunkUtils, MeshProtocol, Ndarray, Registry, Vec2, Vec3,
+    VoxelUpdate, MAX_BLOCK_STATES, MAX_VOXEL_ID, MAX_VOXEL_STAGE,
+};
 
-use super::access::VoxelAccess;
+use super::{access::VoxelAccess, palette::ChunkArray};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ChunkStatus {
@@ -21,11 +25,98 @@ impl Default for ChunkStatus {
     }
 }
 
+/// How many bits each voxel takes in chunks, in the protocol and in saved files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VoxelEncoding {
+    /// 32 bits per voxel, for up to 65536 block types, 16 stages and 16 block states. This is the
+    /// default.
+    #[default]
+    Packed,
+
+    /// 64 bits per voxel, kept as a second 32-bit channel next to the packed one, for 32-bit block
+    /// IDs, 16-bit stages and 256 block states.
+    Wide,
+}
+
+impl VoxelEncoding {
+    /// Highest block ID a voxel can hold with this encoding.
+    pub fn max_id(&self) -> u32 {
+        match self {
+            Self::Packed => 0xFFFF,
+            Self::Wide => MAX_VOXEL_ID,
+        }
+    }
+
+    /// Highest stage a voxel can hold with this encoding.
+    pub fn max_stage(&self) -> u32 {
+        match self {
+            Self::Packed => 0xF,
+            Self::Wide => MAX_VOXEL_STAGE,
+        }
+    }
+
+    /// Most states a block can have with this encoding.
+    pub fn max_states(&self) -> usize {
+        match self {
+            Self::Packed => 16,
+            Self::Wide => MAX_BLOCK_STATES,
+        }
+    }
+
+    /// Whether a voxel value fits this encoding, which for `Packed` means its upper 32 bits are empty.
+    pub fn holds(&self, voxel: u64) -> bool {
+        match self {
+            Self::Packed => voxel >> 32 == 0,
+            Self::Wide => true,
+        }
+    }
+
+    /// Drop the bits of a voxel value this encoding does not hold, such as the upper 32 bits of a
+    /// voxel from a `Wide` world in a `Packed` one.
+    pub fn mask(&self, voxel: u64) -> u64 {
+        match self {
+            Self::Packed => voxel & 0xFFFF_FFFF,
+            Self::Wide => voxel,
+        }
+    }
+
+    /// Insert a voxel id into voxel value, keeping as many bits of the id as this encoding holds.
+    pub fn insert_id(&self, voxel: u64, id: u32) -> u64 {
+        match self {
+            Self::Packed => BlockUtils::insert_id(voxel, id),
+            Self::Wide => BlockUtils::insert_wide_id(voxel, id),
+        }
+    }
+
+    /// Insert a voxel stage into voxel value. Panics if the stage overflows `max_stage`.
+    pub fn insert_stage(&self, voxel: u64, stage: u32) -> u64 {
+        match self {
+            Self::Packed => BlockUtils::insert_stage(voxel, stage),
+            Self::Wide => BlockUtils::insert_wide_stage(voxel, stage),
+        }
+    }
+
+    /// Insert a state ID into voxel value. Panics if the state overflows `max_states`.
+    pub fn insert_state(&self, voxel: u64, state: u32) -> u64 {
+        match self {
+            Self::Packed => BlockUtils::insert_state(voxel, state),
+            Self::Wide => BlockUtils::insert_wide_state(voxel, state),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct ChunkOptions {
     pub size: usize,
     pub max_height: usize,
     pub sub_chunks: usize,
+
+    /// Whether voxels and lights are stored palette compressed.
+    pub paletted: bool,
+
+    /// How many bits each voxel takes.
+    pub encoding: VoxelEncoding,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -36,12 +127,19 @@ pub struct Chunk {
 
     pub status: ChunkStatus,
 
-    pub voxels: Ndarray<u32>,
-    pub lights: Ndarray<u32>,
+    pub voxels: ChunkArray,
+
+    /// The upper 32 bits of each voxel, only kept with `VoxelEncoding::Wide`.
+    pub extras: Option<ChunkArray>,
+
+    pub lights: ChunkArray,
     pub height_map: Ndarray<u32>,
 
     pub meshes: Option<HashMap<u32, MeshProtocol>>,
 
+    /// Whether `lights` holds propagated light data, either from meshing or from a saved chunk.
+    pub has_lights: bool,
+
     pub min: Vec3<i32>,
     pub max: Vec3<i32>,
 
@@ -49,6 +147,9 @@ pub struct Chunk {
 
     pub extra_changes: Vec<VoxelUpdate>,
     pub updated_levels: HashSet<u32>,
+
+    /// Scheduled ticks read from storage, handed over to `Chunks::ticks` once the chunk is added.
+    pub(crate) loaded_ticks: Vec<(Vec3<i32>, u64)>,
 }
 
 impl Chunk {
@@ -57,10 +158,14 @@ impl Chunk {
             size,
             max_height,
             sub_chunks,
+            paletted,
+            encoding,
         } = *options;
 
-        let voxels = Ndarray::new(&[size, max_height, size], 0);
-        let lights = Ndarray::new(&[size, max_height, size], 0);
+        let voxels = ChunkArray::new(&[size, max_height, size], 0, paletted, sub_chunks);
+        let extras = (encoding == VoxelEncoding::Wide)
+            .then(|| ChunkArray::new(&[size, max_height, size], 0, paletted, sub_chunks));
+        let lights = ChunkArray::new(&[size, max_height, size], 0, paletted, sub_chunks);
         let height_map = Ndarray::new(&[size, size], 0);
 
         let min = Vec3(cx * size as i32, 0, cz * size as i32);
@@ -76,6 +181,7 @@ impl Chunk {
             coords: Vec2(cx, cz),
 
             voxels,
+            extras,
             lights,
             height_map,
 
@@ -110,6 +216,33 @@ impl Chunk {
         }
     }
 
+    /// Get the raw values of every voxel in this chunk, joining the upper 32 bits back in.
+    pub fn raw_voxels(&self) -> Vec<u64> {
+        let voxels = self.voxels.to_vec();
+
+        match &self.extras {
+            Some(extras) => voxels
+                .into_iter()
+                .zip(extras.to_vec())
+                .map(|(lower, upper)| BlockUtils::join(lower, upper))
+                .collect(),
+            None => voxels.into_iter().map(u64::from).collect(),
+        }
+    }
+
+    /// Replace the raw values of every voxel in this chunk. The upper 32 bits are dropped without
+    /// `VoxelEncoding::Wide`.
+    pub fn assign_raw_voxels(&mut self, voxels: &[u64]) {
+        let (lower, upper): (Vec<_>, Vec<_>) =
+            voxels.iter().map(|&voxel| BlockUtils::split(voxel)).unzip();
+
+        self.voxels.assign(lower);
+
+        if let Some(extras) = self.extras.as_mut() {
+            extras.assign(upper);
+        }
+    }
+
     /// Convert chunk to protocol model.
     pub fn to_model(&self, mesh: bool, data: bool, levels: Range<u32>) -> ChunkProtocol {
         let mut meshes = vec![];
@@ -130,12 +263,17 @@ impl Chunk {
             id: self.id.clone(),
             meshes,
             voxels: if data {
-                Some(self.voxels.to_owned())
+                Some(self.voxels.to_ndarray())
+            } else {
+                None
+            },
+            extras: if data {
+                self.extras.as_ref().map(|extras| extras.to_ndarray())
             } else {
                 None
             },
             lights: if data {
-                Some(self.lights.to_owned())
+                Some(self.lights.to_ndarray())
             } else {
                 None
             },
@@ -168,22 +306,31 @@ impl Chunk {
 }
 
 impl VoxelAccess for Chunk {
+    fn voxel_encoding(&self) -> VoxelEncoding {
+        self.options.encoding
+    }
+
     /// Get the raw value of voxel.
     ///
     /// Returns 0 if it's outside of the chunk.
-    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u64 {
         if !self.contains(vx, vy, vz) {
             return 0;
         }
 
         let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.voxels[&[lx, ly, lz]]
+        let upper = self
+            .extras
+            .as_ref()
+            .map_or(0, |extras| extras.get(lx, ly, lz));
+
+        BlockUtils::join(self.voxels.get(lx, ly, lz), upper)
     }
 
     /// Set the raw value of voxel.
     ///
-    /// Panics if the coordinates are outside of chunk.
-    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, val: u32) -> bool {
+    /// Panics if the value needs the upper 32 bits without `VoxelEncoding::Wide`.
+    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, val: u64) -> bool {
         if !self.contains(vx, vy, vz) {
             if vy >= 0 && vy < self.options.max_height as i32 {
                 self.extra_changes.push((Vec3(vx, vy, vz), val));
@@ -195,7 +342,18 @@ impl VoxelAccess for Chunk {
         self.add_updated_level(vy);
 
         let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.voxels[&[lx, ly, lz]] = val;
+        let (lower, upper) = BlockUtils::split(val);
+
+        match self.extras.as_mut() {
+            Some(extras) => extras.set(lx, ly, lz, upper),
+            None => assert!(
+                upper == 0,
+                "Voxel {:#x} needs the wide voxel encoding, see `WorldConfig::voxel_encoding`.",
+                val
+            ),
+        }
+
+        self.voxels.set(lx, ly, lz, lower);
 
         true
     }
@@ -209,7 +367,7 @@ impl VoxelAccess for Chunk {
         }
 
         let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.lights[&[lx, ly, lz]]
+        self.lights.get(lx, ly, lz)
     }
 
     /// Set the raw light of voxel.
@@ -223,7 +381,7 @@ impl VoxelAccess for Chunk {
         self.add_updated_level(vy);
 
         let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.lights[&[lx, ly, lz]] = level;
+        self.lights.set(lx, ly, lz, level);
 
         true
     }
@@ -254,12 +412,14 @@ impl VoxelAccess for Chunk {
         true
     }
 
+    /// Get the lights as a dense array. Returns `None` if the lights are palette compressed.
     fn get_lights(&self, _: i32, _: i32) -> Option<&Ndarray<u32>> {
-        Some(&self.lights)
+        self.lights.as_ndarray()
     }
 
+    /// Get the voxels as a dense array. Returns `None` if the voxels are palette compressed.
     fn get_voxels(&self, _: i32, _: i32) -> Option<&Ndarray<u32>> {
-        Some(&self.voxels)
+        self.voxels.as_ndarray()
     }
 
     /// Check if chunk contains this voxel coordinate.
@@ -1,36 +1,24 @@
-use base64::{engine::general_purpose::STANDARD, Engine};
-use byteorder::{ByteOrder, LittleEndian};
 use hashbrown::{HashMap, HashSet};
-use libflate::zlib::{Decoder, Encoder};
-use log::info;
-use serde::{Deserialize, Serialize};
+use log::{error, warn};
+use serde_json::json;
 use specs::Entity;
-use std::{
-    collections::VecDeque,
-    fs::{self, File},
-    io::{BufReader, Read, Write},
-    path::PathBuf,
-};
+use std::{collections::VecDeque, io, sync::Arc};
 
 use crate::{
-    ChunkOptions, ChunkStatus, ChunkUtils, LightUtils, MessageType, Registry, Vec2, Vec3,
-    VoxelUpdate, WorldConfig,
+    BlockProperty, BlockState, BlockUtils, ChunkOptions, ChunkRecord, ChunkStatus, ChunkUtils,
+    LightUtils, MessageType, MetadataComp, NeighborChange, Registry, Vec2, Vec3, VoxelUpdate,
+    WorldConfig, WorldStorage,
 };
 
 use super::{
     access::VoxelAccess,
-    chunk::Chunk,
+    chunk::{Chunk, VoxelEncoding},
     space::{SpaceBuilder, SpaceOptions},
+    ticks::ScheduledTicks,
 };
 
-/// Prototype for chunk's internal data used to send to client
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ChunkFileData {
-    id: String,
-    voxels: String,
-    height_map: String,
-}
+/// Metadata key of the list of chunks whose saved lights are out of date.
+const STALE_LIGHTS_KEY: &str = "stale-lights.json";
 
 /// A manager for all chunks in the Voxelize world.
 #[derive(Default)]
@@ -47,7 +35,8 @@ pub struct Chunks {
     /// A list of chunks that are done meshing and ready to be saved, if `config.save` is true.
     pub(crate) to_save: VecDeque<Vec2<i32>>,
 
-    pub(crate) active_voxels: Vec<(u64, Vec3<i32>)>,
+    /// Block ticks scheduled by active blocks.
+    pub ticks: ScheduledTicks,
 
     /// A listener for when a chunk is done generating or meshing.
     pub(crate) listeners: HashMap<Vec2<i32>, Vec<Vec2<i32>>>,
@@ -57,98 +46,354 @@ pub struct Chunks {
 
     pub block_entities: HashMap<Vec3<i32>, Entity>,
 
+    /// Block entities that were unloaded along with their chunk, revived once the chunk is loaded
+    /// again, coords -> (id, etype, metadata).
+    pub(crate) unloaded_block_entities: HashMap<Vec2<i32>, Vec<(String, String, MetadataComp)>>,
+
+    /// JSON data for block entities that pending voxel updates will create, voxel -> JSON.
+    pub(crate) block_entity_data: HashMap<Vec3<i32>, String>,
+
+    /// Neighbor changes waiting for `Block::on_neighbor_changed`, with the depth of their chain.
+    pub(crate) neighbor_changes: VecDeque<(NeighborChange, u32)>,
+
+    /// Depth of the neighbor change chain that pending voxel updates are part of, voxel -> depth.
+    pub(crate) neighbor_depths: HashMap<Vec3<i32>, u32>,
+
+    /// Who made the pending voxel updates that did not come from the server itself, voxel -> source.
+    pub(crate) update_sources: HashMap<Vec3<i32>, String>,
+
     /// A copy of the world's config.
-    config: WorldConfig,
+    pub(crate) config: WorldConfig,
+
+    /// The storage that chunks are saved to and loaded from, if `config.saving` is true.
+    storage: Option<Arc<dyn WorldStorage>>,
+
+    /// Saved chunks whose saved lights are out of date, because a neighbor changed while they were unloaded.
+    stale_lights: HashSet<Vec2<i32>>,
 
-    /// The folder to store the chunks.
-    folder: Option<PathBuf>,
+    /// Whether `stale_lights` changed since it was last written to storage.
+    stale_lights_changed: bool,
+
+    /// Loaded chunks nobody needs, coords -> tick since which they have not been needed.
+    pub(crate) idle_since: HashMap<Vec2<i32>, u64>,
 }
 
 impl Chunks {
     /// Create a new instance of a chunk manager.
-    pub fn new(config: &WorldConfig) -> Self {
-        let folder = if config.saving {
-            let mut folder = PathBuf::from(&config.save_dir);
-            folder.push("chunks");
+    pub fn new(config: &WorldConfig, storage: Option<Arc<dyn WorldStorage>>) -> Self {
+        let mut chunks = Self {
+            config: config.to_owned(),
+            ticks: ScheduledTicks::new(config.chunk_size),
+            ..Default::default()
+        };
 
-            fs::create_dir_all(&folder).expect("Unable to create chunks directory...");
+        chunks.set_storage(storage);
+        chunks
+    }
 
-            Some(folder)
-        } else {
-            None
-        };
+    /// The storage that chunks are saved to, if saving is on.
+    pub fn storage(&self) -> Option<&Arc<dyn WorldStorage>> {
+        self.storage.as_ref()
+    }
 
-        Self {
-            folder,
-            config: config.to_owned(),
-            ..Default::default()
+    /// Swap out the storage that chunks are saved to.
+    pub fn set_storage(&mut self, storage: Option<Arc<dyn WorldStorage>>) {
+        self.stale_lights = storage
+            .as_ref()
+            .and_then(|storage| match storage.load_metadata(STALE_LIGHTS_KEY) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Could not read stale lights from storage: {}", e);
+                    None
+                }
+            })
+            .and_then(|data| serde_json::from_slice::<Vec<[i32; 2]>>(&data).ok())
+            .map(|list| list.into_iter().map(|[x, z]| Vec2(x, z)).collect())
+            .unwrap_or_default();
+        self.stale_lights_changed = false;
+
+        self.storage = storage;
+    }
+
+    /// Mark the saved lights of chunks as out of date, so that they get relit the next time they load.
+    pub fn mark_lights_stale(&mut self, coords: &[Vec2<i32>]) {
+        let mut changed = false;
+
+        for coords in coords {
+            changed |= self.stale_lights.insert(coords.to_owned());
+        }
+
+        if changed {
+            self.stale_lights_changed = true;
+            self.save_stale_lights();
+        }
+    }
+
+    /// Persist the list of chunks with stale lights, if it changed since it was last persisted.
+    /// Called once after a pass of `chunks.save`, instead of for every saved chunk.
+    pub fn save_stale_lights(&mut self) {
+        if !std::mem::take(&mut self.stale_lights_changed) {
+            return;
+        }
+
+        if let Some(storage) = &self.storage {
+            let list: Vec<[i32; 2]> = self.stale_lights.iter().map(|c| [c.0, c.1]).collect();
+
+            if let Err(e) =
+                storage.save_metadata(STALE_LIGHTS_KEY, json!(list).to_string().as_bytes())
+            {
+                warn!("Could not write stale lights to storage: {}", e);
+                self.stale_lights_changed = true;
+            }
         }
     }
 
+    /// Check to see if a chunk has been saved before.
     pub fn test_load(&self, coords: &Vec2<i32>) -> bool {
-        let path = self.get_chunk_file_path(&ChunkUtils::get_chunk_name(coords.0, coords.1));
-        File::open(&path).is_ok()
+        if let Some(storage) = &self.storage {
+            return storage.has_chunk(coords);
+        }
+
+        false
     }
 
-    // Try to load the data of a chunk, returns whether successful or not.
+    // Try to load the data of a chunk, returns whether successful or not. Corrupt chunks are
+    // quarantined, so that they get generated again without losing the damaged data.
     pub fn try_load(&self, coords: &Vec2<i32>, registry: &Registry) -> Option<Chunk> {
         if !self.config.saving {
             return None;
         }
 
-        let path = self.get_chunk_file_path(&ChunkUtils::get_chunk_name(coords.0, coords.1));
-        let file = File::open(&path).ok()?;
-        let chunk_data = BufReader::new(file);
+        let storage = self.storage.as_ref()?;
 
-        let data: ChunkFileData = serde_json::from_reader(chunk_data).ok()?;
+        let record = storage.load_chunk(coords).and_then(|bytes| match bytes {
+            Some(bytes) => ChunkRecord::decode(&bytes).map(Some),
+            None => Ok(None),
+        });
 
-        let decode_base64 = |base: &str| -> Vec<u32> {
-            if base.is_empty() {
-                return vec![];
+        let record = match record {
+            Ok(record) => record?,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                error!(
+                    "Chunk {:?} is corrupted ({}), quarantining it and generating it again.",
+                    coords, e
+                );
+
+                if let Err(e) = storage.quarantine_chunk(coords) {
+                    error!("Could not quarantine chunk {:?}: {}", coords, e);
+                }
+
+                return None;
             }
+            Err(e) => {
+                error!(
+                    "Could not read chunk {:?} from storage, trying again later: {}",
+                    coords, e
+                );
 
-            let decoded = STANDARD.decode(base).expect("Failed to decode base64");
-            let mut decoder = Decoder::new(&decoded[..]).expect("Failed to create decoder");
-            let mut buf = Vec::new();
-            decoder
-                .read_to_end(&mut buf)
-                .expect("Failed to decode data");
-            let mut data = vec![0; buf.len() / 4];
-            LittleEndian::read_u32_into(&buf, &mut data);
-            data
+                return None;
+            }
         };
 
-        let (voxels, height_map) = rayon::join(
-            || decode_base64(&data.voxels),
-            || decode_base64(&data.height_map),
-        );
-
         let mut chunk = Chunk::new(
-            &data.id,
+            &record.id,
             coords.0,
             coords.1,
             &ChunkOptions {
                 max_height: self.config.max_height,
                 sub_chunks: self.config.sub_chunks,
                 size: self.config.chunk_size,
+                paletted: self.config.paletted_chunks,
+                encoding: self.config.voxel_encoding,
             },
         );
 
-        chunk.voxels.data = voxels;
+        let mut voxels = record
+            .voxels
+            .iter()
+            .enumerate()
+            .map(|(index, &lower)| {
+                BlockUtils::join(lower, record.extras.get(index).cloned().unwrap_or(0))
+            })
+            .collect::<Vec<_>>();
+        let remapped = self.remap_voxels(coords, &mut voxels, &record.palette, registry);
+        let remapped =
+            self.remap_states(&mut voxels, &record.palette, &record.states, registry) || remapped;
+
+        // Dropping the upper bits would change blocks, and the next save would make it permanent.
+        if chunk.extras.is_none() && voxels.iter().any(|&voxel| voxel >> 32 != 0) {
+            error!(
+                "Chunk {:?} was saved with the wide voxel encoding and does not fit this world, quarantining it and generating it again.",
+                coords
+            );
+
+            if let Err(e) = storage.quarantine_chunk(coords) {
+                error!("Could not quarantine chunk {:?}: {}", coords, e);
+            }
+
+            return None;
+        }
 
-        if height_map.len() > 0 {
-            chunk.height_map.data = height_map;
+        chunk.assign_raw_voxels(&voxels);
+
+        if !record.height_map.is_empty() && !remapped {
+            chunk.height_map.data = record.height_map;
         } else {
             chunk.calculate_max_height(registry);
         }
 
+        // Lights are only trusted if no neighbor changed since they were saved, and no block changed on load.
+        if record.lights.len() == chunk.lights.len()
+            && !remapped
+            && !self.stale_lights.contains(coords)
+        {
+            chunk.lights.assign(record.lights);
+            chunk.has_lights = true;
+        }
+
+        chunk.loaded_ticks = record.ticks;
         chunk.status = ChunkStatus::Meshing;
 
         Some(chunk)
     }
 
-    // Save a certain chunk.
-    pub fn save(&self, coords: &Vec2<i32>) -> bool {
+    /// Remap the block IDs of loaded voxels from the palette they were saved with to the current
+    /// registry. Blocks that are no longer registered become `config.fallback_block`. Returns
+    /// whether any voxel changed.
+    fn remap_voxels(
+        &self,
+        coords: &Vec2<i32>,
+        voxels: &mut [u64],
+        palette: &[(u32, String)],
+        registry: &Registry,
+    ) -> bool {
+        let mut mapping = HashMap::new();
+        let mut unknown = vec![];
+
+        for (id, name) in palette {
+            match registry.blocks_by_name.get(&name.to_lowercase()) {
+                Some(block) if block.id == *id => {}
+                Some(block) => {
+                    mapping.insert(*id, Some(block.id));
+                }
+                None => {
+                    mapping.insert(*id, None);
+                    unknown.push(name.as_str());
+                }
+            }
+        }
+
+        if mapping.is_empty() {
+            return false;
+        }
+
+        let fallback = self.fallback_id(registry);
+
+        if !unknown.is_empty() {
+            warn!(
+                "Chunk {:?} has blocks that are no longer registered: {:?}, replacing them with {:?}.",
+                coords, unknown, self.config.fallback_block
+            );
+        }
+
+        for voxel in voxels.iter_mut() {
+            match mapping.get(&BlockUtils::extract_id(*voxel)) {
+                Some(Some(id)) => *voxel = BlockUtils::insert_wide_id(*voxel, *id),
+                Some(None) => *voxel = BlockUtils::insert_wide_id(0, fallback),
+                None => {}
+            }
+        }
+
+        true
+    }
+
+    /// Remap the state IDs of loaded voxels from the block properties they were saved with to the
+    /// current properties of their blocks, matching states by property names and values. Properties
+    /// that were added take their default, and ones that were taken out are dropped. Should run after
+    /// `remap_voxels`. Returns whether any voxel changed.
+    fn remap_states(
+        &self,
+        voxels: &mut [u64],
+        palette: &[(u32, String)],
+        states: &[(u32, Vec<BlockProperty>)],
+        registry: &Registry,
+    ) -> bool {
+        let mut mapping = HashMap::new();
+
+        for (id, properties) in states {
+            let block = match palette
+                .iter()
+                .find(|(palette_id, _)| palette_id == id)
+                .and_then(|(_, name)| registry.blocks_by_name.get(&name.to_lowercase()))
+            {
+                Some(block) => block,
+                None => continue,
+            };
+
+            if block.properties == *properties {
+                continue;
+            }
+
+            let state_ids = BlockProperty::combine(properties)
+                .into_iter()
+                .map(|state| {
+                    let kept: BlockState = state
+                        .into_iter()
+                        .filter(|(name, value)| {
+                            block.properties.iter().any(|property| {
+                                property.name == *name && property.values.contains(value)
+                            })
+                        })
+                        .collect();
+
+                    registry.get_state_id(block.id, &kept).unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+
+            mapping.insert(block.id, state_ids);
+        }
+
+        if mapping.is_empty() {
+            return false;
+        }
+
+        for voxel in voxels.iter_mut() {
+            if let Some(state_ids) = mapping.get(&BlockUtils::extract_id(*voxel)) {
+                let state = state_ids
+                    .get(BlockUtils::extract_state(*voxel) as usize)
+                    .cloned()
+                    .unwrap_or(0);
+
+                *voxel = self.config.voxel_encoding.insert_state(*voxel, state);
+            }
+        }
+
+        true
+    }
+
+    /// The ID of `config.fallback_block`, which takes the place of blocks that are no longer
+    /// registered. Air if the fallback block is not registered either.
+    pub(crate) fn fallback_id(&self, registry: &Registry) -> u32 {
+        match registry
+            .blocks_by_name
+            .get(&self.config.fallback_block.to_lowercase())
+        {
+            Some(block) => block.id,
+            None => {
+                warn!(
+                    "Fallback block {:?} is not registered, using air instead.",
+                    self.config.fallback_block
+                );
+                0
+            }
+        }
+    }
+
+    // Save a certain chunk, along with the names and properties of its blocks so that their IDs and states
+    // can be remapped on load.
+    // Returns false if the chunk could not be saved, in which case it stays queued to be saved again.
+    // Call `chunks.save_stale_lights` after a pass of saves.
+    pub fn save(&mut self, coords: &Vec2<i32>, registry: &Registry) -> bool {
         if !self.config.saving {
             panic!("Calling `chunks.save` when saving mode is not on.");
         }
@@ -159,29 +404,74 @@ impl Chunks {
             return false;
         };
 
-        let path = self.get_chunk_file_path(&chunk.name);
-        let mut file = File::create(&path).expect("Could not create chunk file.");
+        let mut palette: Vec<(u32, String)> = chunk
+            .raw_voxels()
+            .iter()
+            .map(|&voxel| BlockUtils::extract_id(voxel))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|id| {
+                registry
+                    .blocks_by_id
+                    .get(&id)
+                    .map(|block| (id, block.name.to_owned()))
+            })
+            .collect();
+        palette.sort();
+
+        let states = palette
+            .iter()
+            .map(|(id, _)| (*id, &registry.get_block_by_id(*id).properties))
+            .filter(|(_, properties)| !properties.is_empty())
+            .map(|(id, properties)| (id, properties.to_owned()))
+            .collect();
+
+        let record = ChunkRecord {
+            id: chunk.id.to_owned(),
+            voxels: chunk.voxels.to_vec(),
+            extras: chunk
+                .extras
+                .as_ref()
+                .map(|extras| extras.to_vec())
+                .unwrap_or_default(),
+            height_map: chunk.height_map.data.to_owned(),
+            lights: if self.config.save_lights && chunk.has_lights {
+                chunk.lights.to_vec()
+            } else {
+                vec![]
+            },
+            palette,
+            states,
+            ticks: self.ticks.chunk_ticks(coords),
+        };
 
-        let to_base_64 = |data: &Vec<u32>| {
-            let mut bytes = vec![0; data.len() * 4];
-            LittleEndian::write_u32_into(data, &mut bytes);
+        let storage = self.storage.as_ref().unwrap().to_owned();
 
-            let mut encoder = Encoder::new(vec![]).unwrap();
-            encoder.write_all(bytes.as_slice()).unwrap();
-            let encoded = encoder.finish().into_result().unwrap();
-            base64::encode(&encoded)
-        };
+        if let Err(e) = storage.save_chunk(coords, &record.encode()) {
+            error!(
+                "Could not write chunk {:?} to storage, trying again later: {}",
+                coords, e
+            );
 
-        let data = ChunkFileData {
-            id: chunk.id.to_owned(),
-            voxels: to_base_64(&chunk.voxels.data),
-            height_map: to_base_64(&chunk.height_map.data),
-        };
+            self.add_chunk_to_save(coords, false);
+            return false;
+        }
 
-        let j = serde_json::to_string(&data).unwrap();
+        // Light from this chunk may reach unloaded neighbors, whose saved lights are now out of date.
+        // Neighbors that were never saved are generated and lit from scratch anyways.
+        let mut changed = self.stale_lights.remove(coords);
 
-        file.write_all(j.as_bytes())
-            .expect("Unable to write to chunk file.");
+        for n_coords in self.light_traversed_chunks(coords) {
+            if !self.map.contains_key(&n_coords)
+                && !self.stale_lights.contains(&n_coords)
+                && storage.has_chunk(&n_coords)
+            {
+                self.stale_lights.insert(n_coords);
+                changed = true;
+            }
+        }
+
+        self.stale_lights_changed |= changed;
 
         true
     }
@@ -192,16 +482,44 @@ impl Chunks {
             if let Some(mut old_chunk) = self.map.remove(&chunk.coords) {
                 old_chunk.meshes = chunk.meshes;
                 old_chunk.status = chunk.status;
+
+                // Chunks relit while meshing, such as after a registry reload, keep their new lights.
+                if !old_chunk.has_lights && chunk.has_lights {
+                    old_chunk.lights = chunk.lights;
+                    old_chunk.has_lights = true;
+                }
                 self.map.insert(chunk.coords.to_owned(), old_chunk);
             }
 
             return;
         }
 
+        let mut chunk = chunk;
+        if !chunk.loaded_ticks.is_empty() {
+            self.ticks.restore(&std::mem::take(&mut chunk.loaded_ticks));
+        }
+
         self.map.remove(&chunk.coords);
         self.map.insert(chunk.coords.to_owned(), chunk);
     }
 
+    /// Drop a chunk from memory along with its scheduled ticks, without saving it. Returns the
+    /// removed chunk, if any.
+    pub fn unload(&mut self, coords: &Vec2<i32>) -> Option<Chunk> {
+        self.ticks.remove_chunk(coords);
+        self.idle_since.remove(coords);
+        self.cache.remove(coords);
+        self.to_save.retain(|c| c != coords);
+
+        self.listeners.remove(coords);
+        self.listeners.retain(|_, listeners| {
+            listeners.retain(|c| c != coords);
+            !listeners.is_empty()
+        });
+
+        self.map.remove(coords)
+    }
+
     /// Add a new chunk, synonym for `chunks.renew`
     pub fn add(&mut self, chunk: Chunk) {
         self.renew(chunk, false);
@@ -368,27 +686,78 @@ impl Chunks {
 
     /// Update a voxel in the chunk map. This includes recalculating the light and height maps
     /// and sending the chunk to the interested clients. This process is not instant, and will
-    /// be done in the background.
-    pub fn update_voxel(&mut self, voxel: &Vec3<i32>, val: u32) {
+    /// be done in the background. Values that do not fit `config.voxel_encoding` are dropped.
+    pub fn update_voxel(&mut self, voxel: &Vec3<i32>, val: u64) {
+        if !self.config.voxel_encoding.holds(val) {
+            warn!(
+                "Dropped update of voxel {:?} to {:#x}, which needs the wide voxel encoding.",
+                voxel, val
+            );
+            return;
+        }
+
         self.updates
             .retain(|(v, _)| !(v.0 == voxel.0 && v.1 == voxel.1 && v.2 == voxel.2));
 
         self.updates.push_back((voxel.to_owned(), val));
+        self.update_sources.remove(voxel);
     }
 
-    pub fn update_voxels(&mut self, voxels: &[(Vec3<i32>, u32)]) {
-        for (voxel, val) in voxels {
-            self.update_voxel(voxel, *val);
+    /// Update a list of voxels, see `update_voxel`. Later updates to the same voxel win.
+    pub fn update_voxels(&mut self, voxels: &[VoxelUpdate]) {
+        let encoding = self.config.voxel_encoding;
+        let dropped = voxels
+            .iter()
+            .filter(|(_, val)| !encoding.holds(*val))
+            .count();
+
+        if dropped > 0 {
+            warn!(
+                "Dropped {} voxel updates that need the wide voxel encoding.",
+                dropped
+            );
+
+            let voxels: Vec<VoxelUpdate> = voxels
+                .iter()
+                .filter(|(_, val)| encoding.holds(*val))
+                .cloned()
+                .collect();
+
+            return self.update_voxels(&voxels);
         }
+
+        let updated: HashSet<&Vec3<i32>> = voxels.iter().map(|(voxel, _)| voxel).collect();
+        self.updates.retain(|(voxel, _)| !updated.contains(voxel));
+        self.update_sources
+            .retain(|voxel, _| !updated.contains(voxel));
+
+        let mut seen = HashSet::new();
+        let latest: Vec<&VoxelUpdate> = voxels
+            .iter()
+            .rev()
+            .filter(|(voxel, _)| seen.insert(voxel))
+            .collect();
+
+        self.updates
+            .extend(latest.into_iter().rev().map(|update| update.to_owned()));
     }
 
-    pub fn mark_voxel_active(&mut self, voxel: &Vec3<i32>, active_at: u64) {
-        if let Some(_) = self.active_voxels.iter().find(|(_, v)| v == voxel) {
-            self.active_voxels
-                .retain(|(_, v)| !(v.0 == voxel.0 && v.1 == voxel.1 && v.2 == voxel.2));
+    /// Update a list of voxels on behalf of a source, such as a client ID, which the voxel history
+    /// records the changes under. See `update_voxels`.
+    pub fn update_voxels_as(&mut self, source: &str, voxels: &[VoxelUpdate]) {
+        self.update_voxels(voxels);
+
+        for (voxel, val) in voxels {
+            if self.config.voxel_encoding.holds(*val) {
+                self.update_sources
+                    .insert(voxel.to_owned(), source.to_owned());
+            }
         }
+    }
 
-        self.active_voxels.push((active_at, voxel.to_owned()));
+    /// Schedule a voxel to tick at a certain tick, replacing its pending tick if it has one.
+    pub fn mark_voxel_active(&mut self, voxel: &Vec3<i32>, active_at: u64) {
+        self.ticks.schedule(voxel, active_at);
     }
 
     /// Add a chunk to be saved.
@@ -423,16 +792,6 @@ impl Chunks {
         self.listeners.insert(coords.to_owned(), listeners);
     }
 
-    fn get_chunk_file_path(&self, chunk_name: &str) -> PathBuf {
-        if self.folder.is_none() {
-            return PathBuf::new();
-        }
-
-        let mut path = self.folder.clone().unwrap();
-        path.push(format!("{}.json", chunk_name));
-        path
-    }
-
     fn add_updated_level_at(&mut self, vx: i32, vy: i32, vz: i32) {
         self.voxel_affected_chunks(vx, vy, vz)
             .into_iter()
@@ -445,8 +804,12 @@ impl Chunks {
 }
 
 impl VoxelAccess for Chunks {
+    fn voxel_encoding(&self) -> VoxelEncoding {
+        self.config.voxel_encoding
+    }
+
     /// Get the raw voxel value at a voxel coordinate. If chunk not found, 0 is returned.
-    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u64 {
         if let Some(chunk) = self.raw_chunk_by_voxel(vx, vy, vz) {
             chunk.get_raw_voxel(vx, vy, vz)
         } else {
@@ -455,7 +818,7 @@ impl VoxelAccess for Chunks {
     }
 
     /// Set the raw voxel value at a voxel coordinate. Returns false couldn't set.
-    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, id: u32) -> bool {
+    fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, id: u64) -> bool {
         if let Some(chunk) = self.raw_chunk_by_voxel_mut(vx, vy, vz) {
             chunk.set_raw_voxel(vx, vy, vz, id);
             self.add_updated_level_at(vx, vy, vz);
@@ -0,0 +1,408 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Block, BlockFace, BlockFaces, BlockProperty, YRotatableSegments, AABB};
+
+/// Faces of a block made from one of the `BlockFaces` presets, such as
+/// `{ "preset": "sixFaces", "scaleY": 0.5 }`. Parameters that are left out keep the default of the preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "preset")]
+pub enum FacesDefinition {
+    /// `BlockFaces::six_faces`, ordered PX, PY, PZ, NX, NY, NZ.
+    #[serde(rename_all = "camelCase")]
+    SixFaces {
+        scale_x: Option<f32>,
+        scale_y: Option<f32>,
+        scale_z: Option<f32>,
+        offset_x: Option<f32>,
+        offset_y: Option<f32>,
+        offset_z: Option<f32>,
+        uv_scale_x: Option<f32>,
+        uv_scale_y: Option<f32>,
+        uv_scale_z: Option<f32>,
+        uv_offset_x: Option<f32>,
+        uv_offset_y: Option<f32>,
+        uv_offset_z: Option<f32>,
+        prefix: Option<String>,
+        suffix: Option<String>,
+        concat: Option<String>,
+        auto_uv_offset: Option<bool>,
+
+        /// Indices of the faces that have a texture of their own.
+        #[serde(default)]
+        independent_at: Vec<usize>,
+
+        /// Indices of the faces that have a texture of their own per voxel.
+        #[serde(default)]
+        isolated_at: Vec<usize>,
+    },
+
+    /// `BlockFaces::diagonal_faces`, ordered one, two.
+    #[serde(rename_all = "camelCase")]
+    DiagonalFaces {
+        scale_horizontal: Option<f32>,
+        scale_vertical: Option<f32>,
+        offset_x: Option<f32>,
+        offset_y: Option<f32>,
+        offset_z: Option<f32>,
+        prefix: Option<String>,
+        suffix: Option<String>,
+        concat: Option<String>,
+
+        /// Split each diagonal face in two.
+        #[serde(default)]
+        to_four: bool,
+    },
+}
+
+impl FacesDefinition {
+    /// Build the faces this definition describes.
+    pub fn build(&self) -> Vec<BlockFace> {
+        match self {
+            Self::SixFaces {
+                scale_x,
+                scale_y,
+                scale_z,
+                offset_x,
+                offset_y,
+                offset_z,
+                uv_scale_x,
+                uv_scale_y,
+                uv_scale_z,
+                uv_offset_x,
+                uv_offset_y,
+                uv_offset_z,
+                prefix,
+                suffix,
+                concat,
+                auto_uv_offset,
+                independent_at,
+                isolated_at,
+            } => {
+                let mut builder = BlockFaces::six_faces();
+
+                if let Some(value) = *scale_x {
+                    builder = builder.scale_x(value);
+                }
+                if let Some(value) = *scale_y {
+                    builder = builder.scale_y(value);
+                }
+                if let Some(value) = *scale_z {
+                    builder = builder.scale_z(value);
+                }
+                if let Some(value) = *offset_x {
+                    builder = builder.offset_x(value);
+                }
+                if let Some(value) = *offset_y {
+                    builder = builder.offset_y(value);
+                }
+                if let Some(value) = *offset_z {
+                    builder = builder.offset_z(value);
+                }
+                if let Some(value) = *uv_scale_x {
+                    builder = builder.uv_scale_x(value);
+                }
+                if let Some(value) = *uv_scale_y {
+                    builder = builder.uv_scale_y(value);
+                }
+                if let Some(value) = *uv_scale_z {
+                    builder = builder.uv_scale_z(value);
+                }
+                if let Some(value) = *uv_offset_x {
+                    builder = builder.uv_offset_x(value);
+                }
+                if let Some(value) = *uv_offset_y {
+                    builder = builder.uv_offset_y(value);
+                }
+                if let Some(value) = *uv_offset_z {
+                    builder = builder.uv_offset_z(value);
+                }
+                if let Some(value) = prefix {
+                    builder = builder.prefix(value);
+                }
+                if let Some(value) = suffix {
+                    builder = builder.suffix(value);
+                }
+                if let Some(value) = concat {
+                    builder = builder.concat(value);
+                }
+                if let Some(value) = *auto_uv_offset {
+                    builder = builder.auto_uv_offset(value);
+                }
+                for &index in independent_at {
+                    builder = builder.independent_at(index);
+                }
+                for &index in isolated_at {
+                    builder = builder.isolated_at(index);
+                }
+
+                builder.build().to_vec()
+            }
+            Self::DiagonalFaces {
+                scale_horizontal,
+                scale_vertical,
+                offset_x,
+                offset_y,
+                offset_z,
+                prefix,
+                suffix,
+                concat,
+                to_four,
+            } => {
+                let mut builder = BlockFaces::diagonal_faces();
+
+                if let Some(value) = *scale_horizontal {
+                    builder = builder.scale_horizontal(value);
+                }
+                if let Some(value) = *scale_vertical {
+                    builder = builder.scale_vertical(value);
+                }
+                if let Some(value) = *offset_x {
+                    builder = builder.offset_x(value);
+                }
+                if let Some(value) = *offset_y {
+                    builder = builder.offset_y(value);
+                }
+                if let Some(value) = *offset_z {
+                    builder = builder.offset_z(value);
+                }
+                if let Some(value) = prefix {
+                    builder = builder.prefix(value);
+                }
+                if let Some(value) = suffix {
+                    builder = builder.suffix(value);
+                }
+                if let Some(value) = concat {
+                    builder = builder.concat(value);
+                }
+                if *to_four {
+                    builder = builder.to_four();
+                }
+
+                builder.build().to_vec()
+            }
+        }
+    }
+}
+
+/// A block as written in a block file. Everything but the name is optional and falls back to the
+/// defaults of `Block::new`. Callbacks such as `on_signal` can only be set in Rust.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDefinition {
+    /// Name of the block.
+    pub name: String,
+
+    /// ID of the block. Defaults to the next available ID, or the ID it already has on reload.
+    pub id: Option<u32>,
+
+    pub rotatable: Option<bool>,
+    pub y_rotatable: Option<bool>,
+    pub y_rotatable_segments: Option<YRotatableSegments>,
+    pub is_empty: Option<bool>,
+    pub is_fluid: Option<bool>,
+    pub fluid_flow_rate: Option<u64>,
+    pub fluid_range: Option<u32>,
+    pub fluid_regenerates: Option<bool>,
+    pub gravity: Option<bool>,
+    pub signal_power: Option<u32>,
+    pub signal_conductor: Option<bool>,
+    pub signal_faces: Option<[bool; 6]>,
+    pub is_passable: Option<bool>,
+
+    /// Sets the red, green and blue light levels at once, before the levels of each color.
+    pub torch_light_level: Option<u32>,
+    pub red_light_level: Option<u32>,
+    pub green_light_level: Option<u32>,
+    pub blue_light_level: Option<u32>,
+
+    pub transparent_standalone: Option<bool>,
+    pub is_see_through: Option<bool>,
+    pub light_reduce: Option<bool>,
+    pub is_entity: Option<bool>,
+
+    /// Tags of the block, such as "logs" or "replaceable".
+    pub tags: Option<Vec<String>>,
+
+    /// Named properties of the block, such as `{ "name": "open", "values": [false, true] }`.
+    pub properties: Option<Vec<BlockProperty>>,
+
+    /// Sets the transparency of every side, before that of each axis and of each side.
+    pub is_transparent: Option<bool>,
+    pub is_x_transparent: Option<bool>,
+    pub is_y_transparent: Option<bool>,
+    pub is_z_transparent: Option<bool>,
+    pub is_px_transparent: Option<bool>,
+    pub is_py_transparent: Option<bool>,
+    pub is_pz_transparent: Option<bool>,
+    pub is_nx_transparent: Option<bool>,
+    pub is_ny_transparent: Option<bool>,
+    pub is_nz_transparent: Option<bool>,
+
+    /// Faces of the block, joined in order. Defaults to a full six-faced cube.
+    pub faces: Option<Vec<FacesDefinition>>,
+
+    /// Bounding boxes of the block. Defaults to a full cube.
+    pub aabbs: Option<Vec<AABB>>,
+}
+
+impl BlockDefinition {
+    /// Build the block this definition describes.
+    pub fn to_block(&self) -> Block {
+        let mut builder = Block::new(&self.name);
+
+        if let Some(id) = self.id {
+            builder = builder.id(id);
+        }
+        if let Some(value) = self.rotatable {
+            builder = builder.rotatable(value);
+        }
+        if let Some(value) = self.y_rotatable {
+            builder = builder.y_rotatable(value);
+        }
+        if let Some(value) = &self.y_rotatable_segments {
+            builder = builder.y_rotatable_segments(value);
+        }
+        if let Some(value) = self.is_empty {
+            builder = builder.is_empty(value);
+        }
+        if let Some(value) = self.is_fluid {
+            builder = builder.is_fluid(value);
+        }
+        if let Some(value) = self.fluid_flow_rate {
+            builder = builder.fluid_flow_rate(value);
+        }
+        if let Some(value) = self.fluid_range {
+            builder = builder.fluid_range(value);
+        }
+        if let Some(value) = self.fluid_regenerates {
+            builder = builder.fluid_regenerates(value);
+        }
+        if let Some(value) = self.gravity {
+            builder = builder.gravity(value);
+        }
+        if let Some(value) = self.signal_power {
+            builder = builder.signal_power(value);
+        }
+        if let Some(value) = self.signal_conductor {
+            builder = builder.signal_conductor(value);
+        }
+        if let Some(value) = self.signal_faces {
+            builder = builder.signal_faces(value);
+        }
+        if let Some(value) = self.is_passable {
+            builder = builder.is_passable(value);
+        }
+        if let Some(value) = self.torch_light_level {
+            builder = builder.torch_light_level(value);
+        }
+        if let Some(value) = self.red_light_level {
+            builder = builder.red_light_level(value);
+        }
+        if let Some(value) = self.green_light_level {
+            builder = builder.green_light_level(value);
+        }
+        if let Some(value) = self.blue_light_level {
+            builder = builder.blue_light_level(value);
+        }
+        if let Some(value) = self.transparent_standalone {
+            builder = builder.transparent_standalone(value);
+        }
+        if let Some(value) = self.is_see_through {
+            builder = builder.is_see_through(value);
+        }
+        if let Some(value) = self.light_reduce {
+            builder = builder.light_reduce(value);
+        }
+        if let Some(value) = self.is_entity {
+            builder = builder.is_entity(value);
+        }
+        if let Some(tags) = &self.tags {
+            for tag in tags {
+                builder = builder.tag(tag);
+            }
+        }
+        if let Some(properties) = &self.properties {
+            for property in properties {
+                builder = builder.property(property.to_owned());
+            }
+        }
+        if let Some(value) = self.is_transparent {
+            builder = builder.is_transparent(value);
+        }
+        if let Some(value) = self.is_x_transparent {
+            builder = builder.is_x_transparent(value);
+        }
+        if let Some(value) = self.is_y_transparent {
+            builder = builder.is_y_transparent(value);
+        }
+        if let Some(value) = self.is_z_transparent {
+            builder = builder.is_z_transparent(value);
+        }
+        if let Some(value) = self.is_px_transparent {
+            builder = builder.is_px_transparent(value);
+        }
+        if let Some(value) = self.is_py_transparent {
+            builder = builder.is_py_transparent(value);
+        }
+        if let Some(value) = self.is_pz_transparent {
+            builder = builder.is_pz_transparent(value);
+        }
+        if let Some(value) = self.is_nx_transparent {
+            builder = builder.is_nx_transparent(value);
+        }
+        if let Some(value) = self.is_ny_transparent {
+            builder = builder.is_ny_transparent(value);
+        }
+        if let Some(value) = self.is_nz_transparent {
+            builder = builder.is_nz_transparent(value);
+        }
+        if let Some(faces) = &self.faces {
+            let faces = faces
+                .iter()
+                .flat_map(|faces| faces.build())
+                .collect::<Vec<_>>();
+            builder = builder.faces(&faces);
+        }
+        if let Some(aabbs) = &self.aabbs {
+            builder = builder.aabbs(aabbs);
+        }
+
+        builder.build()
+    }
+}
+
+/// The contents of a block file, a `blocks` list in JSON, or `[[blocks]]` tables in TOML.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockFile {
+    pub blocks: Vec<BlockDefinition>,
+}
+
+impl BlockFile {
+    /// Whether a path looks like a block file, going by its extension.
+    pub fn is_block_file(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("json") | Some("toml")
+        )
+    }
+
+    /// Read a block file, parsed as TOML if it ends with `.toml` and as JSON otherwise.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data = fs::read_to_string(path)?;
+
+        let parsed = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&data).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_str(&data).map_err(|e| e.to_string())
+        };
+
+        parsed.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not parse block file {:?}: {}", path, e),
+            )
+        })
+    }
+}
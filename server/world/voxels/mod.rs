@@ -2,10 +2,20 @@ mod access;
 mod block;
 mod chunk;
 mod chunks;
+mod definitions;
+mod palette;
+mod schematic;
 mod space;
+mod states;
+mod ticks;
 
 pub use access::VoxelAccess;
 pub use block::*;
 pub use chunk::*;
 pub use chunks::Chunks;
+pub use definitions::*;
+pub use palette::*;
+pub use schematic::*;
 pub use space::*;
+pub use states::*;
+pub use ticks::*;
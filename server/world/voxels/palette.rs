@@ -0,0 +1,376 @@
+use std::mem::size_of;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::Ndarray;
+
+/// Number of bits in a word of packed palette indices.
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Number of bits needed to index into a palette of `len` values. A palette of one value needs none.
+fn bits_for(len: usize) -> usize {
+    if len <= 1 {
+        0
+    } else {
+        (usize::BITS - (len - 1).leading_zeros()) as usize
+    }
+}
+
+/// A section of values stored as a palette of the distinct values and a bit-packed array of
+/// indices into that palette. A section holding a single value stores no indices at all.
+#[derive(Debug, Clone)]
+pub struct PaletteSection {
+    /// Number of values in this section.
+    len: usize,
+
+    /// The distinct values of this section. May hold values no longer in use until the next repack.
+    palette: Vec<u32>,
+
+    /// Bits per packed index, zero if the section holds a single value.
+    bits: usize,
+
+    /// Packed palette indices. Indices never span across two words.
+    words: Vec<u64>,
+}
+
+impl PaletteSection {
+    /// Create a section of `len` values that are all `value`.
+    pub fn new(len: usize, value: u32) -> Self {
+        Self {
+            len,
+            palette: vec![value],
+            bits: 0,
+            words: vec![],
+        }
+    }
+
+    /// Create a section from a list of values, with the smallest palette possible.
+    pub fn from_values(values: &[u32]) -> Self {
+        Self::pack(values, 0)
+    }
+
+    /// Create a section from a list of values, with room for at least `1 << min_bits` distinct values.
+    fn pack(values: &[u32], min_bits: usize) -> Self {
+        let mut palette = vec![];
+        let mut lookup = HashMap::new();
+
+        let indices: Vec<usize> = values
+            .iter()
+            .map(|&value| {
+                *lookup.entry(value).or_insert_with(|| {
+                    palette.push(value);
+                    palette.len() - 1
+                })
+            })
+            .collect();
+
+        if palette.len() <= 1 && min_bits == 0 {
+            return Self::new(values.len(), palette.first().cloned().unwrap_or_default());
+        }
+
+        let bits = bits_for(palette.len()).max(min_bits);
+        let per_word = WORD_BITS / bits;
+
+        let mut section = Self {
+            len: values.len(),
+            palette,
+            bits,
+            words: vec![0; values.len().div_ceil(per_word)],
+        };
+
+        indices
+            .into_iter()
+            .enumerate()
+            .for_each(|(i, index)| section.write_index(i, index));
+
+        section
+    }
+
+    /// Get the value at an index of this section.
+    pub fn get(&self, i: usize) -> u32 {
+        if self.bits == 0 {
+            return self.palette[0];
+        }
+
+        self.palette[self.read_index(i)]
+    }
+
+    /// Set the value at an index of this section, growing the palette if needed.
+    pub fn set(&mut self, i: usize, value: u32) {
+        let index = match self.palette.iter().position(|&v| v == value) {
+            Some(index) => index,
+            None => {
+                if self.palette.len() >= 1 << self.bits {
+                    self.repack(i, value);
+                    return;
+                }
+
+                self.palette.push(value);
+                self.palette.len() - 1
+            }
+        };
+
+        if self.bits == 0 {
+            return;
+        }
+
+        self.write_index(i, index);
+    }
+
+    /// All the values of this section, in order.
+    pub fn values(&self) -> Vec<u32> {
+        (0..self.len).map(|i| self.get(i)).collect()
+    }
+
+    /// Number of distinct values this section can currently reference.
+    pub fn palette_len(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Whether this section holds a single value.
+    pub fn is_single_value(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Approximate number of bytes this section takes up.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>() + self.palette.capacity() * 4 + self.words.capacity() * 8
+    }
+
+    /// Out of room for a new value, so repack with only the values still in use. The bit width only
+    /// grows when that frees less than half of the palette, and then leaves at least half of it
+    /// free, so that values coming in one at a time do not repack the whole section every time.
+    fn repack(&mut self, i: usize, value: u32) {
+        let mut values = self.values();
+        values[i] = value;
+
+        let used = values.iter().collect::<HashSet<_>>().len();
+        let bits = if used * 2 <= 1 << self.bits {
+            self.bits
+        } else {
+            bits_for(used * 2)
+        };
+
+        *self = Self::pack(&values, bits);
+    }
+
+    fn read_index(&self, i: usize) -> usize {
+        let per_word = WORD_BITS / self.bits;
+        let shift = (i % per_word) * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+
+        ((self.words[i / per_word] >> shift) & mask) as usize
+    }
+
+    fn write_index(&mut self, i: usize, index: usize) {
+        let per_word = WORD_BITS / self.bits;
+        let shift = (i % per_word) * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+
+        let word = &mut self.words[i / per_word];
+        *word = (*word & !(mask << shift)) | ((index as u64) << shift);
+    }
+}
+
+/// Per-voxel values of a chunk split into vertical sections, one per sub-chunk, each with its own palette.
+#[derive(Debug, Clone)]
+pub struct PaletteArray {
+    /// Shape of the array, `[size, max_height, size]`.
+    pub shape: Vec<usize>,
+
+    /// Height of each section. The top section is shorter if the height of the array is not a
+    /// multiple of it.
+    pub section_height: usize,
+
+    /// The sections, from bottom to top.
+    pub sections: Vec<PaletteSection>,
+}
+
+impl PaletteArray {
+    /// Create a palette array of a shape, filled with one value.
+    pub fn new(shape: &[usize], default: u32, sections: usize) -> Self {
+        let section_height = shape[1].div_ceil(sections.max(1)).max(1);
+
+        Self {
+            shape: shape.to_vec(),
+            section_height,
+            sections: (0..shape[1].div_ceil(section_height).max(1))
+                .map(|section| {
+                    let height = (shape[1] - section * section_height).min(section_height);
+                    PaletteSection::new(shape[0] * height * shape[2], default)
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the value at a local coordinate.
+    pub fn get(&self, lx: usize, ly: usize, lz: usize) -> u32 {
+        let (section, i) = self.locate(lx, ly, lz);
+        self.sections[section].get(i)
+    }
+
+    /// Set the value at a local coordinate.
+    pub fn set(&mut self, lx: usize, ly: usize, lz: usize, value: u32) {
+        let (section, i) = self.locate(lx, ly, lz);
+        self.sections[section].set(i, value);
+    }
+
+    /// Replace all the values with data laid out like an `Ndarray` of the same shape.
+    pub fn assign(&mut self, data: &[u32]) {
+        let mut values = vec![vec![]; self.sections.len()];
+
+        for lx in 0..self.shape[0] {
+            for ly in 0..self.shape[1] {
+                let row = (lx * self.shape[1] + ly) * self.shape[2];
+                values[ly / self.section_height].extend_from_slice(&data[row..row + self.shape[2]]);
+            }
+        }
+
+        self.sections = values
+            .iter()
+            .map(|values| PaletteSection::from_values(values))
+            .collect();
+    }
+
+    /// Expand into a dense n-dimensional array.
+    pub fn to_ndarray(&self) -> Ndarray<u32> {
+        let mut ndarray = Ndarray::new(&self.shape, 0);
+
+        for lx in 0..self.shape[0] {
+            for ly in 0..self.shape[1] {
+                for lz in 0..self.shape[2] {
+                    ndarray[&[lx, ly, lz]] = self.get(lx, ly, lz);
+                }
+            }
+        }
+
+        ndarray
+    }
+
+    /// Approximate number of bytes this array takes up.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .sections
+                .iter()
+                .map(|section| section.memory_usage())
+                .sum::<usize>()
+    }
+
+    /// Convert a local coordinate to a section and an index within the section.
+    fn locate(&self, lx: usize, ly: usize, lz: usize) -> (usize, usize) {
+        let section = ly / self.section_height;
+        let sy = ly % self.section_height;
+        let height = (self.shape[1] - section * self.section_height).min(self.section_height);
+        let i = (lx * height + sy) * self.shape[2] + lz;
+
+        (section, i)
+    }
+}
+
+/// Storage of per-voxel chunk data such as voxels or lights. Either a dense n-dimensional array,
+/// or palette compressed sections that take much less memory for mostly uniform chunks.
+#[derive(Debug, Clone)]
+pub enum ChunkArray {
+    Dense(Ndarray<u32>),
+    Paletted(PaletteArray),
+}
+
+impl Default for ChunkArray {
+    fn default() -> Self {
+        Self::Dense(Ndarray::default())
+    }
+}
+
+impl ChunkArray {
+    /// Create a chunk array of a shape filled with one value. Paletted arrays get one section per sub-chunk.
+    pub fn new(shape: &[usize], default: u32, paletted: bool, sub_chunks: usize) -> Self {
+        if paletted {
+            Self::Paletted(PaletteArray::new(shape, default, sub_chunks))
+        } else {
+            Self::Dense(Ndarray::new(shape, default))
+        }
+    }
+
+    /// Get the value at a local coordinate.
+    pub fn get(&self, lx: usize, ly: usize, lz: usize) -> u32 {
+        match self {
+            Self::Dense(ndarray) => ndarray[&[lx, ly, lz]],
+            Self::Paletted(array) => array.get(lx, ly, lz),
+        }
+    }
+
+    /// Set the value at a local coordinate.
+    pub fn set(&mut self, lx: usize, ly: usize, lz: usize, value: u32) {
+        match self {
+            Self::Dense(ndarray) => ndarray[&[lx, ly, lz]] = value,
+            Self::Paletted(array) => array.set(lx, ly, lz, value),
+        }
+    }
+
+    /// Shape of the array.
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            Self::Dense(ndarray) => &ndarray.shape,
+            Self::Paletted(array) => &array.shape,
+        }
+    }
+
+    /// Number of values in the array.
+    pub fn len(&self) -> usize {
+        self.shape().iter().product()
+    }
+
+    /// Whether the array holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether this array is palette compressed.
+    pub fn is_paletted(&self) -> bool {
+        matches!(self, Self::Paletted(_))
+    }
+
+    /// Replace all the values with data laid out like an `Ndarray` of the same shape, keeping the
+    /// storage kind. Panics if the lengths do not match.
+    pub fn assign(&mut self, data: Vec<u32>) {
+        assert_eq!(data.len(), self.len(), "Chunk array data length mismatch.");
+
+        match self {
+            Self::Dense(ndarray) => ndarray.data = data,
+            Self::Paletted(array) => array.assign(&data),
+        }
+    }
+
+    /// Get the dense array directly, if this array is not palette compressed.
+    pub fn as_ndarray(&self) -> Option<&Ndarray<u32>> {
+        match self {
+            Self::Dense(ndarray) => Some(ndarray),
+            Self::Paletted(_) => None,
+        }
+    }
+
+    /// Copy the values into a dense n-dimensional array.
+    pub fn to_ndarray(&self) -> Ndarray<u32> {
+        match self {
+            Self::Dense(ndarray) => ndarray.clone(),
+            Self::Paletted(array) => array.to_ndarray(),
+        }
+    }
+
+    /// Copy the values into a list laid out like an `Ndarray`.
+    pub fn to_vec(&self) -> Vec<u32> {
+        match self {
+            Self::Dense(ndarray) => ndarray.data.clone(),
+            Self::Paletted(array) => array.to_ndarray().data,
+        }
+    }
+
+    /// Approximate number of bytes this array takes up.
+    pub fn memory_usage(&self) -> usize {
+        match self {
+            Self::Dense(ndarray) => size_of::<Self>() + ndarray.data.capacity() * 4,
+            Self::Paletted(array) => array.memory_usage(),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use hashbrown::{HashMap, HashSet};
+use libflate::zlib::{Decoder, Encoder};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{BlockUtils, Registry, Vec3, VoxelEncoding, VoxelUpdate};
+
+use super::{
+    access::VoxelAccess,
+    block::{
+        BlockRotation, NX_ROTATION, NY_ROTATION, NZ_ROTATION, PX_ROTATION, PZ_ROTATION,
+        Y_ROT_SEGMENTS,
+    },
+    chunks::Chunks,
+};
+
+/// Version of the schematic file layout.
+pub const SCHEMATIC_VERSION: u32 = 1;
+
+/// A block entity captured in a schematic.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchematicBlockEntity {
+    /// Position of the block entity relative to the schematic's minimum corner.
+    pub voxel: Vec3<i32>,
+
+    /// The JSON data of the block entity.
+    pub json: String,
+}
+
+/// A portable box of voxels, saved as zlib-compressed JSON. Block IDs are stored alongside the
+/// names of their blocks so that a schematic can be pasted into worlds with different registries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Schematic {
+    /// Version of the layout the schematic was saved with.
+    pub version: u32,
+
+    /// Size of the box in voxels, along x, y and z.
+    pub size: Vec3<usize>,
+
+    /// Raw voxel values (ID, rotation and stage), indexed by `(x * size.1 + y) * size.2 + z`.
+    pub voxels: Vec<u64>,
+
+    /// Block ID -> block name of every block in the schematic.
+    pub palette: Vec<(u32, String)>,
+
+    /// Block entities inside the box.
+    pub block_entities: Vec<SchematicBlockEntity>,
+}
+
+/// How a schematic is placed when pasted. Mirroring is applied before rotating.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasteOptions {
+    /// Number of quarter turns around the y axis, in the same direction as block y rotations.
+    pub rotation: u32,
+
+    /// Whether to mirror the schematic along the x axis.
+    pub mirror_x: bool,
+
+    /// Whether to mirror the schematic along the z axis.
+    pub mirror_z: bool,
+
+    /// Whether air in the schematic leaves the voxels it lands on untouched.
+    pub skip_air: bool,
+}
+
+impl PasteOptions {
+    /// The size of a box of `size` once pasted with these options.
+    pub fn pasted_size(&self, size: &Vec3<usize>) -> Vec3<usize> {
+        if self.rotation % 2 == 1 {
+            Vec3(size.2, size.1, size.0)
+        } else {
+            size.to_owned()
+        }
+    }
+
+    /// Where a voxel inside a box of `size` lands relative to the pasted box's minimum corner.
+    pub fn transform_voxel(&self, voxel: &Vec3<i32>, size: &Vec3<usize>) -> Vec3<i32> {
+        let Vec3(mut x, y, mut z) = voxel.to_owned();
+        let (mut width, mut depth) = (size.0 as i32, size.2 as i32);
+
+        if self.mirror_x {
+            x = width - 1 - x;
+        }
+
+        if self.mirror_z {
+            z = depth - 1 - z;
+        }
+
+        // Same direction as `BlockRotation::rotate_node`, which turns +x into -z.
+        for _ in 0..self.rotation % 4 {
+            (x, z) = (z, width - 1 - x);
+            (width, depth) = (depth, width);
+        }
+
+        Vec3(x, y, z)
+    }
+
+    /// Turn the rotation of a raw voxel value along with the schematic. Only the parts of the
+    /// rotation the block supports are changed. Y rotations are mirrored as if blocks face along
+    /// the z axis when unrotated.
+    pub fn transform_raw(&self, raw: u64, rotatable: bool, y_rotatable: bool) -> u64 {
+        if !rotatable && !y_rotatable {
+            return raw;
+        }
+
+        let (mut axis, mut y_rotation) = BlockRotation::decode(&BlockUtils::extract_rotation(raw));
+        let half = Y_ROT_SEGMENTS / 2;
+        let quarter = Y_ROT_SEGMENTS / 4;
+
+        if self.mirror_x {
+            if rotatable {
+                axis = match axis {
+                    PX_ROTATION => NX_ROTATION,
+                    NX_ROTATION => PX_ROTATION,
+                    other => other,
+                };
+            }
+
+            if y_rotatable {
+                y_rotation = (Y_ROT_SEGMENTS - y_rotation) % Y_ROT_SEGMENTS;
+            }
+        }
+
+        if self.mirror_z {
+            if rotatable {
+                axis = match axis {
+                    PZ_ROTATION => NZ_ROTATION,
+                    NZ_ROTATION => PZ_ROTATION,
+                    other => other,
+                };
+            }
+
+            if y_rotatable {
+                y_rotation = (half + Y_ROT_SEGMENTS - y_rotation) % Y_ROT_SEGMENTS;
+            }
+        }
+
+        for _ in 0..self.rotation % 4 {
+            if rotatable {
+                axis = match axis {
+                    PX_ROTATION => NZ_ROTATION,
+                    NZ_ROTATION => NX_ROTATION,
+                    NX_ROTATION => PZ_ROTATION,
+                    PZ_ROTATION => PX_ROTATION,
+                    other => other,
+                };
+            }
+
+            // Upside down blocks are flipped after their y rotation, which reverses it.
+            if y_rotatable {
+                y_rotation = if axis == NY_ROTATION {
+                    (y_rotation + Y_ROT_SEGMENTS - quarter) % Y_ROT_SEGMENTS
+                } else {
+                    (y_rotation + quarter) % Y_ROT_SEGMENTS
+                };
+            }
+        }
+
+        BlockUtils::insert_rotation(raw, &BlockRotation::encode(axis, y_rotation))
+    }
+}
+
+impl Schematic {
+    /// Index of a voxel in `voxels`.
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * self.size.1 + y) * self.size.2 + z
+    }
+
+    /// Raw voxel value at a position relative to the minimum corner.
+    pub fn get_raw_voxel(&self, x: usize, y: usize, z: usize) -> u64 {
+        self.voxels[self.index(x, y, z)]
+    }
+
+    /// Encode this schematic into bytes.
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut encoder = Encoder::new(Vec::new())?;
+        encoder.write_all(&serde_json::to_vec(self)?)?;
+        encoder.finish().into_result()
+    }
+
+    /// Decode a schematic from bytes.
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut data = vec![];
+        Decoder::new(bytes)?.read_to_end(&mut data)?;
+
+        let schematic: Self = serde_json::from_slice(&data)?;
+
+        if schematic.version > SCHEMATIC_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Schematic version {} is newer than the supported version {}.",
+                    schematic.version, SCHEMATIC_VERSION
+                ),
+            ));
+        }
+
+        if schematic.voxels.len() != schematic.size.0 * schematic.size.1 * schematic.size.2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Schematic voxels do not match its size.",
+            ));
+        }
+
+        Ok(schematic)
+    }
+
+    /// Save this schematic to a file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.encode()?)
+    }
+
+    /// Load a schematic from a file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// The voxel updates that paste this schematic with its minimum corner at `origin`, with its
+    /// blocks mapped to a registry by name. Blocks the registry does not know are skipped, and the
+    /// bits of each voxel that `encoding` does not hold are dropped.
+    pub fn voxel_updates(
+        &self,
+        origin: &Vec3<i32>,
+        options: &PasteOptions,
+        registry: &Registry,
+        encoding: VoxelEncoding,
+    ) -> Vec<VoxelUpdate> {
+        let mut mapping = HashMap::new();
+        let mut unknown = vec![];
+
+        for (id, name) in &self.palette {
+            match registry.blocks_by_name.get(&name.to_lowercase()) {
+                Some(block) => {
+                    mapping.insert(*id, block);
+                }
+                None => unknown.push(name.as_str()),
+            }
+        }
+
+        if !unknown.is_empty() {
+            warn!(
+                "Schematic has blocks that are not registered: {:?}, skipping them.",
+                unknown
+            );
+        }
+
+        let mut updates = vec![];
+
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                for z in 0..self.size.2 {
+                    let raw = self.get_raw_voxel(x, y, z);
+
+                    let block = match mapping.get(&BlockUtils::extract_id(raw)) {
+                        Some(block) => block,
+                        None => continue,
+                    };
+
+                    if options.skip_air && block.is_empty {
+                        continue;
+                    }
+
+                    let raw = encoding.mask(options.transform_raw(
+                        BlockUtils::insert_wide_id(raw, block.id),
+                        block.rotatable,
+                        block.y_rotatable,
+                    ));
+
+                    let Vec3(px, py, pz) =
+                        options.transform_voxel(&Vec3(x as i32, y as i32, z as i32), &self.size);
+
+                    updates.push((Vec3(origin.0 + px, origin.1 + py, origin.2 + pz), raw));
+                }
+            }
+        }
+
+        updates
+    }
+}
+
+impl Chunks {
+    /// Copy the voxels between two inclusive corners into a schematic. Voxels in chunks that are
+    /// not loaded are exported as air. Block entities are left out, as their data lives in the ECS,
+    /// see `World::export_schematic`.
+    pub fn export_schematic(
+        &self,
+        min: &Vec3<i32>,
+        max: &Vec3<i32>,
+        registry: &Registry,
+    ) -> Schematic {
+        let (min, max) = (
+            Vec3(min.0.min(max.0), min.1.min(max.1), min.2.min(max.2)),
+            Vec3(min.0.max(max.0), min.1.max(max.1), min.2.max(max.2)),
+        );
+        let size = Vec3(
+            (max.0 - min.0 + 1) as usize,
+            (max.1 - min.1 + 1) as usize,
+            (max.2 - min.2 + 1) as usize,
+        );
+
+        let mut voxels = Vec::with_capacity(size.0 * size.1 * size.2);
+
+        for vx in min.0..=max.0 {
+            for vy in min.1..=max.1 {
+                for vz in min.2..=max.2 {
+                    voxels.push(self.get_raw_voxel(vx, vy, vz));
+                }
+            }
+        }
+
+        let mut palette: Vec<(u32, String)> = voxels
+            .iter()
+            .map(|&voxel| BlockUtils::extract_id(voxel))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|id| {
+                registry
+                    .blocks_by_id
+                    .get(&id)
+                    .map(|block| (id, block.name.to_owned()))
+            })
+            .collect();
+        palette.sort();
+
+        Schematic {
+            version: SCHEMATIC_VERSION,
+            size,
+            voxels,
+            palette,
+            block_entities: vec![],
+        }
+    }
+
+    /// Paste a schematic with its minimum corner at `origin`. Voxels are queued as regular voxel
+    /// updates so that lights and meshes are kept up to date, which means the chunks pasted into
+    /// need to be loaded. Blocks the registry does not know are skipped. Returns the number of
+    /// voxel updates queued.
+    pub fn paste_schematic(
+        &mut self,
+        schematic: &Schematic,
+        origin: &Vec3<i32>,
+        options: &PasteOptions,
+        registry: &Registry,
+    ) -> usize {
+        let updates =
+            schematic.voxel_updates(origin, options, registry, self.config.voxel_encoding);
+
+        for block_entity in &schematic.block_entities {
+            let Vec3(px, py, pz) = options.transform_voxel(&block_entity.voxel, &schematic.size);
+
+            self.block_entity_data.insert(
+                Vec3(origin.0 + px, origin.1 + py, origin.2 + pz),
+                block_entity.json.to_owned(),
+            );
+        }
+
+        self.update_voxels(&updates);
+
+        updates.len()
+    }
+}
@@ -9,6 +9,9 @@ use super::{
     chunks::Chunks,
 };
 
+/// The lower 32 bits of a chunk's voxels, and the upper 32 bits with `VoxelEncoding::Wide`.
+type SpaceVoxels = (Ndarray<u32>, Option<Ndarray<u32>>);
+
 /// What kind of data does this space have/need?
 #[derive(Default)]
 pub struct SpaceData {
@@ -61,8 +64,8 @@ pub struct Space {
     /// A set of sub-chunks that have been updated.
     pub updated_levels: HashSet<u32>,
 
-    /// A map of voxels, chunk coordinates -> n-dims array of voxels.
-    voxels: HashMap<Vec2<i32>, Ndarray<u32>>,
+    /// A map of voxels, chunk coordinates -> n-dims arrays of voxels.
+    voxels: HashMap<Vec2<i32>, SpaceVoxels>,
 
     /// A map of lights, chunk coordinates -> n-dims array of lights.
     lights: HashMap<Vec2<i32>, Ndarray<u32>>,
@@ -157,15 +160,21 @@ impl SpaceBuilder<'_> {
 
                 if let Some(chunk) = self.chunks.raw(&n_coords) {
                     let voxels = if self.needs_voxels {
-                        Some((n_coords.clone(), chunk.voxels.clone()))
+                        Some((
+                            n_coords.clone(),
+                            (
+                                chunk.voxels.to_ndarray(),
+                                chunk.extras.as_ref().map(|extras| extras.to_ndarray()),
+                            ),
+                        ))
                     } else {
                         None
                     };
 
                     let lights = if self.needs_lights {
-                        Some((n_coords.clone(), chunk.lights.clone()))
+                        Some((n_coords.clone(), chunk.lights.to_ndarray()))
                     } else {
-                        Some((n_coords.clone(), ndarray(&chunk.lights.shape, 0)))
+                        Some((n_coords.clone(), ndarray(chunk.lights.shape(), 0)))
                     };
 
                     let height_maps = if self.needs_height_maps {
@@ -236,19 +245,21 @@ impl SpaceBuilder<'_> {
 impl VoxelAccess for Space {
     /// Get the raw voxel data at the voxel position. Zero is returned if chunk doesn't exist.
     /// Panics if space does not contain voxel data.
-    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+    fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u64 {
         if self.voxels.is_empty() {
             panic!("Space does not contain voxel data.");
         }
 
         let (coords, Vec3(lx, ly, lz)) = self.to_local(vx, vy, vz);
 
-        if let Some(voxels) = self.voxels.get(&coords) {
+        if let Some((voxels, extras)) = self.voxels.get(&coords) {
             if !voxels.contains(&[lx, ly, lz]) {
                 return 0;
             }
 
-            return voxels[&[lx, ly, lz]];
+            let upper = extras.as_ref().map_or(0, |extras| extras[&[lx, ly, lz]]);
+
+            return BlockUtils::join(voxels[&[lx, ly, lz]], upper);
         }
 
         0
@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+/// Most states a block can have, as the state ID of a voxel is kept in 8 bits with
+/// `VoxelEncoding::Wide`. `VoxelEncoding::Packed` voxels only hold the lower 4 of them.
+pub const MAX_BLOCK_STATES: usize = 256;
+
+/// A value of a block property, written as a plain boolean, number or string in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i32),
+    Name(String),
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for PropertyValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        Self::Name(value.to_owned())
+    }
+}
+
+/// The value of each property of a block, property name -> value.
+pub type BlockState = BTreeMap<String, PropertyValue>;
+
+/// A named property of a block with a finite set of values, such as whether a door is open. The
+/// first value is the default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockProperty {
+    /// Name of the property.
+    pub name: String,
+
+    /// Every value the property can take.
+    pub values: Vec<PropertyValue>,
+}
+
+impl BlockProperty {
+    /// A property that is either `false` or `true`, `false` by default.
+    pub fn boolean(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            values: vec![false.into(), true.into()],
+        }
+    }
+
+    /// A property that takes the numbers within a range, the lowest by default.
+    pub fn int(name: &str, range: RangeInclusive<i32>) -> Self {
+        Self {
+            name: name.to_owned(),
+            values: range.map(PropertyValue::from).collect(),
+        }
+    }
+
+    /// A property that takes one of a set of names, the first by default.
+    pub fn named(name: &str, values: &[&str]) -> Self {
+        Self {
+            name: name.to_owned(),
+            values: values.iter().map(|&value| value.into()).collect(),
+        }
+    }
+
+    /// Every combination of values of a set of properties, indexed by state ID. The first property
+    /// changes the fastest, so state 0 has the default of every property.
+    pub fn combine(properties: &[BlockProperty]) -> Vec<BlockState> {
+        properties
+            .iter()
+            .fold(vec![BlockState::new()], |states, property| {
+                property
+                    .values
+                    .iter()
+                    .flat_map(|value| {
+                        states.iter().map(move |state| {
+                            let mut state = state.to_owned();
+                            state.insert(property.name.to_owned(), value.to_owned());
+                            state
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+    }
+}
@@ -0,0 +1,181 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::{ChunkUtils, Vec2, Vec3};
+
+/// Stale heap entries allowed on top of the live ones before the heap is rebuilt.
+const COMPACT_THRESHOLD: usize = 1024;
+
+/// Block ticks scheduled for later, kept in a time-ordered queue with a bucket of voxels per
+/// chunk. Each voxel has at most one pending tick, and scheduling it again moves that tick.
+#[derive(Debug, Default, Clone)]
+pub struct ScheduledTicks {
+    /// Earliest tick first. Entries whose voxel has since been moved or cancelled are skipped.
+    queue: BinaryHeap<Reverse<(u64, [i32; 3])>>,
+
+    /// The tick each voxel is scheduled for, voxel -> tick.
+    scheduled: HashMap<Vec3<i32>, u64>,
+
+    /// The voxels with a pending tick in each chunk, coords -> voxels.
+    chunks: HashMap<Vec2<i32>, HashSet<Vec3<i32>>>,
+
+    /// The last tick that ticks were taken at.
+    current: u64,
+
+    chunk_size: usize,
+}
+
+impl ScheduledTicks {
+    /// Create an empty queue of scheduled ticks for chunks of a certain size.
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            ..Default::default()
+        }
+    }
+
+    /// Number of voxels with a pending tick.
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    /// Whether no ticks are pending.
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    /// The last tick that ticks were taken at.
+    pub fn current_tick(&self) -> u64 {
+        self.current
+    }
+
+    /// The tick a voxel is scheduled for, if any.
+    pub fn get(&self, voxel: &Vec3<i32>) -> Option<u64> {
+        self.scheduled.get(voxel).copied()
+    }
+
+    /// Schedule a voxel to tick at a certain tick, replacing its pending tick if it has one.
+    pub fn schedule(&mut self, voxel: &Vec3<i32>, at: u64) {
+        if self.scheduled.insert(voxel.to_owned(), at) == Some(at) {
+            return;
+        }
+
+        self.chunks
+            .entry(self.chunk_of(voxel))
+            .or_default()
+            .insert(voxel.to_owned());
+        self.queue.push(Reverse((at, voxel.to_arr())));
+
+        self.compact();
+    }
+
+    /// Cancel the pending tick of a voxel. Returns the tick it was scheduled for.
+    pub fn cancel(&mut self, voxel: &Vec3<i32>) -> Option<u64> {
+        let at = self.scheduled.remove(voxel)?;
+        self.forget_in_chunk(voxel);
+        self.compact();
+
+        Some(at)
+    }
+
+    /// Take the voxels that are due at `tick`, earliest first.
+    pub fn take_due(&mut self, tick: u64) -> Vec<Vec3<i32>> {
+        self.current = self.current.max(tick);
+
+        let mut due = vec![];
+
+        while let Some(Reverse((at, voxel))) = self.queue.peek() {
+            if *at > tick {
+                break;
+            }
+
+            let (at, voxel) = (*at, Vec3(voxel[0], voxel[1], voxel[2]));
+            self.queue.pop();
+
+            if self.scheduled.get(&voxel) == Some(&at) {
+                self.scheduled.remove(&voxel);
+                self.forget_in_chunk(&voxel);
+                due.push(voxel);
+            }
+        }
+
+        due
+    }
+
+    /// Whether a chunk has any pending ticks.
+    pub fn has_chunk(&self, coords: &Vec2<i32>) -> bool {
+        self.chunks.contains_key(coords)
+    }
+
+    /// The pending ticks of a chunk, as each voxel and the number of ticks it has left.
+    pub fn chunk_ticks(&self, coords: &Vec2<i32>) -> Vec<(Vec3<i32>, u64)> {
+        let mut ticks: Vec<(Vec3<i32>, u64)> = self
+            .chunks
+            .get(coords)
+            .map(|voxels| {
+                voxels
+                    .iter()
+                    .map(|voxel| {
+                        let at = self.scheduled[voxel];
+                        (voxel.to_owned(), at.saturating_sub(self.current))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ticks.sort_by_key(|(voxel, delay)| (*delay, voxel.to_arr()));
+        ticks
+    }
+
+    /// Remove and return the pending ticks of a chunk, see `chunk_ticks`.
+    pub fn remove_chunk(&mut self, coords: &Vec2<i32>) -> Vec<(Vec3<i32>, u64)> {
+        let ticks = self.chunk_ticks(coords);
+
+        if let Some(voxels) = self.chunks.remove(coords) {
+            for voxel in voxels {
+                self.scheduled.remove(&voxel);
+            }
+
+            self.compact();
+        }
+
+        ticks
+    }
+
+    /// Schedule ticks given as each voxel and the number of ticks it has left, see `chunk_ticks`.
+    pub fn restore(&mut self, ticks: &[(Vec3<i32>, u64)]) {
+        for (voxel, delay) in ticks {
+            self.schedule(voxel, self.current + delay);
+        }
+    }
+
+    fn chunk_of(&self, voxel: &Vec3<i32>) -> Vec2<i32> {
+        ChunkUtils::map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, self.chunk_size)
+    }
+
+    fn forget_in_chunk(&mut self, voxel: &Vec3<i32>) {
+        let coords = self.chunk_of(voxel);
+
+        if let Some(voxels) = self.chunks.get_mut(&coords) {
+            voxels.remove(voxel);
+
+            if voxels.is_empty() {
+                self.chunks.remove(&coords);
+            }
+        }
+    }
+
+    /// Drop the heap entries of moved or cancelled ticks once there are too many of them.
+    fn compact(&mut self) {
+        if self.queue.len() <= self.scheduled.len() * 2 + COMPACT_THRESHOLD {
+            return;
+        }
+
+        self.queue = self
+            .scheduled
+            .iter()
+            .map(|(voxel, at)| Reverse((*at, voxel.to_arr())))
+            .collect();
+    }
+}
@@ -0,0 +1,234 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use voxelize::{AtlasImage, Block, BlockFaces, Registry, TextureAtlas, UV};
+
+    use crate::common::temp_save_dir;
+
+    /// An image with a different color in every pixel.
+    fn gradient(width: u32, height: u32, blue: u8) -> AtlasImage {
+        let mut image = AtlasImage::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                image.set_pixel(x, y, [x as u8, y as u8, blue, 255]);
+            }
+        }
+
+        image
+    }
+
+    /// Top left pixel of a range on a page.
+    fn origin(range: &UV, page: &AtlasImage) -> (u32, u32) {
+        (
+            (range.start_u * page.width as f32).round() as u32,
+            ((1.0 - range.end_v) * page.height as f32).round() as u32,
+        )
+    }
+
+    #[test]
+    fn textures_are_packed_with_gutters() {
+        let atlas = TextureAtlas::new()
+            .gutter(2)
+            .mip_levels(1)
+            .texture("Stone::px", &gradient(16, 16, 1))
+            .texture("Stone::py", &gradient(8, 8, 2))
+            .texture("Glass::pz", &gradient(32, 16, 3))
+            .build()
+            .unwrap();
+
+        assert_eq!(atlas.pages.len(), 1);
+        let page = &atlas.pages[0];
+
+        let mut rects = vec![];
+
+        for (name, blue, width, height) in [
+            ("stone::px", 1, 16, 16),
+            ("stone::py", 2, 8, 8),
+            ("glass::pz", 3, 32, 16),
+        ] {
+            let entry = &atlas.textures[name];
+            let (x, y) = origin(&entry.range, page);
+
+            assert_eq!(x % 2, 0);
+            assert_eq!(
+                (entry.range.end_u - entry.range.start_u) * page.width as f32,
+                width as f32
+            );
+            assert_eq!(page.get_pixel(x, y), [0, 0, blue, 255]);
+            assert_eq!(
+                page.get_pixel(x + width - 1, y + height - 1),
+                [width as u8 - 1, height as u8 - 1, blue, 255]
+            );
+
+            // The gutter repeats the edges of the texture.
+            assert_eq!(page.get_pixel(x - 2, y - 2), [0, 0, blue, 255]);
+            assert_eq!(
+                page.get_pixel(x + width, y),
+                [width as u8 - 1, 0, blue, 255]
+            );
+
+            rects.push((x, y, width, height));
+        }
+
+        for (i, a) in rects.iter().enumerate() {
+            for b in &rects[i + 1..] {
+                let apart = a.0 + a.2 + 2 <= b.0
+                    || b.0 + b.2 + 2 <= a.0
+                    || a.1 + a.3 + 2 <= b.1
+                    || b.1 + b.3 + 2 <= a.1;
+                assert!(apart, "{:?} and {:?} overlap", a, b);
+            }
+        }
+
+        assert_eq!(atlas.get("STONE", "PX"), &atlas.textures["stone::px"]);
+        assert_eq!(atlas.get("Stone", "nx"), &atlas.unknown);
+    }
+
+    #[test]
+    fn strips_are_animated() {
+        let mut strip = AtlasImage::new(4, 12);
+        for frame in 0..3 {
+            for y in 0..4 {
+                for x in 0..4 {
+                    strip.set_pixel(x, frame * 4 + y, [frame as u8, 0, 0, 255]);
+                }
+            }
+        }
+
+        let atlas = TextureAtlas::new()
+            .frame_interval(50)
+            .texture("Water::py", &strip)
+            .texture("Water::px", &gradient(4, 6, 0))
+            .build()
+            .unwrap();
+
+        let water = &atlas.textures["water::py"];
+        assert_eq!(water.frames.len(), 3);
+        assert_eq!(water.range, water.frames[0]);
+        assert_eq!(water.interval, 50);
+
+        let page = &atlas.pages[water.page];
+        for (frame, range) in water.frames.iter().enumerate() {
+            let (x, y) = origin(range, page);
+            assert_eq!(page.get_pixel(x, y), [frame as u8, 0, 0, 255]);
+            assert_eq!(page.get_pixel(x + 3, y + 3), [frame as u8, 0, 0, 255]);
+        }
+
+        // Only whole multiples of the width are strips.
+        assert!(atlas.textures["water::px"].frames.is_empty());
+    }
+
+    #[test]
+    fn textures_overflow_onto_more_pages() {
+        let mut builder = TextureAtlas::new().max_size(64).gutter(4).mip_levels(2);
+        for i in 0..12 {
+            builder = builder.texture(&format!("Block{}::px", i), &gradient(16, 16, i));
+        }
+        let atlas = builder.build().unwrap();
+
+        assert!(atlas.pages.len() > 1);
+        assert!(atlas.pages.iter().all(|page| page.width <= 64));
+        assert!(atlas.textures.values().any(|entry| entry.page > 0));
+
+        // Block faces know which page their range is on.
+        let mut registry = Registry::new();
+        for i in 0..12 {
+            registry.register_block(
+                &Block::new(&format!("Block{}", i))
+                    .id(i + 1)
+                    .faces(&BlockFaces::six_faces().build().to_vec())
+                    .build(),
+            );
+        }
+        registry.set_atlas(&atlas);
+        registry.generate();
+
+        for i in 0..12 {
+            let name = format!("Block{}", i);
+            let block = registry.get_block_by_name(&name);
+
+            for face in &block.faces {
+                let entry = atlas.get(&name, &face.name);
+                assert_eq!((&face.range, face.page), (&entry.range, entry.page));
+            }
+        }
+
+        assert!(TextureAtlas::new()
+            .max_size(16)
+            .texture("Big::px", &gradient(16, 16, 0))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn textures_are_read_from_a_folder() {
+        let folder = temp_save_dir("atlas-folder");
+
+        fs::write(
+            folder.join("Dirt::py.png"),
+            gradient(8, 8, 7).encode().unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            folder.join("readme.png"),
+            gradient(8, 8, 0).encode().unwrap(),
+        )
+        .unwrap();
+        fs::write(folder.join("notes.txt"), "not a texture").unwrap();
+
+        let atlas = TextureAtlas::new().dir(&folder).build().unwrap();
+        assert_eq!(atlas.textures.len(), 1);
+
+        let entry = &atlas.textures["dirt::py"];
+        let (x, y) = origin(&entry.range, &atlas.pages[0]);
+        assert_eq!(atlas.pages[0].get_pixel(x + 5, y + 3), [5, 3, 7, 255]);
+
+        let page = AtlasImage::decode(&atlas.pages[0].encode().unwrap()).unwrap();
+        assert_eq!(page, atlas.pages[0]);
+
+        fs::write(folder.join("Broken::px.png"), "not a png").unwrap();
+        assert!(TextureAtlas::new().dir(&folder).build().is_err());
+    }
+
+    #[test]
+    fn registry_takes_face_ranges_from_the_atlas() {
+        let atlas = TextureAtlas::new()
+            .texture("Stone::py", &gradient(16, 16, 0))
+            .build()
+            .unwrap();
+
+        let mut registry = Registry::new();
+        registry.register_block(
+            &Block::new("Stone")
+                .id(1)
+                .faces(&BlockFaces::six_faces().build().to_vec())
+                .build(),
+        );
+        registry.set_atlas(&atlas);
+        registry.generate();
+
+        let stone = registry.get_block_by_name("stone");
+        for face in &stone.faces {
+            let expected = if face.name == "py" {
+                &atlas.textures["stone::py"].range
+            } else {
+                &atlas.unknown.range
+            };
+
+            assert_eq!(&face.range, expected);
+        }
+
+        // Reloading generates the ranges again from the same atlas.
+        registry.reload().unwrap();
+        assert_eq!(
+            registry.get_block_by_id(1).faces[2].range,
+            atlas
+                .get("stone", &registry.get_block_by_id(1).faces[2].name)
+                .range
+        );
+    }
+}
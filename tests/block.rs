@@ -15,6 +15,20 @@ mod tests {
         assert_eq!(BlockUtils::extract_id(voxel), 1);
     }
 
+    #[test]
+    fn wide_id_insertion() {
+        let mut voxel = 100230120;
+
+        voxel = BlockUtils::insert_wide_id(voxel, 65537);
+        assert_eq!(BlockUtils::extract_id(voxel), 65537);
+
+        // The packed half keeps the lower 16 bits.
+        assert_eq!(BlockUtils::extract_id(BlockUtils::split(voxel).0 as u64), 1);
+
+        voxel = BlockUtils::insert_id(voxel, 13);
+        assert_eq!(BlockUtils::extract_id(voxel), 13);
+    }
+
     #[test]
     fn rotation_insertion() {
         // let mut voxel = 0;
@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use specs::RunNow;
+use voxelize::{
+    Chunk, ChunkOptions, ChunkStatus, ChunkUpdatingSystem, Registry, Vec2, World, WorldConfig,
+};
+
+/// Create a world with a registry and a 3×3 grid of empty, ready chunks around the origin.
+pub fn setup_world(config: &WorldConfig, registry: Registry) -> World {
+    let mut world = World::new("test", config);
+    world.ecs_mut().insert(registry);
+
+    let options = ChunkOptions {
+        size: config.chunk_size,
+        max_height: config.max_height,
+        sub_chunks: config.sub_chunks,
+        paletted: config.paletted_chunks,
+        encoding: config.voxel_encoding,
+    };
+
+    for cx in -1..=1 {
+        for cz in -1..=1 {
+            let mut chunk = Chunk::new("test", cx, cz, &options);
+            chunk.status = ChunkStatus::Ready;
+            world.chunks_mut().map.insert(Vec2(cx, cz), chunk);
+        }
+    }
+
+    world
+}
+
+/// Process the queued voxel updates once.
+pub fn tick(world: &mut World) {
+    ChunkUpdatingSystem.run_now(world.ecs());
+
+    // Nothing meshes in tests, so the chunks are taken out of the mesher by hand.
+    for cx in -1..=1 {
+        for cz in -1..=1 {
+            world.mesher_mut().remove_chunk(&Vec2(cx, cz));
+        }
+    }
+}
+
+/// An empty folder under the system temp folder to save test worlds in, removed when dropped.
+pub struct TempSaveDir(PathBuf);
+
+impl Deref for TempSaveDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempSaveDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempSaveDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Create an empty save folder for a test, named so that tests running in parallel don't collide.
+pub fn temp_save_dir(name: &str) -> TempSaveDir {
+    let mut folder = std::env::temp_dir();
+    folder.push(format!("voxelize-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    TempSaveDir(folder)
+}
@@ -0,0 +1,159 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use specs::RunNow;
+    use voxelize::{
+        Block, Edits, EditsSystem, PasteOptions, Registry, Vec3, VoxelAccess, World, WorldConfig,
+    };
+
+    use crate::common::setup_world;
+
+    fn tick(world: &mut World) {
+        EditsSystem.run_now(world.ecs());
+        crate::common::tick(world);
+    }
+
+    fn count(world: &World, min: Vec3<i32>, max: Vec3<i32>, id: u32) -> usize {
+        let chunks = world.chunks();
+        let mut count = 0;
+
+        for vx in min.0..=max.0 {
+            for vy in min.1..=max.1 {
+                for vz in min.2..=max.2 {
+                    if chunks.get_voxel(vx, vy, vz) == id {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    #[test]
+    fn edits_are_throttled_and_can_be_undone() {
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .max_updates_per_tick(20)
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Dirt").id(2).build(),
+        ]);
+
+        let mut world = setup_world(&config, registry);
+
+        let fill = world
+            .edit("builder")
+            .fill(&Vec3(4, 4, 4), &Vec3(2, 2, 2), 1);
+
+        // 27 voxels take two ticks with 20 updates per tick.
+        tick(&mut world);
+        assert_eq!(
+            world.read_resource::<Edits>().progress(fill),
+            Some((20, 27))
+        );
+        tick(&mut world);
+        assert_eq!(world.read_resource::<Edits>().progress(fill), None);
+        tick(&mut world);
+        assert_eq!(count(&world, Vec3(2, 2, 2), Vec3(4, 4, 4), 1), 27);
+
+        world
+            .edit("builder")
+            .replace(&Vec3(2, 2, 2), &Vec3(4, 2, 4), |raw| raw == 1, 2);
+        for _ in 0..3 {
+            tick(&mut world);
+        }
+        assert_eq!(count(&world, Vec3(2, 2, 2), Vec3(4, 4, 4), 2), 9);
+
+        // Undoing twice puts back the world from before the fill, and redoing brings back the fill.
+        world.edit("builder").undo().unwrap();
+        world.edit("builder").undo().unwrap();
+        assert!(world.edit("builder").undo().is_none());
+        for _ in 0..5 {
+            tick(&mut world);
+        }
+        assert_eq!(count(&world, Vec3(2, 2, 2), Vec3(4, 4, 4), 0), 27);
+
+        world.edit("builder").redo().unwrap();
+        for _ in 0..3 {
+            tick(&mut world);
+        }
+        assert_eq!(count(&world, Vec3(2, 2, 2), Vec3(4, 4, 4), 1), 27);
+
+        // Other clients have their own history.
+        assert!(world.edit("someone").undo().is_none());
+
+        world.edit("builder").sphere(&Vec3(8, 10, 8), 2.0, 1);
+        world.edit("builder").cylinder(&Vec3(12, 1, 12), 1.0, 3, 2);
+        world
+            .edit("builder")
+            .hollow_box(&Vec3(12, 10, 2), &Vec3(14, 12, 4), 1);
+        for _ in 0..10 {
+            tick(&mut world);
+        }
+        assert_eq!(count(&world, Vec3(6, 8, 6), Vec3(10, 12, 10), 1), 33);
+        assert_eq!(count(&world, Vec3(11, 0, 11), Vec3(13, 5, 13), 2), 15);
+        assert_eq!(count(&world, Vec3(12, 10, 2), Vec3(14, 12, 4), 1), 26);
+
+        // Copy the filled box, paste it somewhere else and stack it upwards.
+        assert_eq!(
+            world.edit("builder").copy(&Vec3(2, 2, 2), &Vec3(4, 4, 4)),
+            Vec3(3, 3, 3)
+        );
+        assert!(world
+            .edit("someone")
+            .paste(&Vec3(0, 0, 0), &PasteOptions::default())
+            .is_none());
+        world
+            .edit("builder")
+            .paste(&Vec3(6, 2, 12), &PasteOptions::default())
+            .unwrap();
+        world
+            .edit("builder")
+            .stack(&Vec3(2, 2, 2), &Vec3(4, 4, 4), &Vec3(0, 1, 0), 2);
+        for _ in 0..10 {
+            tick(&mut world);
+        }
+        assert_eq!(count(&world, Vec3(6, 2, 12), Vec3(8, 4, 14), 1), 27);
+        assert_eq!(count(&world, Vec3(2, 2, 2), Vec3(4, 10, 4), 1), 81);
+    }
+
+    #[test]
+    fn voxels_outside_the_world_are_not_counted_as_applied() {
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .max_updates_per_tick(6)
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stone").id(1).build()]);
+
+        let mut world = setup_world(&config, registry);
+
+        // The chunk at x = 1000 is not loaded.
+        let far = world
+            .edit("builder")
+            .fill(&Vec3(1000, 0, 0), &Vec3(1009, 0, 0), 1);
+
+        tick(&mut world);
+        assert_eq!(world.read_resource::<Edits>().progress(far), Some((0, 10)));
+        tick(&mut world);
+        assert_eq!(world.read_resource::<Edits>().progress(far), None);
+
+        // Half of the fill is above the world.
+        let fill = world
+            .edit("builder")
+            .fill(&Vec3(0, 30, 0), &Vec3(0, 33, 0), 1);
+
+        tick(&mut world);
+        assert_eq!(world.read_resource::<Edits>().progress(fill), None);
+        tick(&mut world);
+        assert_eq!(count(&world, Vec3(0, 30, 0), Vec3(0, 31, 0), 1), 2);
+    }
+}
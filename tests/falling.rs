@@ -0,0 +1,66 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use specs::{Join, RunNow, WorldExt};
+    use voxelize::{
+        Block, FallingBlockComp, FallingBlocksSystem, PhysicsSystem, Registry, Vec3, VoxelAccess,
+        World, WorldConfig,
+    };
+
+    use crate::common::setup_world;
+
+    fn tick(world: &mut World) {
+        crate::common::tick(world);
+        PhysicsSystem.run_now(world.ecs());
+        FallingBlocksSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+    }
+
+    fn falling_blocks(world: &World) -> Vec<u64> {
+        world
+            .ecs()
+            .read_storage::<FallingBlockComp>()
+            .join()
+            .map(|falling_block| falling_block.0)
+            .collect()
+    }
+
+    #[test]
+    fn gravity_blocks_fall_and_land() {
+        let config = WorldConfig::new().max_height(32).sub_chunks(2).build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Sand").id(2).gravity(true).build(),
+        ]);
+
+        let mut world = setup_world(&config, registry);
+        world.stats_mut().delta = 1.0 / 20.0;
+
+        {
+            let mut chunks = world.chunks_mut();
+            chunks.set_raw_voxel(8, 0, 8, 1);
+            chunks.set_raw_voxel(8, 3, 8, 1);
+            chunks.set_raw_voxel(8, 4, 8, 2);
+            chunks.set_raw_voxel(8, 5, 8, 2);
+            chunks.update_voxels(&[(Vec3(8, 3, 8), 0)]);
+        }
+
+        // Both sand blocks lose their support at once.
+        tick(&mut world);
+        assert_eq!(world.chunks().get_voxel(8, 4, 8), 0);
+        assert_eq!(world.chunks().get_voxel(8, 5, 8), 0);
+        assert_eq!(falling_blocks(&world), vec![2, 2]);
+
+        for _ in 0..100 {
+            tick(&mut world);
+        }
+
+        assert!(falling_blocks(&world).is_empty());
+        assert_eq!(world.chunks().get_voxel(8, 1, 8), 2);
+        assert_eq!(world.chunks().get_voxel(8, 2, 8), 2);
+        assert_eq!(world.chunks().get_voxel(8, 3, 8), 0);
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use voxelize::{
+        Block, BlockUtils, Chunk, ChunkOptions, FluidUtils, Registry, Vec3, VoxelAccess,
+        VoxelEncoding,
+    };
+
+    /// Flow every fluid voxel at once until nothing changes, returning the number of steps taken.
+    fn settle(chunk: &mut Chunk, registry: &Registry) -> usize {
+        for step in 0..100 {
+            let mut updates = vec![];
+
+            for vx in 0..16 {
+                for vy in 0..16 {
+                    for vz in 0..16 {
+                        updates.extend(FluidUtils::flow(&Vec3(vx, vy, vz), &*chunk, registry));
+                    }
+                }
+            }
+
+            if updates.is_empty() {
+                return step;
+            }
+
+            for (Vec3(vx, vy, vz), raw) in updates {
+                chunk.set_raw_voxel(vx, vy, vz, raw);
+            }
+        }
+
+        panic!("Fluid never settled.");
+    }
+
+    #[test]
+    fn fluids_fall_spread_and_dry_up() {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Water")
+                .id(2)
+                .is_fluid(true)
+                .fluid_range(3)
+                .fluid_regenerates(true)
+                .build(),
+            Block::new("Oil")
+                .id(3)
+                .is_fluid(true)
+                .fluid_flow_rate(0)
+                .build(),
+        ]);
+
+        let mut chunk = Chunk::new(
+            "test",
+            0,
+            0,
+            &ChunkOptions {
+                size: 16,
+                max_height: 16,
+                sub_chunks: 1,
+                paletted: false,
+                encoding: VoxelEncoding::Packed,
+            },
+        );
+
+        for vx in 0..16 {
+            for vz in 0..16 {
+                chunk.set_voxel(vx, 0, vz, 1);
+            }
+        }
+
+        chunk.set_voxel(8, 5, 8, 2);
+        chunk.set_voxel(1, 1, 1, 3);
+        settle(&mut chunk, &registry);
+
+        let water = |chunk: &Chunk, vx, vy, vz| {
+            let raw = chunk.get_raw_voxel(vx, vy, vz);
+            (BlockUtils::extract_id(raw) == 2).then(|| raw)
+        };
+
+        assert!(FluidUtils::is_falling(water(&chunk, 8, 3, 8).unwrap()));
+        assert!(FluidUtils::is_falling(water(&chunk, 8, 1, 8).unwrap()));
+        assert_eq!(
+            FluidUtils::extract_level(water(&chunk, 9, 1, 8).unwrap()),
+            1
+        );
+        assert_eq!(
+            FluidUtils::extract_level(water(&chunk, 11, 1, 8).unwrap()),
+            3
+        );
+        assert_eq!(
+            FluidUtils::extract_level(water(&chunk, 9, 1, 10).unwrap()),
+            3
+        );
+        assert!(water(&chunk, 12, 1, 8).is_none());
+        assert!(water(&chunk, 9, 2, 8).is_none());
+
+        // Still fluids stay put.
+        assert_eq!(chunk.get_voxel(1, 1, 1), 3);
+        assert_eq!(chunk.get_voxel(2, 1, 1), 0);
+
+        // Without its source, the water dries up.
+        chunk.set_voxel(8, 5, 8, 0);
+        settle(&mut chunk, &registry);
+        assert!((0..16).all(|vx| (1..16).all(|vy| water(&chunk, vx, vy, 8).is_none())));
+
+        // Flowing water between two sources becomes a source.
+        chunk.set_voxel(4, 1, 12, 2);
+        chunk.set_voxel(6, 1, 12, 2);
+        settle(&mut chunk, &registry);
+        assert!(FluidUtils::is_source(water(&chunk, 5, 1, 12).unwrap()));
+        assert!(!FluidUtils::is_source(water(&chunk, 7, 1, 12).unwrap()));
+    }
+}
@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use specs::RunNow;
+    use voxelize::{
+        Block, EditsSystem, History, RegionArea, Registry, Vec2, Vec3, VoxelAccess, World,
+        WorldConfig, SYSTEM_SOURCE,
+    };
+
+    use crate::common::{setup_world, temp_save_dir};
+
+    fn tick(world: &mut World) {
+        EditsSystem.run_now(world.ecs());
+        crate::common::tick(world);
+    }
+
+    fn sources(history: &History, voxel: Vec3<i32>) -> Vec<String> {
+        history
+            .at(&voxel)
+            .into_iter()
+            .map(|entry| entry.source)
+            .collect()
+    }
+
+    #[test]
+    fn changes_are_recorded_and_rolled_back() {
+        let folder = temp_save_dir("history");
+
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .saving(true)
+            .save_dir(folder.to_str().unwrap())
+            .record_history(true)
+            .build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Dirt").id(2).build(),
+        ]);
+
+        let mut world = setup_world(&config, registry);
+
+        world
+            .edit("builder")
+            .fill(&Vec3(1, 2, 1), &Vec3(3, 2, 1), 1);
+        tick(&mut world);
+
+        world
+            .chunks_mut()
+            .update_voxels_as("griefer", &[(Vec3(1, 2, 1), 2), (Vec3(2, 2, 1), 2)]);
+        world.chunks_mut().update_voxels(&[(Vec3(5, 2, 5), 1)]);
+        tick(&mut world);
+
+        world
+            .chunks_mut()
+            .update_voxels_as("griefer", &[(Vec3(1, 2, 1), 0)]);
+        tick(&mut world);
+
+        {
+            let history = world.history();
+
+            assert_eq!(
+                sources(&history, Vec3(1, 2, 1)),
+                ["builder", "griefer", "griefer"]
+            );
+            assert_eq!(sources(&history, Vec3(5, 2, 5)), [SYSTEM_SOURCE]);
+            assert_eq!(history.by_source("griefer", UNIX_EPOCH).len(), 3);
+
+            let last = history.at(&Vec3(1, 2, 1)).pop().unwrap();
+            assert_eq!((last.old, last.new), (2, 0));
+        }
+
+        // The log is on disk, so a new history reads the same changes.
+        let reloaded = History::new(
+            true,
+            &config.history_dir,
+            config.chunk_size,
+            config.max_memory_history,
+        );
+        assert_eq!(reloaded.at(&Vec3(1, 2, 1)).len(), 3);
+
+        let area = RegionArea::Box {
+            min: Vec3(0, 0, 0),
+            max: Vec3(4, 4, 4),
+        };
+
+        assert!(world
+            .rollback("builder", &area, SystemTime::now())
+            .is_none());
+        assert!(world.rollback("griefer", &area, UNIX_EPOCH).is_some());
+        tick(&mut world);
+
+        {
+            let chunks = world.chunks();
+            assert_eq!(chunks.get_voxel(1, 2, 1), 1);
+            assert_eq!(chunks.get_voxel(2, 2, 1), 1);
+            assert_eq!(chunks.get_voxel(3, 2, 1), 1);
+        }
+
+        assert_eq!(
+            sources(&world.history(), Vec3(1, 2, 1)).last().unwrap(),
+            SYSTEM_SOURCE
+        );
+    }
+
+    #[test]
+    fn history_in_memory_keeps_the_latest_changes() {
+        let mut history = History::new(true, "", 16, 2);
+
+        for new in 1..=3 {
+            history.record("builder", new, &Vec3(1, 2, 1), new - 1, new);
+            history.flush();
+        }
+
+        let entries = history.chunk(&Vec2(0, 0));
+        assert_eq!(
+            entries.iter().map(|entry| entry.new).collect::<Vec<_>>(),
+            [2, 3]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use voxelize::{Block, BlockUtils, Registry, Vec3, VoxelAccess, WorldConfig};
+
+    use crate::common::{setup_world, tick};
+
+    #[test]
+    fn neighbor_changes_notify_blocks_with_loop_protection() {
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .max_neighbor_update_depth(3)
+            .build();
+
+        let flips = Arc::new(AtomicUsize::new(0));
+        let (ping_flips, pong_flips) = (flips.clone(), flips.clone());
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Torch")
+                .id(2)
+                .is_transparent(true)
+                .on_neighbor_changed(|change, _, _| {
+                    if change.offset == Vec3(0, -1, 0) && BlockUtils::extract_id(change.new) == 0 {
+                        vec![(change.voxel.clone(), 0)]
+                    } else {
+                        vec![]
+                    }
+                })
+                .build(),
+            Block::new("Ping")
+                .id(3)
+                .on_neighbor_changed(move |change, _, _| {
+                    ping_flips.fetch_add(1, Ordering::SeqCst);
+                    vec![(change.voxel.clone(), 4)]
+                })
+                .build(),
+            Block::new("Pong")
+                .id(4)
+                .on_neighbor_changed(move |change, _, _| {
+                    pong_flips.fetch_add(1, Ordering::SeqCst);
+                    vec![(change.voxel.clone(), 3)]
+                })
+                .build(),
+        ]);
+        let mut world = setup_world(&config, registry);
+
+        {
+            let mut chunks = world.chunks_mut();
+            chunks.set_raw_voxel(8, 1, 8, 1);
+            chunks.set_raw_voxel(8, 2, 8, 2);
+            chunks.set_raw_voxel(2, 1, 2, 3);
+            chunks.update_voxels(&[(Vec3(8, 1, 8), 0), (Vec3(3, 1, 2), 3)]);
+        }
+
+        tick(&mut world);
+        assert_eq!(world.chunks().get_voxel(8, 1, 8), 0);
+        assert_eq!(world.chunks().get_voxel(8, 2, 8), 2);
+
+        // The torch breaks once its support is gone.
+        tick(&mut world);
+        assert_eq!(world.chunks().get_voxel(8, 2, 8), 0);
+
+        // Blocks that keep flipping each other stop once the chain gets too long.
+        for _ in 0..10 {
+            tick(&mut world);
+        }
+        assert_eq!(flips.load(Ordering::SeqCst), 3);
+        assert_eq!(world.chunks().get_voxel(2, 1, 2), 3);
+        assert_eq!(world.chunks().get_voxel(3, 1, 2), 4);
+    }
+}
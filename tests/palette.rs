@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use voxelize::{Chunk, ChunkArray, ChunkOptions, PaletteSection, VoxelAccess, VoxelEncoding};
+
+    #[test]
+    fn section_grows_and_repacks() {
+        let mut section = PaletteSection::new(4096, 0);
+        assert!(section.is_single_value());
+        assert_eq!(section.get(4095), 0);
+
+        for i in 0..4096 {
+            section.set(i, (i % 37) as u32);
+        }
+
+        assert!(!section.is_single_value());
+        assert!((0..4096).all(|i| section.get(i) == (i % 37) as u32));
+
+        // Values no longer in use are dropped once the palette runs out of room.
+        for i in 0..4096 {
+            section.set(i, 1000 + (i % 3) as u32);
+        }
+
+        for i in 0..4096 {
+            section.set(i, 2000 + (i % 50) as u32);
+        }
+
+        assert!(section.palette_len() <= 64);
+        assert!((0..4096).all(|i| section.get(i) == 2000 + (i % 50) as u32));
+    }
+
+    #[test]
+    fn section_keeps_room_after_repacking() {
+        let mut section = PaletteSection::new(4096, 0);
+
+        for i in 0..4096 {
+            section.set(i, (i % 40) as u32);
+        }
+
+        for i in 0..4096 {
+            section.set(i, (i % 2) as u32);
+        }
+
+        // Running out of room drops the unused values but keeps the bit width, so the values that
+        // come after are added without repacking the section again.
+        for value in 100..150 {
+            section.set(0, value);
+        }
+
+        assert_eq!(section.palette_len(), 28);
+        assert_eq!(section.get(0), 149);
+        assert!((1..4096).all(|i| section.get(i) == (i % 2) as u32));
+    }
+
+    #[test]
+    fn uneven_sections_match_dense() {
+        let shape = [4, 50, 4];
+        let mut dense = ChunkArray::new(&shape, 0, false, 8);
+        let mut paletted = ChunkArray::new(&shape, 0, true, 8);
+
+        for lx in 0..4 {
+            for ly in 0..50 {
+                for lz in 0..4 {
+                    let value = (lx * 7 + ly * 3 + lz) as u32;
+                    dense.set(lx, ly, lz, value);
+                    paletted.set(lx, ly, lz, value);
+                }
+            }
+        }
+
+        assert_eq!(dense.to_vec(), paletted.to_vec());
+
+        let mut reloaded = ChunkArray::new(&shape, 0, true, 8);
+        reloaded.assign(dense.to_vec());
+        assert_eq!(reloaded.to_vec(), dense.to_vec());
+    }
+
+    #[test]
+    fn paletted_matches_dense() {
+        let shape = [16, 256, 16];
+        let mut dense = ChunkArray::new(&shape, 0, false, 8);
+        let mut paletted = ChunkArray::new(&shape, 0, true, 8);
+
+        for lx in 0..16 {
+            for lz in 0..16 {
+                for ly in 0..(lx * 3 + lz) {
+                    let value = ((lx * lz + ly) % 5) as u32;
+                    dense.set(lx, ly, lz, value);
+                    paletted.set(lx, ly, lz, value);
+                }
+            }
+        }
+
+        assert_eq!(dense.to_vec(), paletted.to_vec());
+
+        let mut reloaded = ChunkArray::new(&shape, 0, true, 8);
+        reloaded.assign(dense.to_vec());
+        assert_eq!(reloaded.to_vec(), dense.to_vec());
+
+        assert!(paletted.memory_usage() * 4 < dense.memory_usage());
+    }
+
+    #[test]
+    fn paletted_chunk_access() {
+        let options = ChunkOptions {
+            size: 16,
+            max_height: 256,
+            sub_chunks: 8,
+            paletted: true,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunk = Chunk::new("test", -1, 2, &options);
+
+        chunk.set_voxel(-3, 40, 33, 7);
+        chunk.set_sunlight(-3, 41, 33, 15);
+
+        assert_eq!(chunk.get_voxel(-3, 40, 33), 7);
+        assert_eq!(chunk.get_voxel(-3, 41, 33), 0);
+        assert_eq!(chunk.get_sunlight(-3, 41, 33), 15);
+        assert!(chunk.get_voxels(-1, 2).is_none());
+    }
+}
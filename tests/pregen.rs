@@ -0,0 +1,127 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use voxelize::{
+        Block, ChunkRecord, FlatlandStage, PregenArea, PregenArgs, Registry, Vec2, VoxelEncoding,
+        World, WorldConfig,
+    };
+
+    use crate::common::temp_save_dir;
+
+    #[test]
+    fn pregen_args() {
+        let args = PregenArgs::parse(
+            [
+                "--config",
+                "world.toml",
+                "--rect",
+                "-2",
+                "3",
+                "4",
+                "-5",
+                "--mesh",
+            ]
+            .map(String::from),
+        )
+        .unwrap();
+
+        assert_eq!(args.config, PathBuf::from("world.toml"));
+        assert_eq!(args.save_dir, None);
+        assert_eq!(args.area, PregenArea::Rect(Vec2(-2, 3), Vec2(4, -5)));
+        assert!(args.mesh);
+        assert_eq!(args.area.chunks().len(), 7 * 9);
+
+        assert!(PregenArgs::parse(["--radius", "1"].map(String::from)).is_err());
+        assert!(PregenArgs::parse(["--config", "world.toml"].map(String::from)).is_err());
+        assert!(PregenArgs::parse(["--config", "a", "--radius", "x"].map(String::from)).is_err());
+    }
+
+    #[test]
+    fn pregen_world_config_is_read_from_a_file() {
+        let folder = temp_save_dir("pregen-config");
+        let file = folder.join("world.toml");
+        fs::write(
+            &file,
+            "maxHeight = 64\nseed = 42\nvoxelEncoding = \"wide\"\nsaveDir = \"from-file\"\n",
+        )
+        .unwrap();
+
+        let args = PregenArgs::parse(
+            [
+                "--config",
+                file.to_str().unwrap(),
+                "--save-dir",
+                folder.join("saves").to_str().unwrap(),
+                "--radius",
+                "1",
+            ]
+            .map(String::from),
+        )
+        .unwrap();
+
+        let config = args.world_config().unwrap();
+        assert_eq!(config.max_height, 64);
+        assert_eq!(config.seed, 42);
+        assert_eq!(config.voxel_encoding, VoxelEncoding::Wide);
+        assert_eq!(config.chunk_size, WorldConfig::default().chunk_size);
+        assert!(config.saving);
+        assert_eq!(config.save_dir, folder.join("saves").to_str().unwrap());
+
+        // Without `--save-dir`, the world is saved where the file says, and it has to say somewhere.
+        let args = PregenArgs::parse(
+            ["--config", file.to_str().unwrap(), "--radius", "0"].map(String::from),
+        )
+        .unwrap();
+        assert_eq!(args.world_config().unwrap().save_dir, "from-file");
+
+        fs::write(&file, "maxHeight = 64\n").unwrap();
+        assert!(args.world_config().is_err());
+
+        fs::write(&file, "maxHeight = \"tall\"\n").unwrap();
+        assert!(args.world_config().is_err());
+    }
+
+    #[test]
+    fn pregenerates_into_storage() {
+        for mesh in [false, true] {
+            let folder = temp_save_dir(&format!("pregen-mesh-{}", mesh));
+
+            let config = WorldConfig::new()
+                .max_height(32)
+                .saving(true)
+                .save_dir(folder.to_str().unwrap())
+                .build();
+
+            let mut registry = Registry::new();
+            registry.register_blocks(&[Block::new("Stone").id(1).build()]);
+
+            let mut world = World::new("test", &config);
+            world
+                .pipeline_mut()
+                .add_stage(FlatlandStage::new().add_soiling(1, 4));
+
+            let mut last = (0, 0);
+            let count = world
+                .pregenerate(
+                    &registry,
+                    &PregenArea::Radius(Vec2(0, 0), 0),
+                    mesh,
+                    |done, total| last = (done, total),
+                )
+                .unwrap();
+
+            assert_eq!(count, 1);
+            assert_eq!(last, (1, 1));
+
+            let storage = world.storage().unwrap();
+            let data = storage.load_chunk(&Vec2(0, 0)).unwrap().unwrap();
+            let record = ChunkRecord::decode(&data).unwrap();
+
+            assert_eq!(record.voxels[0], 1);
+            assert!(!record.lights.is_empty());
+        }
+    }
+}
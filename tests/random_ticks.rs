@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use specs::{Builder, RunNow, WorldExt};
+    use voxelize::{
+        Block, Chunk, ChunkOptions, ChunkRandomTickingSystem, ChunkStatus, ClientFlag,
+        CurrentChunkComp, Registry, Vec2, VoxelAccess, VoxelEncoding, World, WorldConfig,
+    };
+
+    #[test]
+    fn random_ticks_pick_voxels_near_clients() {
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .random_tick_speed(3)
+            .random_tick_radius(0)
+            .build();
+        let mut world = World::new("test", &config);
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let counter = ticks.clone();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Grass")
+            .id(1)
+            .random_tick(move |voxel, _, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                vec![(voxel, 0)]
+            })
+            .build()]);
+        world.ecs_mut().insert(registry);
+
+        for coords in [Vec2(0, 0), Vec2(3, 0)] {
+            let options = ChunkOptions {
+                size: config.chunk_size,
+                max_height: config.max_height,
+                sub_chunks: config.sub_chunks,
+                paletted: false,
+                encoding: VoxelEncoding::Packed,
+            };
+
+            let mut chunk = Chunk::new("test", coords.0, coords.1, &options);
+            for vx in 0..16 {
+                for vy in 0..32 {
+                    for vz in 0..16 {
+                        chunk.set_voxel(coords.0 * 16 + vx, vy, vz, 1);
+                    }
+                }
+            }
+            chunk.status = ChunkStatus::Ready;
+
+            world.chunks_mut().map.insert(coords, chunk);
+        }
+
+        // Nobody is around, so nothing ticks.
+        ChunkRandomTickingSystem.run_now(world.ecs());
+        assert_eq!(ticks.load(Ordering::SeqCst), 0);
+
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(ClientFlag::default())
+            .with(CurrentChunkComp::default())
+            .build();
+
+        // Only the chunk the client is in ticks, 3 voxels for each of its 2 sub chunks.
+        ChunkRandomTickingSystem.run_now(world.ecs());
+        assert_eq!(ticks.load(Ordering::SeqCst), 6);
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use specs::{Builder, WorldExt};
+    use voxelize::{
+        Block, BlockUtils, ClientFlag, IDComp, MemoryStorage, PositionComp, Region, RegionArea,
+        RegionFlag, Regions, Registry, Vec2, Vec3, VoxelAccess, WorldConfig, WorldStorage,
+        REGIONS_KEY,
+    };
+
+    use crate::common::setup_world;
+
+    fn spawn() -> Region {
+        Region::new(
+            "spawn",
+            RegionArea::Box {
+                min: Vec3(-8, 0, -8),
+                max: Vec3(8, 16, 8),
+            },
+        )
+        .owner("admin")
+        .flag(RegionFlag::Build, false)
+        .flag(RegionFlag::Break, false)
+    }
+
+    fn plot() -> Region {
+        Region::new(
+            "plot",
+            RegionArea::Box {
+                min: Vec3(0, 0, 0),
+                max: Vec3(3, 16, 3),
+            },
+        )
+        .owner("alice")
+        .member("bob")
+        .flag(RegionFlag::Build, false)
+        .flag(RegionFlag::Pvp, false)
+        .priority(1)
+    }
+
+    #[test]
+    fn regions_apply_by_priority_and_persist() {
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+        let mut regions = Regions::new(16, Some(storage.clone()));
+
+        regions.add(spawn());
+        regions.add(plot());
+
+        let in_spawn = Vec3(-4, 2, -4);
+        let in_plot = Vec3(1, 2, 1);
+
+        assert_eq!(regions.at(&in_plot).len(), 2);
+        assert!(!regions.is_allowed(&in_spawn, RegionFlag::Build, Some("bob")));
+        assert!(regions.is_allowed(&in_spawn, RegionFlag::Build, Some("admin")));
+        assert!(regions.is_allowed(&in_spawn, RegionFlag::Pvp, Some("bob")));
+
+        // The plot outranks spawn protection, so its members may build there.
+        assert!(regions.is_allowed(&in_plot, RegionFlag::Build, Some("bob")));
+        assert!(!regions.is_allowed(&in_plot, RegionFlag::Build, Some("admin")));
+        assert!(regions.is_allowed(&in_plot, RegionFlag::Break, Some("carol")));
+        assert!(regions.is_allowed(&Vec3(20, 2, 20), RegionFlag::Build, None));
+
+        regions.update("plot", |plot| {
+            plot.area = RegionArea::Chunks {
+                chunks: vec![Vec2(1, 1)],
+            }
+        });
+        assert_eq!(regions.at(&in_plot).len(), 1);
+
+        let loaded = Regions::new(16, Some(storage));
+        assert_eq!(loaded.get("spawn"), Some(&spawn()));
+        assert!(!loaded.is_allowed(&Vec3(17, 2, 17), RegionFlag::Build, Some("carol")));
+        assert!(loaded.is_allowed(&Vec3(17, 2, 17), RegionFlag::Build, Some("alice")));
+    }
+
+    #[test]
+    fn client_updates_respect_regions() {
+        let config = WorldConfig::new().build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Tall Grass").id(2).is_empty(true).build(),
+        ]);
+
+        let mut world = setup_world(&config, registry);
+        world.chunks_mut().set_voxel(0, 1, 1, 1);
+        world.chunks_mut().set_voxel(0, 1, 2, 2);
+
+        world.regions_mut().add(spawn());
+
+        for (id, x) in [("visitor", 0.5), ("admin", 1.5)] {
+            world
+                .ecs_mut()
+                .create_entity()
+                .with(ClientFlag::default())
+                .with(IDComp::new(id))
+                .with(PositionComp::new(x, 2.0, 0.5))
+                .build();
+        }
+
+        assert!(!world.validate_update("visitor", &(Vec3(0, 1, 0), 1)));
+        assert!(world.validate_update("visitor", &(Vec3(0, 1, 0), 0)));
+        assert!(world.validate_update("visitor", &(Vec3(12, 1, 0), 1)));
+        assert!(world.validate_update("admin", &(Vec3(0, 1, 0), 1)));
+
+        // Empty blocks can be broken and placed, and unregistered ones are treated as solid.
+        assert!(world.validate_update("visitor", &(Vec3(0, 1, 2), 0)));
+        assert!(world.validate_update("visitor", &(Vec3(0, 1, 0), 2)));
+        assert!(!world.validate_update("visitor", &(Vec3(0, 1, 0), 9)));
+        assert!(!world.validate_update("visitor", &(Vec3(0, 1, 1), 0)));
+
+        // Changing the state of a block is interacting with it.
+        let opened = BlockUtils::insert_state(1, 1);
+        assert!(world.validate_update("visitor", &(Vec3(0, 1, 1), opened)));
+
+        world
+            .regions_mut()
+            .protect_method("fight", RegionFlag::Interact);
+        world.regions_mut().update("spawn", |spawn| {
+            spawn.flags.insert(RegionFlag::Interact, false);
+        });
+
+        assert!(!world.is_client_allowed("visitor", RegionFlag::Interact));
+        assert!(!world.validate_update("visitor", &(Vec3(0, 1, 1), opened)));
+        assert!(world.validate_update("admin", &(Vec3(0, 1, 1), opened)));
+        assert!(world.is_client_allowed("admin", RegionFlag::Interact));
+        assert!(!world.is_client_allowed("ghost", RegionFlag::Interact));
+    }
+
+    #[test]
+    fn box_corners_can_be_given_in_any_order() {
+        let mut regions = Regions::new(16, None);
+
+        regions.add(Region::new(
+            "flipped",
+            RegionArea::Box {
+                min: Vec3(20, 16, 20),
+                max: Vec3(-4, 0, -4),
+            },
+        ));
+
+        assert_eq!(regions.at(&Vec3(0, 8, 0)).len(), 1);
+        assert_eq!(regions.at(&Vec3(18, 8, 18)).len(), 1);
+        assert!(regions.at(&Vec3(21, 8, 0)).is_empty());
+    }
+
+    #[test]
+    fn unreadable_regions_are_not_saved_over() {
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+        storage.save_metadata(REGIONS_KEY, b"[{").unwrap();
+
+        let mut regions = Regions::new(16, Some(storage.clone()));
+        regions.add(spawn());
+        assert_eq!(
+            storage.load_metadata(REGIONS_KEY).unwrap(),
+            Some(b"[{".to_vec())
+        );
+
+        regions.overwrite_saved();
+        assert_eq!(Regions::new(16, Some(storage)).get("spawn"), Some(&spawn()));
+    }
+}
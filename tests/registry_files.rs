@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use voxelize::{Registry, Vec2, VoxelAccess, WorldConfig, REGISTRY_EVENT};
+
+    use crate::common::{setup_world, temp_save_dir};
+
+    const BLOCKS_JSON: &str = r#"{
+        "blocks": [
+            { "name": "Stone" },
+            {
+                "name": "Grass",
+                "isPassable": true,
+                "isTransparent": true,
+                "faces": [{ "preset": "diagonalFaces", "scaleHorizontal": 0.8, "toFour": true }],
+                "aabbs": []
+            }
+        ]
+    }"#;
+
+    const BLOCKS_TOML: &str = r#"
+        [[blocks]]
+        name = "Slab"
+        id = 10
+        isYTransparent = true
+
+        [[blocks.faces]]
+        preset = "sixFaces"
+        scaleY = 0.5
+        independentAt = [1]
+
+        [[blocks.aabbs]]
+        minX = 0.0
+        minY = 0.0
+        minZ = 0.0
+        maxX = 1.0
+        maxY = 0.5
+        maxZ = 1.0
+    "#;
+
+    #[test]
+    fn blocks_are_loaded_from_files_and_reloaded() {
+        let folder = temp_save_dir("registry");
+
+        fs::write(folder.join("blocks.json"), BLOCKS_JSON).unwrap();
+        fs::write(folder.join("slabs.toml"), BLOCKS_TOML).unwrap();
+        fs::write(folder.join("notes.txt"), "not blocks").unwrap();
+
+        let mut registry = Registry::new();
+        assert_eq!(registry.register_dir(&folder).unwrap(), 3);
+        registry.generate();
+
+        let stone = registry.get_block_by_name("Stone");
+        assert_eq!(stone.faces.len(), 6);
+        assert!(stone.is_opaque);
+
+        let grass = registry.get_block_by_name("Grass");
+        assert_eq!(grass.faces.len(), 4);
+        assert!(grass.is_passable && grass.aabbs.is_empty());
+
+        let slab = registry.get_block_by_name("Slab");
+        assert_eq!(slab.id, 10);
+        assert!(slab.faces[1].independent);
+        assert_eq!(slab.aabbs[0].max_y, 0.5);
+
+        let stone_id = registry.get_id_by_name("Stone");
+
+        let config = WorldConfig::new().max_height(32).sub_chunks(2).build();
+        let mut world = setup_world(&config, registry);
+
+        world.chunks_mut().set_voxel(1, 1, 1, stone_id);
+        world
+            .chunks_mut()
+            .map
+            .values_mut()
+            .for_each(|chunk| chunk.has_lights = true);
+
+        // A broken file leaves the registry as it was.
+        fs::write(folder.join("broken.json"), "{ \"blocks\": [").unwrap();
+        assert!(world.reload_registry().is_err());
+        fs::remove_file(folder.join("broken.json")).unwrap();
+
+        // Only the chunks holding a block that changed are meshed again.
+        fs::write(
+            folder.join("blocks.json"),
+            BLOCKS_JSON.replace(
+                r#"{ "name": "Stone" }"#,
+                r#"{ "name": "Stone", "isPassable": true }, { "name": "Marble" }"#,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(world.reload_registry().unwrap(), 1);
+        assert!(world.mesher().has_chunk(&Vec2(0, 0)));
+        assert!(!world.mesher().has_chunk(&Vec2(1, 1)));
+        assert!(world.chunks().raw(&Vec2(1, 1)).unwrap().has_lights);
+
+        {
+            let registry = world.registry();
+
+            let stone = registry.get_block_by_name("Stone");
+            assert_eq!(stone.id, stone_id);
+            assert!(stone.is_passable);
+
+            assert!(registry.has_type(registry.get_id_by_name("Marble")));
+        }
+
+        // Blocks giving off light differently relight every chunk their light could reach.
+        fs::write(
+            folder.join("blocks.json"),
+            BLOCKS_JSON.replace(
+                r#"{ "name": "Stone" }"#,
+                r#"{ "name": "Stone", "torchLightLevel": 10 }, { "name": "Marble" }"#,
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(world.reload_registry().unwrap(), 9);
+        assert!(world.registry().get_block_by_name("Stone").is_light);
+        assert!(!world.chunks().raw(&Vec2(1, 1)).unwrap().has_lights);
+
+        assert!(world
+            .events()
+            .queue
+            .iter()
+            .any(|event| event.name == REGISTRY_EVENT));
+    }
+
+    #[test]
+    fn blocks_taken_out_of_files_are_unregistered() {
+        let folder = temp_save_dir("registry-removed");
+        fs::write(folder.join("blocks.json"), BLOCKS_JSON).unwrap();
+
+        let mut registry = Registry::new();
+        registry.register_dir(&folder).unwrap();
+        registry.generate();
+
+        let grass_id = registry.get_id_by_name("Grass");
+        let stone_id = registry.get_id_by_name("Stone");
+
+        let config = WorldConfig::new()
+            .max_height(32)
+            .sub_chunks(2)
+            .fallback_block("Stone")
+            .build();
+        let mut world = setup_world(&config, registry);
+
+        world.chunks_mut().set_voxel(1, 1, 1, grass_id);
+
+        fs::write(
+            folder.join("blocks.json"),
+            r#"{ "blocks": [{ "name": "Stone" }] }"#,
+        )
+        .unwrap();
+
+        assert!(world.reload_registry().unwrap() > 0);
+        assert!(!world.registry().has_type(grass_id));
+        assert!(!world.registry().blocks_by_name.contains_key("grass"));
+        assert_eq!(world.chunks().get_voxel(1, 1, 1), stone_id);
+    }
+}
@@ -0,0 +1,207 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use specs::WorldExt;
+    use voxelize::{
+        Block, BlockRotation, BlockUtils, Chunk, ChunkOptions, ChunkStatus, Chunks, JsonComp,
+        PasteOptions, Registry, Schematic, SchematicBlockEntity, Vec2, Vec3, VoxelAccess,
+        VoxelEncoding, WorldConfig, SCHEMATIC_VERSION,
+    };
+
+    use crate::common::{setup_world, tick};
+
+    #[test]
+    fn paste_options_turn_voxels_and_rotations() {
+        let size = Vec3(3, 1, 2);
+        let options = PasteOptions {
+            rotation: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(options.pasted_size(&size), Vec3(2, 1, 3));
+        assert_eq!(
+            options.transform_voxel(&Vec3(0, 0, 0), &size),
+            Vec3(0, 0, 2)
+        );
+        assert_eq!(
+            options.transform_voxel(&Vec3(2, 0, 1), &size),
+            Vec3(1, 0, 0)
+        );
+
+        let facing_x = BlockUtils::insert_rotation(7, &BlockRotation::PX(0.0));
+        assert_eq!(
+            BlockUtils::extract_rotation(options.transform_raw(facing_x, true, false)),
+            BlockRotation::NZ(0.0)
+        );
+        assert_eq!(options.transform_raw(facing_x, false, false), facing_x);
+
+        let mirrored = PasteOptions {
+            mirror_x: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            mirrored.transform_voxel(&Vec3(0, 0, 1), &size),
+            Vec3(2, 0, 1)
+        );
+        assert_eq!(
+            BlockUtils::extract_rotation(mirrored.transform_raw(facing_x, true, false)),
+            BlockRotation::NX(0.0)
+        );
+
+        // Four quarter turns of a y rotatable block end where they started.
+        let turned = BlockUtils::insert_rotation(7, &BlockRotation::encode(0, 3));
+        let full_turn = (0..4).fold(turned, |raw, _| options.transform_raw(raw, false, true));
+        assert_eq!(full_turn, turned);
+    }
+
+    #[test]
+    fn schematics_round_trip_and_paste() {
+        let config = WorldConfig::new().build();
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Log").id(2).rotatable(true).build(),
+        ]);
+
+        let mut chunks = Chunks::new(&config, None);
+        let mut chunk = Chunk::new("test", 0, 0, &options);
+        chunk.status = ChunkStatus::Ready;
+        chunks.map.insert(Vec2(0, 0), chunk);
+
+        chunks.set_raw_voxel(1, 0, 1, 1);
+        chunks.set_raw_voxel(
+            2,
+            1,
+            1,
+            BlockUtils::insert_rotation(2, &BlockRotation::PX(0.0)),
+        );
+
+        let schematic = chunks.export_schematic(&Vec3(3, 1, 2), &Vec3(1, 0, 1), &registry);
+        assert_eq!(schematic.size, Vec3(3, 2, 2));
+        assert_eq!(schematic.get_raw_voxel(0, 0, 0), 1);
+        assert_eq!(
+            schematic.palette,
+            vec![
+                (0, "Air".to_owned()),
+                (1, "Stone".to_owned()),
+                (2, "Log".to_owned())
+            ]
+        );
+
+        let decoded = Schematic::decode(&schematic.encode().unwrap()).unwrap();
+        assert_eq!(decoded, schematic);
+        assert!(Schematic::decode(b"not a schematic").is_err());
+
+        // Pasting into a registry where the blocks moved remaps them by name.
+        let mut other = Registry::new();
+        other.register_blocks(&[Block::new("Log").id(5).rotatable(true).build()]);
+
+        let paste = PasteOptions {
+            skip_air: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            chunks.paste_schematic(&decoded, &Vec3(0, 10, 0), &paste, &other),
+            1
+        );
+    }
+
+    #[test]
+    fn dropped_pastes_forget_their_block_entities() {
+        let config = WorldConfig::new().max_height(32).sub_chunks(2).build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Chest").id(1).is_entity(true).build()]);
+        let mut world = setup_world(&config, registry.clone());
+
+        let schematic = Schematic {
+            version: SCHEMATIC_VERSION,
+            size: Vec3(1, 1, 1),
+            voxels: vec![1],
+            palette: vec![(1, "Chest".to_owned())],
+            block_entities: vec![SchematicBlockEntity {
+                voxel: Vec3(0, 0, 0),
+                json: "{\"items\":3}".to_owned(),
+            }],
+        };
+
+        // The chunk pasted into is not loaded, so the paste is dropped.
+        world.chunks_mut().paste_schematic(
+            &schematic,
+            &Vec3(40, 1, 0),
+            &PasteOptions::default(),
+            &registry,
+        );
+        tick(&mut world);
+
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        for cx in 2..=3 {
+            for cz in -1..=1 {
+                let mut chunk = Chunk::new("test", cx, cz, &options);
+                chunk.status = ChunkStatus::Ready;
+                world.chunks_mut().map.insert(Vec2(cx, cz), chunk);
+            }
+        }
+
+        // A later chest at the same voxel starts out empty.
+        world.chunks_mut().update_voxels(&[(Vec3(40, 1, 0), 1)]);
+        tick(&mut world);
+        world.ecs_mut().maintain();
+
+        let entity = world.chunks().block_entities[&Vec3(40, 1, 0)];
+        assert_eq!(
+            world.read_component::<JsonComp>().get(entity).unwrap().0,
+            "{}"
+        );
+    }
+
+    #[test]
+    fn wide_voxels_are_masked_in_packed_worlds() {
+        let config = WorldConfig::new().max_height(32).sub_chunks(2).build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Crop").id(1).build()]);
+        let mut world = setup_world(&config, registry.clone());
+
+        // Exported from a wide world, with a stage a packed world has no room for.
+        let schematic = Schematic {
+            version: SCHEMATIC_VERSION,
+            size: Vec3(1, 1, 1),
+            voxels: vec![BlockUtils::insert_wide_stage(1, 0x23)],
+            palette: vec![(1, "Crop".to_owned())],
+            block_entities: vec![],
+        };
+
+        world.chunks_mut().paste_schematic(
+            &schematic,
+            &Vec3(1, 1, 1),
+            &PasteOptions::default(),
+            &registry,
+        );
+        world
+            .chunks_mut()
+            .update_voxels(&[(Vec3(2, 1, 1), BlockUtils::insert_wide_id(0, 0x10001))]);
+        tick(&mut world);
+
+        let chunks = world.chunks();
+        assert_eq!(chunks.get_voxel(1, 1, 1), 1);
+        assert_eq!(chunks.get_voxel_stage(1, 1, 1), 3);
+        assert_eq!(chunks.get_raw_voxel(2, 1, 1), 0);
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use voxelize::{Block, Registry, Vec3, VoxelAccess, World, WorldConfig};
+
+    use crate::common::{setup_world, tick};
+
+    const WIRE: u32 = 1;
+    const SWITCH: u32 = 2;
+    const WEAK_SWITCH: u32 = 3;
+    const DOOR: u32 = 4;
+    const OPEN_DOOR: u32 = 5;
+    const X_WIRE: u32 = 6;
+
+    fn stages(world: &World, from: i32, to: i32, z: i32) -> Vec<u32> {
+        let chunks = world.chunks();
+        (from..=to)
+            .map(|vx| chunks.get_voxel_stage(vx, 1, z))
+            .collect()
+    }
+
+    fn setup() -> World {
+        let config = WorldConfig::new().max_height(32).sub_chunks(2).build();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Wire").id(WIRE).signal_conductor(true).build(),
+            Block::new("Switch").id(SWITCH).signal_power(15).build(),
+            Block::new("Weak Switch")
+                .id(WEAK_SWITCH)
+                .signal_power(3)
+                .build(),
+            Block::new("Door")
+                .id(DOOR)
+                .on_signal(|voxel, power, _, _| {
+                    if power > 0 {
+                        vec![(voxel, OPEN_DOOR.into())]
+                    } else {
+                        vec![]
+                    }
+                })
+                .build(),
+            Block::new("Open Door")
+                .id(OPEN_DOOR)
+                .on_signal(|voxel, power, _, _| {
+                    if power == 0 {
+                        vec![(voxel, DOOR.into())]
+                    } else {
+                        vec![]
+                    }
+                })
+                .build(),
+            Block::new("X Wire")
+                .id(X_WIRE)
+                .signal_conductor(true)
+                .signal_faces([true, false, false, true, false, false])
+                .build(),
+        ]);
+
+        setup_world(&config, registry)
+    }
+
+    #[test]
+    fn switches_power_wires_and_open_doors() {
+        let mut world = setup();
+
+        world.chunks_mut().update_voxels(&[
+            (Vec3(1, 1, 1), SWITCH.into()),
+            (Vec3(2, 1, 1), WIRE.into()),
+            (Vec3(3, 1, 1), WIRE.into()),
+            (Vec3(4, 1, 1), WIRE.into()),
+            (Vec3(5, 1, 1), DOOR.into()),
+        ]);
+        tick(&mut world);
+        assert_eq!(stages(&world, 2, 4, 1), [14, 13, 12]);
+
+        tick(&mut world);
+        assert_eq!(world.chunks().get_voxel(5, 1, 1), OPEN_DOOR);
+
+        // Breaking the middle of the wire cuts the door off.
+        world.chunks_mut().update_voxels(&[(Vec3(3, 1, 1), 0)]);
+        tick(&mut world);
+        assert_eq!(stages(&world, 2, 4, 1), [14, 0, 0]);
+
+        tick(&mut world);
+        assert_eq!(world.chunks().get_voxel(5, 1, 1), DOOR);
+
+        // Mending it powers the rest back up.
+        world
+            .chunks_mut()
+            .update_voxels(&[(Vec3(3, 1, 1), WIRE.into())]);
+        tick(&mut world);
+        tick(&mut world);
+        assert_eq!(stages(&world, 2, 4, 1), [14, 13, 12]);
+        assert_eq!(world.chunks().get_voxel(5, 1, 1), OPEN_DOOR);
+
+        // Turning the switch off takes all the power away.
+        world.chunks_mut().update_voxels(&[(Vec3(1, 1, 1), 0)]);
+        tick(&mut world);
+        tick(&mut world);
+        assert_eq!(stages(&world, 2, 4, 1), [0, 0, 0]);
+        assert_eq!(world.chunks().get_voxel(5, 1, 1), DOOR);
+    }
+
+    #[test]
+    fn signals_decay_and_respect_faces() {
+        let mut world = setup();
+
+        world.chunks_mut().update_voxels(&[
+            (Vec3(1, 1, 5), WEAK_SWITCH.into()),
+            (Vec3(2, 1, 5), WIRE.into()),
+            (Vec3(3, 1, 5), WIRE.into()),
+            (Vec3(4, 1, 5), WIRE.into()),
+            (Vec3(5, 1, 5), DOOR.into()),
+            (Vec3(1, 1, 8), SWITCH.into()),
+            (Vec3(2, 1, 8), X_WIRE.into()),
+            (Vec3(3, 1, 8), WIRE.into()),
+            (Vec3(2, 1, 9), WIRE.into()),
+        ]);
+        tick(&mut world);
+        tick(&mut world);
+
+        assert_eq!(stages(&world, 2, 4, 5), [2, 1, 0]);
+        assert_eq!(world.chunks().get_voxel(5, 1, 5), DOOR);
+
+        // Signals only go through the sides of the x wire along the x axis.
+        assert_eq!(stages(&world, 2, 3, 8), [14, 13]);
+        assert_eq!(world.chunks().get_voxel_stage(2, 1, 9), 0);
+    }
+}
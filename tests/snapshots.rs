@@ -0,0 +1,78 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use voxelize::{
+        Chunk, ChunkOptions, ChunkRecord, ChunkStatus, Registry, Vec2, VoxelAccess, VoxelEncoding,
+        World, WorldConfig,
+    };
+
+    use crate::common::temp_save_dir;
+
+    fn set_chunk(world: &mut World, coords: Vec2<i32>, voxel: u32) {
+        let config = world.config().make_copy();
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunk = Chunk::new("test", coords.0, coords.1, &options);
+        chunk.set_voxel(coords.0 * 16, 0, coords.1 * 16, voxel);
+        chunk.status = ChunkStatus::Ready;
+
+        let mut chunks = world.chunks_mut();
+        chunks.map.insert(coords.clone(), chunk);
+        chunks.add_chunk_to_save(&coords, false);
+    }
+
+    fn saved_voxel(world: &World, coords: Vec2<i32>) -> Option<u32> {
+        let data = world.storage().unwrap().load_chunk(&coords).unwrap()?;
+        Some(ChunkRecord::decode(&data).unwrap().voxels[0])
+    }
+
+    #[test]
+    fn snapshots_restore_whole_worlds_and_regions() {
+        let folder = temp_save_dir("snapshots");
+
+        let config = WorldConfig::new()
+            .saving(true)
+            .save_dir(folder.to_str().unwrap())
+            .build();
+        let mut world = World::new("test", &config);
+        world.ecs_mut().insert(Registry::new());
+
+        set_chunk(&mut world, Vec2(0, 0), 1);
+        set_chunk(&mut world, Vec2(5, 5), 1);
+
+        let snapshot = world.snapshot().unwrap();
+        assert_eq!(snapshot.chunks, 2);
+        assert_eq!(world.snapshots().list().unwrap().len(), 1);
+
+        set_chunk(&mut world, Vec2(0, 0), 2);
+        set_chunk(&mut world, Vec2(5, 5), 2);
+        set_chunk(&mut world, Vec2(9, 9), 2);
+        world.flush();
+
+        // Only the region around the first chunk goes back.
+        let restored = world
+            .restore_snapshot(&snapshot.name, Some((Vec2(-1, -1), Vec2(1, 1))))
+            .unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(saved_voxel(&world, Vec2(0, 0)), Some(1));
+        assert_eq!(saved_voxel(&world, Vec2(5, 5)), Some(2));
+        assert!(world.chunks().raw(&Vec2(0, 0)).is_none());
+
+        world.restore_snapshot(&snapshot.name, None).unwrap();
+        assert_eq!(saved_voxel(&world, Vec2(5, 5)), Some(1));
+        assert_eq!(saved_voxel(&world, Vec2(9, 9)), None);
+
+        assert!(world.restore_snapshot("../escape", None).is_err());
+        assert!(world
+            .snapshot_command(&format!("delete {}", snapshot.name))
+            .starts_with("Deleted"));
+        assert!(world.snapshots().list().unwrap().is_empty());
+    }
+}
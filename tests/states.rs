@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use voxelize::{
+        Block, BlockConditionalPart, BlockDynamicPattern, BlockFaces, BlockProperty, BlockRule,
+        BlockRuleLogic, BlockState, Chunk, ChunkOptions, ChunkStatus, Chunks, MemoryStorage,
+        PropertyValue, Registry, Vec2, Vec3, VoxelAccess, VoxelEncoding, WorldConfig, WorldStorage,
+    };
+
+    fn state(properties: &[(&str, PropertyValue)]) -> BlockState {
+        properties
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_owned()))
+            .collect()
+    }
+
+    fn door_registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Door")
+                .id(2)
+                .property(BlockProperty::boolean("open"))
+                .property(BlockProperty::named("hinge", &["left", "right"]))
+                .property(BlockProperty::int("power", 0..=1))
+                .build(),
+        ]);
+        registry
+    }
+
+    fn chunk(encoding: VoxelEncoding) -> Chunk {
+        Chunk::new(
+            "test",
+            0,
+            0,
+            &ChunkOptions {
+                size: 16,
+                max_height: 16,
+                sub_chunks: 1,
+                paletted: false,
+                encoding,
+            },
+        )
+    }
+
+    #[test]
+    fn states_are_every_combination_of_properties() {
+        let registry = door_registry();
+
+        let states = &registry.get_state_table()[&2];
+        assert_eq!(states.len(), 8);
+        assert_eq!(
+            states[0],
+            state(&[
+                ("open", false.into()),
+                ("hinge", "left".into()),
+                ("power", 0.into())
+            ])
+        );
+        assert_eq!(states[1]["open"], true.into());
+        assert!(registry.get_state(1, 0).is_none());
+
+        // Properties that are left out take their default.
+        assert_eq!(
+            registry.get_state_id(2, &state(&[("hinge", "right".into())])),
+            Some(2)
+        );
+        assert_eq!(
+            registry.get_state_id(2, &state(&[("color", "red".into())])),
+            None
+        );
+        assert_eq!(
+            registry.get_matching_states(2, &state(&[("open", true.into())])),
+            [1, 3, 5, 7]
+        );
+    }
+
+    #[test]
+    fn voxel_properties_are_read_and_written() {
+        let registry = door_registry();
+        let mut chunk = chunk(VoxelEncoding::Packed);
+
+        chunk.set_voxel(1, 1, 1, 2);
+        chunk.set_voxel_stage(1, 1, 1, 9);
+
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "open", &registry),
+            Some(false.into())
+        );
+        assert!(chunk.set_state(1, 1, 1, "open", true.into(), &registry));
+        assert!(chunk.set_state(1, 1, 1, "power", 1.into(), &registry));
+        assert!(!chunk.set_state(1, 1, 1, "power", 2.into(), &registry));
+        assert!(!chunk.set_state(1, 1, 1, "color", "red".into(), &registry));
+
+        assert_eq!(chunk.get_voxel_state(1, 1, 1), 5);
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "open", &registry),
+            Some(true.into())
+        );
+        assert_eq!(chunk.get_voxel(1, 1, 1), 2);
+        assert_eq!(chunk.get_voxel_stage(1, 1, 1), 9);
+
+        chunk.set_voxel(2, 1, 1, 1);
+        assert_eq!(chunk.get_state(2, 1, 1, "open", &registry), None);
+    }
+
+    #[test]
+    fn state_rules_match_and_resolve_to_state_ids() {
+        let top = BlockFaces::six_faces().scale_y(0.5).build().to_vec();
+
+        let mut registry = door_registry();
+        registry.register_block(
+            &Block::new("Mat")
+                .id(3)
+                .dynamic_patterns(&[BlockDynamicPattern {
+                    parts: vec![BlockConditionalPart {
+                        rule: BlockRule::State {
+                            offset: Vec3(0, -1, 0),
+                            id: Some(2),
+                            properties: state(&[("open", true.into())]),
+                        },
+                        faces: top.clone(),
+                        aabbs: vec![],
+                        is_transparent: [false; 6],
+                    }],
+                }])
+                .build(),
+        );
+
+        let mut chunk = chunk(VoxelEncoding::Packed);
+        chunk.set_voxel(1, 1, 1, 2);
+        chunk.set_voxel(3, 1, 3, 2);
+        chunk.set_state(1, 1, 1, "open", true.into(), &registry);
+
+        let mat = registry.get_block_by_id(3).to_owned();
+        assert_eq!(mat.get_faces(&Vec3(1, 2, 1), &chunk, &registry), top);
+        assert_ne!(mat.get_faces(&Vec3(3, 2, 3), &chunk, &registry), top);
+
+        // Clients only know about state IDs, so the rule is sent as the matching states.
+        registry.generate();
+
+        let mat = registry.get_block_by_name("Mat");
+        match &mat.dynamic_patterns.as_ref().unwrap()[0].parts[0].rule {
+            BlockRule::Combination {
+                logic: BlockRuleLogic::Or,
+                rules,
+            } => assert_eq!(rules.len(), 4),
+            _ => panic!("State rule was not resolved."),
+        }
+    }
+
+    #[test]
+    fn wide_voxels_hold_more_states() {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stairs")
+            .id(3)
+            .property(BlockProperty::named(
+                "shape",
+                &[
+                    "straight",
+                    "inner_left",
+                    "inner_right",
+                    "outer_left",
+                    "outer_right",
+                ],
+            ))
+            .property(BlockProperty::named("half", &["bottom", "top"]))
+            .property(BlockProperty::boolean("waterlogged"))
+            .build()]);
+        assert_eq!(registry.get_state_table()[&3].len(), 20);
+
+        let mut chunk = chunk(VoxelEncoding::Wide);
+        chunk.set_voxel(1, 1, 1, 3);
+        chunk.set_voxel_stage(1, 1, 1, 300);
+
+        assert!(chunk.set_state(1, 1, 1, "shape", "outer_right".into(), &registry));
+        assert!(chunk.set_state(1, 1, 1, "half", "top".into(), &registry));
+        assert!(chunk.set_state(1, 1, 1, "waterlogged", true.into(), &registry));
+
+        assert_eq!(chunk.get_voxel_state(1, 1, 1), 19);
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "shape", &registry),
+            Some("outer_right".into())
+        );
+        assert_eq!(chunk.get_voxel(1, 1, 1), 3);
+        assert_eq!(chunk.get_voxel_stage(1, 1, 1), 300);
+
+        // Packed voxels only hold the first 16 states.
+        let mut packed = self::chunk(VoxelEncoding::Packed);
+        let result = std::panic::catch_unwind(move || packed.set_voxel_state(1, 1, 1, 16));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn saved_states_follow_their_properties() {
+        let config = WorldConfig::new()
+            .saving(true)
+            .chunk_size(16)
+            .max_height(16)
+            .sub_chunks(1)
+            .build();
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+        let registry = door_registry();
+
+        let mut chunk = chunk(VoxelEncoding::Packed);
+        chunk.set_voxel(1, 1, 1, 2);
+        chunk.set_state(1, 1, 1, "open", true.into(), &registry);
+        chunk.set_state(1, 1, 1, "hinge", "right".into(), &registry);
+        chunk.set_state(1, 1, 1, "power", 1.into(), &registry);
+        chunk.status = ChunkStatus::Ready;
+
+        let mut chunks = Chunks::new(&config, Some(storage.clone()));
+        chunks.map.insert(Vec2(0, 0), chunk);
+        chunks.save(&Vec2(0, 0), &registry);
+
+        // The properties are reordered, one is taken out and one is added.
+        let mut changed = Registry::new();
+        changed.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Door")
+                .id(2)
+                .property(BlockProperty::named("hinge", &["left", "right"]))
+                .property(BlockProperty::boolean("locked"))
+                .property(BlockProperty::boolean("open"))
+                .build(),
+        ]);
+
+        let chunks = Chunks::new(&config, Some(storage.clone()));
+        let chunk = chunks.try_load(&Vec2(0, 0), &changed).unwrap();
+
+        assert_eq!(chunk.get_voxel(1, 1, 1), 2);
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "open", &changed),
+            Some(true.into())
+        );
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "hinge", &changed),
+            Some("right".into())
+        );
+        assert_eq!(
+            chunk.get_state(1, 1, 1, "locked", &changed),
+            Some(false.into())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn blocks_cannot_have_too_many_states() {
+        Block::new("Lamp")
+            .property(BlockProperty::int("level", 0..=255))
+            .property(BlockProperty::boolean("lit"))
+            .build();
+    }
+}
@@ -0,0 +1,492 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io, sync::Arc};
+
+    use specs::{RunNow, WorldExt};
+    use voxelize::{
+        Block, Chunk, ChunkOptions, ChunkRecord, ChunkStatus, ChunkStorage, ChunkUnloadingSystem,
+        ChunkUtils, Chunks, IDComp, MemoryStorage, MetadataComp, RegionStorage, Registry,
+        SingleFileStorage, StorageBackend, Vec2, Vec3, VoxelAccess, VoxelComp, VoxelEncoding,
+        World, WorldConfig, WorldStorage,
+    };
+
+    use crate::common::temp_save_dir;
+
+    #[test]
+    fn region_round_trip() {
+        let folder = temp_save_dir("region-round-trip");
+        let regions = RegionStorage::new(&folder);
+
+        let coords = [Vec2(0, 0), Vec2(-1, 5), Vec2(31, 31), Vec2(32, -33)];
+
+        for (i, coords) in coords.iter().enumerate() {
+            let record = ChunkRecord {
+                id: format!("chunk-{}", i),
+                voxels: vec![i as u32; 16 * 16 * 16],
+                extras: vec![],
+                height_map: vec![3; 16 * 16],
+                lights: vec![],
+                palette: vec![],
+                states: vec![],
+                ticks: vec![],
+            };
+
+            regions.save(coords, &record.encode()).unwrap();
+        }
+
+        // Reopen from disk to make sure the offset table was persisted.
+        regions.close();
+
+        for (i, coords) in coords.iter().enumerate() {
+            let bytes = regions.load(coords).unwrap().unwrap();
+            let record = ChunkRecord::decode(&bytes).unwrap();
+
+            assert_eq!(record.id, format!("chunk-{}", i));
+            assert_eq!(record.voxels, vec![i as u32; 16 * 16 * 16]);
+            assert_eq!(record.height_map, vec![3; 16 * 16]);
+        }
+
+        let mut listed = regions.list().unwrap();
+        listed.sort_by_key(|c| (c.0, c.1));
+        let mut expected = coords.to_vec();
+        expected.sort_by_key(|c| (c.0, c.1));
+        assert_eq!(listed, expected);
+
+        assert!(regions.load(&Vec2(1, 1)).unwrap().is_none());
+    }
+
+    #[test]
+    fn region_rewrites_and_deletes() {
+        let folder = temp_save_dir("region-rewrites");
+        let regions = RegionStorage::new(&folder);
+        let coords = Vec2(3, 4);
+
+        // Incompressible data, so that growing it needs more sectors.
+        let small: Vec<u8> = (0..100).map(|i| (i * 7) as u8).collect();
+        let large: Vec<u8> = (0..20000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+
+        regions.save(&coords, &small).unwrap();
+        regions.save(&Vec2(4, 4), &small).unwrap();
+        regions.save(&coords, &large).unwrap();
+        regions.save(&coords, &small).unwrap();
+
+        regions.close();
+
+        assert_eq!(regions.load(&coords).unwrap().unwrap(), small);
+        assert_eq!(regions.load(&Vec2(4, 4)).unwrap().unwrap(), small);
+
+        regions.delete(&coords).unwrap();
+        assert!(!regions.contains(&coords));
+        assert!(regions.contains(&Vec2(4, 4)));
+    }
+
+    fn exercise_storage(storage: &dyn WorldStorage) {
+        storage.save_chunk(&Vec2(1, -2), b"chunk").unwrap();
+        storage.save_chunk(&Vec2(1, -2), b"chunk again").unwrap();
+        storage.save_entity("a", b"{}").unwrap();
+        storage.save_entity("b", b"{}").unwrap();
+        storage.save_metadata("stats.json", b"{}").unwrap();
+
+        assert_eq!(
+            storage.load_chunk(&Vec2(1, -2)).unwrap().unwrap(),
+            b"chunk again"
+        );
+        assert!(storage.has_chunk(&Vec2(1, -2)));
+        assert!(!storage.has_chunk(&Vec2(0, 0)));
+        assert_eq!(storage.list_chunks().unwrap(), vec![Vec2(1, -2)]);
+
+        storage.delete_entity("a").unwrap();
+        storage.delete_entity("missing").unwrap();
+        assert_eq!(storage.list_entities().unwrap(), vec!["b".to_owned()]);
+        assert!(storage.load_entity("a").unwrap().is_none());
+
+        assert_eq!(storage.load_metadata("stats.json").unwrap().unwrap(), b"{}");
+        assert!(storage
+            .list_metadata()
+            .unwrap()
+            .contains(&"stats.json".to_owned()));
+    }
+
+    #[test]
+    fn memory_storage() {
+        exercise_storage(&MemoryStorage::new());
+    }
+
+    #[test]
+    fn directory_storage() {
+        let folder = temp_save_dir("directory-storage");
+        let storage = StorageBackend::Directory.open(folder.to_str().unwrap());
+        exercise_storage(storage.as_ref());
+    }
+
+    #[test]
+    fn single_file_storage_survives_reopen() {
+        let folder = temp_save_dir("single-file-storage");
+        let mut path = folder.clone();
+        path.push("world.vxw");
+
+        {
+            let storage = SingleFileStorage::open(&path).unwrap();
+            exercise_storage(&storage);
+        }
+
+        let storage = SingleFileStorage::open(&path).unwrap();
+        assert_eq!(
+            storage.load_chunk(&Vec2(1, -2)).unwrap().unwrap(),
+            b"chunk again"
+        );
+        assert_eq!(storage.list_entities().unwrap(), vec!["b".to_owned()]);
+
+        storage.compact().unwrap();
+        assert_eq!(
+            storage.load_chunk(&Vec2(1, -2)).unwrap().unwrap(),
+            b"chunk again"
+        );
+        assert!(storage.load_entity("a").unwrap().is_none());
+    }
+
+    #[test]
+    fn saved_lights_go_stale_when_a_neighbor_changes() {
+        let config = WorldConfig::new().saving(true).build();
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+        let registry = Registry::new();
+
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunks = Chunks::new(&config, Some(storage.clone()));
+
+        for coords in [Vec2(0, 0), Vec2(1, 0)] {
+            let mut chunk = Chunk::new("test", coords.0, coords.1, &options);
+            chunk.lights.assign(vec![15; chunk.lights.len()]);
+            chunk.has_lights = true;
+            chunk.status = ChunkStatus::Ready;
+            chunks.map.insert(coords.clone(), chunk);
+            chunks.save(&coords, &registry);
+        }
+
+        // Unload the first chunk, then change its neighbor.
+        chunks.map.remove(&Vec2(0, 0));
+        chunks.save(&Vec2(1, 0), &registry);
+        chunks.save_stale_lights();
+
+        // Neighbors that were never saved are not marked.
+        let stale: Vec<[i32; 2]> =
+            serde_json::from_slice(&storage.load_metadata("stale-lights.json").unwrap().unwrap())
+                .unwrap();
+        assert_eq!(stale, vec![[0, 0]]);
+
+        // Stale markers survive a restart.
+        let chunks = Chunks::new(&config, Some(storage));
+
+        let stale = chunks.try_load(&Vec2(0, 0), &registry).unwrap();
+        assert!(!stale.has_lights);
+
+        let fresh = chunks.try_load(&Vec2(1, 0), &registry).unwrap();
+        assert!(fresh.has_lights);
+        assert!(fresh.lights.to_vec().iter().all(|&light| light == 15));
+    }
+
+    #[test]
+    fn record_checksum_catches_damage() {
+        let record = ChunkRecord {
+            id: "chunk".to_owned(),
+            voxels: vec![7; 16 * 16 * 16],
+            extras: vec![],
+            height_map: vec![3; 16 * 16],
+            lights: vec![],
+            palette: vec![],
+            states: vec![],
+            ticks: vec![],
+        };
+
+        let mut bytes = record.encode();
+        assert!(ChunkRecord::decode(&bytes).is_ok());
+
+        bytes[100] ^= 1;
+        let error = ChunkRecord::decode(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let bytes = record.encode();
+        let error = ChunkRecord::decode(&bytes[..bytes.len() - 9]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn entity_and_metadata_checksums_catch_damage() {
+        let folder = temp_save_dir("file-checksums");
+        let storage = StorageBackend::Directory.open(folder.to_str().unwrap());
+
+        storage
+            .save_entity("zombie", b"{\"etype\":\"zombie\"}")
+            .unwrap();
+        storage
+            .save_metadata("stats.json", b"{\"tick\":3}")
+            .unwrap();
+
+        // The files themselves stay plain JSON.
+        let stats: serde_json::Value =
+            serde_json::from_slice(&fs::read(folder.join("stats.json")).unwrap()).unwrap();
+        assert_eq!(stats["tick"], 3);
+
+        for path in [
+            folder.join("entities").join("zombie.json"),
+            folder.join("stats.json"),
+        ] {
+            let mut bytes = fs::read(&path).unwrap();
+            let last = bytes.len() - 1;
+            bytes[last] ^= 1;
+            fs::write(&path, bytes).unwrap();
+        }
+
+        let error = storage.load_entity("zombie").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = storage.load_metadata("stats.json").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // Files edited by hand are read once their checksum is removed.
+        fs::write(folder.join("stats.json"), b"{\"tick\":4}").unwrap();
+        fs::remove_file(folder.join("stats.json.crc")).unwrap();
+        assert_eq!(
+            storage.load_metadata("stats.json").unwrap().unwrap(),
+            b"{\"tick\":4}"
+        );
+        assert_eq!(storage.list_metadata().unwrap(), vec!["stats.json"]);
+
+        // A crash between writing the checksum and moving the data into place is finished on read.
+        storage
+            .save_metadata("stats.json", b"{\"tick\":5}")
+            .unwrap();
+        fs::rename(folder.join("stats.json"), folder.join("stats.json.tmp")).unwrap();
+        fs::write(folder.join("stats.json"), b"{\"tick\":4}").unwrap();
+        assert_eq!(
+            storage.load_metadata("stats.json").unwrap().unwrap(),
+            b"{\"tick\":5}"
+        );
+        assert!(!folder.join("stats.json.tmp").exists());
+    }
+
+    #[test]
+    fn corrupt_chunks_are_quarantined() {
+        let folder = temp_save_dir("quarantine");
+        let config = WorldConfig::new()
+            .saving(true)
+            .save_dir(folder.to_str().unwrap())
+            .build();
+        let storage = config.storage.open(&config.save_dir);
+        let registry = Registry::new();
+
+        storage.save_chunk(&Vec2(2, 3), b"not a chunk").unwrap();
+
+        let chunks = Chunks::new(&config, Some(storage.clone()));
+        assert!(chunks.try_load(&Vec2(2, 3), &registry).is_none());
+        assert!(!storage.has_chunk(&Vec2(2, 3)));
+
+        let mut quarantine = folder.clone();
+        quarantine.push("quarantine");
+        assert_eq!(fs::read_dir(&quarantine).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn legacy_chunk_files_are_migrated_or_kept() {
+        let folder = temp_save_dir("legacy-chunks");
+        let mut chunks = folder.clone();
+        chunks.push("chunks");
+        fs::create_dir_all(&chunks).unwrap();
+
+        let legacy = |name: &str, id: &str| {
+            let mut path = chunks.clone();
+            path.push(format!("{}.json", name));
+            fs::write(
+                &path,
+                format!(r#"{{"id":"{}","voxels":"","heightMap":""}}"#, id),
+            )
+            .unwrap();
+            path
+        };
+
+        let migrated = legacy(&ChunkUtils::get_chunk_name(1, 2), "first");
+        let broken = legacy(&ChunkUtils::get_chunk_name(3, 4), "broken");
+        fs::write(&broken, "{").unwrap();
+        let misnamed = legacy("notes", "misnamed");
+
+        let storage = StorageBackend::Directory.open(folder.to_str().unwrap());
+        let saved = storage.load_chunk(&Vec2(1, 2)).unwrap().unwrap();
+        assert!(!migrated.exists());
+        assert!(broken.exists() && misnamed.exists());
+        assert!(!storage.has_chunk(&Vec2(3, 4)));
+        drop(storage);
+
+        // A leftover legacy file never replaces a chunk that is already in a region file.
+        legacy(&ChunkUtils::get_chunk_name(1, 2), "second");
+        let storage = StorageBackend::Directory.open(folder.to_str().unwrap());
+        assert_eq!(storage.load_chunk(&Vec2(1, 2)).unwrap(), Some(saved));
+        assert!(!migrated.exists());
+    }
+
+    #[test]
+    fn block_ids_are_remapped_on_load() {
+        let config = WorldConfig::new()
+            .saving(true)
+            .fallback_block("Dirt")
+            .build();
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+
+        let mut old_registry = Registry::new();
+        old_registry.register_blocks(&[
+            Block::new("Dirt").id(1).build(),
+            Block::new("Stone").id(2).build(),
+            Block::new("Marble").id(3).build(),
+        ]);
+
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunk = Chunk::new("test", 0, 0, &options);
+        chunk.set_voxel(0, 0, 0, 1);
+        chunk.set_voxel(1, 0, 0, 2);
+        chunk.set_voxel(2, 0, 0, 3);
+        chunk.set_voxel_stage(2, 0, 0, 4);
+        chunk.status = ChunkStatus::Ready;
+
+        let mut chunks = Chunks::new(&config, Some(storage.clone()));
+        chunks.map.insert(Vec2(0, 0), chunk);
+        chunks.save(&Vec2(0, 0), &old_registry);
+
+        // Stone moved to a new ID, and marble was removed.
+        let mut new_registry = Registry::new();
+        new_registry.register_blocks(&[
+            Block::new("Dirt").id(1).build(),
+            Block::new("Stone").id(5).build(),
+        ]);
+
+        let chunks = Chunks::new(&config, Some(storage.clone()));
+        let chunk = chunks.try_load(&Vec2(0, 0), &new_registry).unwrap();
+
+        assert_eq!(chunk.get_voxel(0, 0, 0), 1);
+        assert_eq!(chunk.get_voxel(1, 0, 0), 5);
+        assert_eq!(chunk.get_voxel(2, 0, 0), 1);
+        assert_eq!(chunk.get_voxel_stage(2, 0, 0), 0);
+        assert_eq!(chunk.get_voxel(3, 0, 0), 0);
+
+        // A fallback block that is not registered falls back to air.
+        let config = WorldConfig::new()
+            .saving(true)
+            .fallback_block("Missing")
+            .build();
+        let chunks = Chunks::new(&config, Some(storage));
+        let chunk = chunks.try_load(&Vec2(0, 0), &new_registry).unwrap();
+
+        assert_eq!(chunk.get_voxel(1, 0, 0), 5);
+        assert_eq!(chunk.get_voxel(2, 0, 0), 0);
+    }
+
+    #[test]
+    fn block_entities_unload_with_their_chunk() {
+        let config = WorldConfig::new()
+            .saving(true)
+            .storage(StorageBackend::Memory)
+            .preload(false)
+            .unload_chunks_after(2)
+            .build();
+        let mut world = World::new("test", &config);
+        world.ecs_mut().insert(Registry::new());
+
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunk = Chunk::new("test", 0, 0, &options);
+        chunk.status = ChunkStatus::Ready;
+        world.chunks_mut().map.insert(Vec2(0, 0), chunk.clone());
+
+        let mut metadata = MetadataComp::new();
+        metadata.set("voxel", &VoxelComp::new(1, 2, 3));
+        world.revive_entity("chest", "block::chest", metadata);
+        world.ecs_mut().maintain();
+
+        for _ in 0..3 {
+            world.stats_mut().tick += 1;
+            ChunkUnloadingSystem.run_now(world.ecs());
+            world.ecs_mut().maintain();
+        }
+
+        assert!(world.chunks().raw(&Vec2(0, 0)).is_none());
+        assert!(world.chunks().block_entities.is_empty());
+        assert!(world
+            .storage()
+            .unwrap()
+            .load_entity("chest")
+            .unwrap()
+            .is_some());
+
+        world.chunks_mut().map.insert(Vec2(0, 0), chunk);
+        ChunkUnloadingSystem.run_now(world.ecs());
+        world.ecs_mut().maintain();
+
+        let entity = world.chunks().block_entities[&Vec3(1, 2, 3)];
+        assert_eq!(
+            world.read_component::<IDComp>().get(entity).unwrap().0,
+            "chest"
+        );
+    }
+
+    #[test]
+    fn unloaded_chunks_reload_from_storage() {
+        let config = WorldConfig::new().saving(true).build();
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+        let registry = Registry::new();
+
+        let options = ChunkOptions {
+            size: config.chunk_size,
+            max_height: config.max_height,
+            sub_chunks: config.sub_chunks,
+            paletted: false,
+            encoding: VoxelEncoding::Packed,
+        };
+
+        let mut chunk = Chunk::new("test", 0, 0, &options);
+        chunk.set_voxel(3, 4, 5, 6);
+        chunk.status = ChunkStatus::Ready;
+
+        let mut chunks = Chunks::new(&config, Some(storage));
+        chunks.map.insert(Vec2(0, 0), chunk);
+        chunks.add_chunk_to_save(&Vec2(0, 0), false);
+
+        // Scheduled ticks keep the number of ticks they had left.
+        chunks.ticks.take_due(10);
+        chunks.mark_voxel_active(&Vec3(3, 4, 5), 30);
+        chunks.save(&Vec2(0, 0), &registry);
+
+        assert!(chunks.unload(&Vec2(0, 0)).is_some());
+        assert!(chunks.raw(&Vec2(0, 0)).is_none());
+        assert!(chunks.unload(&Vec2(0, 0)).is_none());
+        assert!(chunks.ticks.is_empty());
+
+        chunks.ticks.take_due(100);
+
+        let chunk = chunks.try_load(&Vec2(0, 0), &registry).unwrap();
+        assert_eq!(chunk.get_voxel(3, 4, 5), 6);
+
+        chunks.add(chunk);
+        assert_eq!(chunks.ticks.get(&Vec3(3, 4, 5)), Some(120));
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use voxelize::{
+        Block, BlockConditionalPart, BlockDynamicPattern, BlockFaces, BlockRule, BlockRuleLogic,
+        Chunk, ChunkOptions, Registry, Vec3, VoxelAccess, VoxelEncoding,
+    };
+
+    #[test]
+    fn blocks_are_looked_up_by_tag() {
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Oak Log")
+                .id(1)
+                .tags(&["Logs", "flammable"])
+                .build(),
+            Block::new("Birch Log").id(70).tag("logs").build(),
+            Block::new("Coal Ore").id(3).tag("ores").build(),
+        ]);
+
+        assert!(registry.has_tag(1, "logs"));
+        assert!(registry.has_tag(70, "LOGS"));
+        assert!(!registry.has_tag(3, "logs"));
+        assert!(!registry.has_tag(1, "climbable"));
+
+        let logs = registry
+            .blocks_with_tag("logs")
+            .into_iter()
+            .map(|block| block.id)
+            .collect::<Vec<_>>();
+        assert_eq!(logs, [1, 70]);
+        assert_eq!(registry.tag("logs").unwrap().ids().count(), 2);
+        assert!(registry.blocks_with_tag("climbable").is_empty());
+    }
+
+    #[test]
+    fn tag_rules_match_and_resolve_to_ids() {
+        let top = BlockFaces::six_faces().scale_y(0.5).build().to_vec();
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Oak Log").id(1).tag("logs").build(),
+            Block::new("Birch Log").id(2).tag("logs").build(),
+            Block::new("Stone").id(3).build(),
+            Block::new("Moss")
+                .id(4)
+                .dynamic_patterns(&[BlockDynamicPattern {
+                    parts: vec![BlockConditionalPart {
+                        rule: BlockRule::Tag {
+                            offset: Vec3(0, -1, 0),
+                            tag: "logs".to_owned(),
+                        },
+                        faces: top.clone(),
+                        aabbs: vec![],
+                        is_transparent: [false; 6],
+                    }],
+                }])
+                .build(),
+        ]);
+
+        let mut chunk = Chunk::new(
+            "test",
+            0,
+            0,
+            &ChunkOptions {
+                size: 16,
+                max_height: 16,
+                sub_chunks: 1,
+                paletted: false,
+                encoding: VoxelEncoding::Packed,
+            },
+        );
+        chunk.set_voxel(1, 1, 1, 2);
+        chunk.set_voxel(3, 1, 3, 3);
+
+        let moss = registry.get_block_by_id(4).to_owned();
+        assert_eq!(moss.get_faces(&Vec3(1, 2, 1), &chunk, &registry), top);
+        assert_ne!(moss.get_faces(&Vec3(3, 2, 3), &chunk, &registry), top);
+
+        // Clients only know about IDs, so the rule is sent as the IDs with the tag.
+        registry.generate();
+
+        let moss = registry.get_block_by_name("Moss");
+        match &moss.dynamic_patterns.as_ref().unwrap()[0].parts[0].rule {
+            BlockRule::Combination {
+                logic: BlockRuleLogic::Or,
+                rules,
+            } => assert_eq!(rules.len(), 2),
+            _ => panic!("Tag rule was not resolved."),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use voxelize::{ScheduledTicks, Vec2, Vec3};
+
+    #[test]
+    fn scheduled_ticks_are_ordered_and_deduplicated() {
+        let mut ticks = ScheduledTicks::new(16);
+
+        ticks.schedule(&Vec3(0, 0, 0), 5);
+        ticks.schedule(&Vec3(1, 0, 0), 3);
+        ticks.schedule(&Vec3(20, 0, 0), 4);
+
+        // Scheduling a voxel again moves its tick instead of adding another.
+        ticks.schedule(&Vec3(0, 0, 0), 8);
+        assert_eq!(ticks.len(), 3);
+
+        assert!(ticks.take_due(2).is_empty());
+        assert_eq!(ticks.take_due(5), vec![Vec3(1, 0, 0), Vec3(20, 0, 0)]);
+        assert_eq!(ticks.take_due(7), vec![]);
+
+        ticks.schedule(&Vec3(2, 0, 0), 9);
+        assert_eq!(ticks.cancel(&Vec3(2, 0, 0)), Some(9));
+
+        assert_eq!(ticks.chunk_ticks(&Vec2(0, 0)), vec![(Vec3(0, 0, 0), 1)]);
+        assert!(!ticks.has_chunk(&Vec2(1, 0)));
+
+        let removed = ticks.remove_chunk(&Vec2(0, 0));
+        assert!(ticks.is_empty());
+        assert!(ticks.take_due(100).is_empty());
+
+        ticks.restore(&removed);
+        assert_eq!(ticks.get(&Vec3(0, 0, 0)), Some(101));
+
+        // However often a voxel is moved, it only ticks once.
+        for at in 0..10_000 {
+            ticks.schedule(&Vec3(0, 0, 0), 200 + at);
+        }
+        assert_eq!(ticks.take_due(20_000), vec![Vec3(0, 0, 0)]);
+    }
+}
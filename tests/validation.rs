@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+    use voxelize::{Block, ClientFlag, IDComp, PositionComp, Registry, Vec3, World, WorldConfig};
+
+    #[test]
+    fn client_updates_are_validated() {
+        let config = WorldConfig::new()
+            .max_update_reach(5.0)
+            .max_updates_per_second(4)
+            .allowed_update_blocks(&[0, 1])
+            .build();
+        let mut world = World::new("test", &config);
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[Block::new("Stone").id(1).build()]);
+        world.ecs_mut().insert(registry);
+
+        world
+            .ecs_mut()
+            .create_entity()
+            .with(ClientFlag::default())
+            .with(IDComp::new("player"))
+            .with(PositionComp::new(0.5, 10.5, 0.5))
+            .build();
+
+        world
+            .set_update_validator(|_, client_id, (voxel, _)| client_id == "player" && voxel.0 >= 0);
+
+        assert!(world.validate_update("player", &(Vec3(0, 8, 0), 1)));
+        assert!(!world.validate_update("player", &(Vec3(0, 8, 0), 2)));
+        assert!(!world.validate_update("player", &(Vec3(0, 0, 0), 1)));
+        assert!(!world.validate_update("player", &(Vec3(-1, 10, 0), 0)));
+
+        // Only the accepted updates count towards the rate limit.
+        for _ in 0..3 {
+            assert!(world.validate_update("player", &(Vec3(0, 8, 0), 1)));
+        }
+        assert!(!world.validate_update("player", &(Vec3(0, 8, 0), 1)));
+
+        // Clients that are nowhere cannot reach anything.
+        assert!(!world.validate_update("ghost", &(Vec3(0, 8, 0), 1)));
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use voxelize::{
+        Block, BlockUtils, Chunk, ChunkOptions, ChunkRecord, ChunkStatus, Chunks, MemoryStorage,
+        Registry, Vec2, VoxelAccess, VoxelEncoding, WorldConfig, WorldStorage,
+    };
+
+    fn chunk(encoding: VoxelEncoding) -> Chunk {
+        Chunk::new(
+            "test",
+            0,
+            0,
+            &ChunkOptions {
+                size: 16,
+                max_height: 16,
+                sub_chunks: 1,
+                paletted: false,
+                encoding,
+            },
+        )
+    }
+
+    #[test]
+    fn wide_ids_and_stages_round_trip() {
+        let mut voxel = BlockUtils::insert_wide_id(0, 70000);
+        voxel = BlockUtils::insert_wide_stage(voxel, 300);
+        voxel = BlockUtils::insert_wide_state(voxel, 200);
+
+        assert_eq!(BlockUtils::extract_id(voxel), 70000);
+        assert_eq!(BlockUtils::extract_stage(voxel), 300);
+        assert_eq!(BlockUtils::extract_state(voxel), 200);
+
+        let (lower, upper) = BlockUtils::split(voxel);
+        assert_eq!(BlockUtils::join(lower, upper), voxel);
+
+        // The lower half reads the same as a packed voxel.
+        assert_eq!(BlockUtils::extract_id(lower as u64), 70000 & 0xFFFF);
+        assert_eq!(BlockUtils::extract_stage(lower as u64), 300 & 0xF);
+        assert_eq!(BlockUtils::extract_state(lower as u64), 200 & 0xF);
+    }
+
+    #[test]
+    fn wide_chunks_keep_the_upper_bits() {
+        let mut chunk = chunk(VoxelEncoding::Wide);
+
+        chunk.set_voxel(1, 2, 3, 70000);
+        chunk.set_voxel_stage(1, 2, 3, 300);
+
+        assert_eq!(chunk.get_voxel(1, 2, 3), 70000);
+        assert_eq!(chunk.get_voxel_stage(1, 2, 3), 300);
+        assert!(chunk.to_model(false, true, 0..1).extras.is_some());
+
+        let raw = chunk.raw_voxels();
+        let mut copy = self::chunk(VoxelEncoding::Wide);
+        copy.assign_raw_voxels(&raw);
+        assert_eq!(copy.get_voxel(1, 2, 3), 70000);
+
+        // Packed chunks drop the upper half of assigned voxels.
+        let mut packed = self::chunk(VoxelEncoding::Packed);
+        packed.assign_raw_voxels(&raw);
+        assert_eq!(packed.get_voxel(1, 2, 3), 70000 & 0xFFFF);
+        assert!(packed.to_model(false, true, 0..1).extras.is_none());
+    }
+
+    #[test]
+    fn packed_chunks_truncate_ids_and_reject_stages() {
+        let mut chunk = chunk(VoxelEncoding::Packed);
+
+        chunk.set_voxel(0, 0, 0, 70000);
+        assert_eq!(chunk.get_voxel(0, 0, 0), 70000 & 0xFFFF);
+
+        let result = std::panic::catch_unwind(move || chunk.set_voxel_stage(0, 0, 0, 16));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn packed_chunks_reject_wide_voxels() {
+        chunk(VoxelEncoding::Packed).set_raw_voxel(0, 0, 0, BlockUtils::insert_wide_id(0, 70000));
+    }
+
+    #[test]
+    fn records_keep_their_extras() {
+        let record = ChunkRecord {
+            id: "wide".to_owned(),
+            voxels: vec![1; 16],
+            extras: vec![2; 16],
+            height_map: vec![],
+            lights: vec![],
+            palette: vec![],
+            states: vec![],
+            ticks: vec![],
+        };
+
+        let decoded = ChunkRecord::decode(&record.encode()).unwrap();
+        assert_eq!(decoded.extras, vec![2; 16]);
+    }
+
+    #[test]
+    fn wide_chunks_are_saved_and_loaded() {
+        let config = WorldConfig::new()
+            .saving(true)
+            .voxel_encoding(VoxelEncoding::Wide)
+            .build();
+        let storage: Arc<dyn WorldStorage> = Arc::new(MemoryStorage::new());
+
+        let mut registry = Registry::new();
+        registry.register_blocks(&[
+            Block::new("Stone").id(1).build(),
+            Block::new("Crystal").id(70000).build(),
+        ]);
+
+        let mut chunk = Chunk::new(
+            "test",
+            0,
+            0,
+            &ChunkOptions {
+                size: config.chunk_size,
+                max_height: config.max_height,
+                sub_chunks: config.sub_chunks,
+                paletted: false,
+                encoding: config.voxel_encoding,
+            },
+        );
+        chunk.set_voxel(0, 0, 0, 1);
+        chunk.set_voxel(1, 0, 0, 70000);
+        chunk.set_voxel_stage(1, 0, 0, 300);
+        chunk.status = ChunkStatus::Ready;
+
+        let mut chunks = Chunks::new(&config, Some(storage.clone()));
+        chunks.map.insert(Vec2(0, 0), chunk);
+        chunks.save(&Vec2(0, 0), &registry);
+
+        let chunks = Chunks::new(&config, Some(storage.clone()));
+        let chunk = chunks.try_load(&Vec2(0, 0), &registry).unwrap();
+
+        assert_eq!(chunk.get_voxel(0, 0, 0), 1);
+        assert_eq!(chunk.get_voxel(1, 0, 0), 70000);
+        assert_eq!(chunk.get_voxel_stage(1, 0, 0), 300);
+
+        // A packed world can't hold the chunk, so it is quarantined instead of truncated.
+        let packed = WorldConfig::new().saving(true).build();
+        let chunks = Chunks::new(&packed, Some(storage.clone()));
+        assert!(chunks.try_load(&Vec2(0, 0), &registry).is_none());
+        assert!(!storage.has_chunk(&Vec2(0, 0)));
+    }
+}